@@ -169,7 +169,7 @@ async fn index_handler(
         lang,
         name: query.name,
     };
-    Ok(Html::new(template.render()?))
+    Ok(template)
 }
 
 #[derive(Debug, Deserialize)]
@@ -202,5 +202,5 @@ async fn greeting_handler(
         lang,
         name: query.name,
     };
-    Ok(Html::new(template.render()?))
+    Ok(template)
 }
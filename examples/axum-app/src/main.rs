@@ -141,7 +141,7 @@ async fn index_handler(
         lang,
         name: query.name,
     };
-    Ok(Html(template.render()?))
+    Ok(template)
 }
 
 #[derive(Debug, Deserialize)]
@@ -169,5 +169,5 @@ async fn greeting_handler(
         lang,
         name: query.name,
     };
-    Ok(Html(template.render()?))
+    Ok(template)
 }
@@ -135,7 +135,7 @@ async fn index_handler(
         lang,
         name: query.name,
     };
-    Ok(Html(template.render()?))
+    Ok(template)
 }
 
 #[derive(Debug, Deserialize)]
@@ -164,5 +164,5 @@ async fn greeting_handler(
         lang,
         name: query.name,
     };
-    Ok(Html(template.render()?))
+    Ok(template)
 }
@@ -138,7 +138,7 @@ fn index_handler(lang: Lang, name: Option<&str>) -> Result<impl Responder<'_, 's
         lang,
         name: name.unwrap_or_default(),
     };
-    Ok(RawHtml(template.render()?))
+    Ok(template)
 }
 
 /// This is the final page of this example application.
@@ -156,5 +156,5 @@ fn greeting_handler<'r>(lang: Lang, name: &str) -> Result<impl Responder<'r, 'st
     }
 
     let template = Tmpl { lang, name };
-    Ok(RawHtml(template.render()?))
+    Ok(template)
 }
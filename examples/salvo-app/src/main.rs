@@ -162,7 +162,7 @@ async fn index_handler(req: &mut Request) -> Result<impl Scribe, AppError> {
     }
 
     let template = Tmpl { lang, name };
-    Ok(Text::Html(template.render()?))
+    Ok(template)
 }
 
 /// This is the final page of this example application.
@@ -195,5 +195,5 @@ async fn greeting_handler(req: &mut Request) -> Result<impl Scribe, AppError> {
     }
 
     let template = Tmpl { lang, name };
-    Ok(Text::Html(template.render()?))
+    Ok(template)
 }
@@ -122,7 +122,7 @@ fn index_handler(lang: Lang, query: IndexHandlerQuery) -> Result<impl Reply, App
         lang,
         name: query.name,
     };
-    Ok(html(template.render()?))
+    Ok(template)
 }
 
 #[derive(Debug, Deserialize)]
@@ -147,5 +147,5 @@ fn greeting_handler(lang: Lang, query: GreetingHandlerQuery) -> Result<impl Repl
         lang,
         name: query.name,
     };
-    Ok(html(template.render()?))
+    Ok(template)
 }
@@ -1,11 +1,32 @@
-use criterion::{Criterion, black_box, criterion_group, criterion_main};
-use rinja::filters::{Html, escape};
+use criterion::{Criterion, Throughput, black_box, criterion_group, criterion_main};
+use rinja::filters::{FastWritable, Html, escape};
 
 criterion_main!(benches);
 criterion_group!(benches, functions);
 
 fn functions(c: &mut Criterion) {
-    c.bench_function("Escaping", escaping);
+    let mut group = c.benchmark_group("Escaping");
+    group.bench_function("scalar", escaping_scalar);
+    group.bench_function("fmt", escaping);
+    group.finish();
+
+    let text = long_text();
+    let mut group = c.benchmark_group("Escaping long text");
+    group.throughput(Throughput::Bytes(text.len() as u64));
+    group.bench_function("scalar", |b| escaping_long_text_scalar(b, &text));
+    group.bench_function("fmt", |b| escaping_long_text(b, &text));
+    group.bench_function("bytes", |b| escaping_long_text_into_bytes(b, &text));
+    group.finish();
+}
+
+fn escaping_scalar(b: &mut criterion::Bencher<'_>) {
+    b.iter(|| {
+        for &s in black_box(STRINGS) {
+            let mut buf = String::new();
+            escape_scalar(&mut buf, s);
+            black_box(buf);
+        }
+    });
 }
 
 fn escaping(b: &mut criterion::Bencher<'_>) {
@@ -16,4 +37,66 @@ fn escaping(b: &mut criterion::Bencher<'_>) {
     });
 }
 
+fn escaping_long_text_scalar(b: &mut criterion::Bencher<'_>, text: &str) {
+    let mut buf = String::with_capacity(2 * text.len());
+    b.iter(|| {
+        buf.clear();
+        escape_scalar(&mut buf, black_box(text));
+        black_box(&buf);
+    });
+}
+
+fn escaping_long_text(b: &mut criterion::Bencher<'_>, text: &str) {
+    let mut buf = String::with_capacity(2 * text.len());
+    b.iter(|| {
+        buf.clear();
+        escape(black_box(text), Html)
+            .unwrap()
+            .write_into(&mut buf)
+            .unwrap();
+        black_box(&buf);
+    });
+}
+
+fn escaping_long_text_into_bytes(b: &mut criterion::Bencher<'_>, text: &str) {
+    // a fixed-size slice works without the feature "alloc"; every byte escapes to at most 5 bytes
+    let mut buf = vec![0; 5 * text.len()];
+    b.iter(|| {
+        escape(black_box(text), Html)
+            .unwrap()
+            .write_bytes_into(&mut &mut buf[..])
+            .unwrap();
+        black_box(&buf);
+    });
+}
+
+/// The byte-by-byte loop that was used before the vectorized scan, as a baseline
+fn escape_scalar(dest: &mut String, src: &str) {
+    let mut last = 0;
+    for (index, byte) in src.bytes().enumerate() {
+        let escaped = match byte {
+            b'"' => "&#34;",
+            b'&' => "&#38;",
+            b'\'' => "&#39;",
+            b'<' => "&#60;",
+            b'>' => "&#62;",
+            _ => continue,
+        };
+        dest.push_str(&src[last..index]);
+        dest.push_str(escaped);
+        last = index + 1;
+    }
+    dest.push_str(&src[last..]);
+}
+
+/// Mostly plain text with a few characters that need escaping, like user generated content
+fn long_text() -> String {
+    let mut text = String::new();
+    for &s in STRINGS.iter().cycle().take(1000) {
+        text.push_str(s);
+        text.push_str(" — Lorem ipsum dolor sit amet, consectetur adipiscing elit. ");
+    }
+    text
+}
+
 const STRINGS: &[&str] = include!("strings.inc");
@@ -29,6 +29,11 @@ pub enum Error {
     /// JSON conversion error
     #[cfg(feature = "serde_json")]
     Json(serde_json::Error),
+    /// An error that happened at a known location in a template
+    ///
+    /// Only returned if `runtime_error_locations` is enabled in the configuration.
+    #[cfg(feature = "alloc")]
+    Located(Box<LocatedError>),
 }
 
 impl Error {
@@ -39,6 +44,44 @@ impl Error {
         Self::Custom(err.into())
     }
 
+    /// Attach the location in a template where this [`Error`] happened
+    ///
+    /// If the error already has a location, it is kept, because the innermost location is the
+    /// most precise one, e.g. if a template renders another template.
+    #[cold]
+    #[cfg(feature = "alloc")]
+    pub fn with_location(self, path: &'static str, row: usize, column: usize) -> Self {
+        match self {
+            Error::Located(_) => self,
+            error => Error::Located(Box::new(LocatedError {
+                error,
+                path,
+                row,
+                column,
+            })),
+        }
+    }
+
+    /// The location in a template where this [`Error`] happened, if known
+    #[inline]
+    #[cfg(feature = "alloc")]
+    pub fn location(&self) -> Option<&LocatedError> {
+        match self {
+            Error::Located(err) => Some(err),
+            _ => None,
+        }
+    }
+
+    /// Strip the location information from this [`Error`], if any
+    #[inline]
+    pub fn without_location(self) -> Self {
+        match self {
+            #[cfg(feature = "alloc")]
+            Error::Located(err) => err.error,
+            error => error,
+        }
+    }
+
     /// Convert this [`Error`] into a
     /// <code>[Box]&lt;dyn [StdError] + [Send] + [Sync]&gt;</code>
     #[cfg(feature = "alloc")]
@@ -50,6 +93,7 @@ impl Error {
             Error::Custom(err) => err,
             #[cfg(feature = "serde_json")]
             Error::Json(err) => err.into(),
+            err @ Error::Located(_) => Box::new(err),
         }
     }
 
@@ -78,6 +122,8 @@ impl StdError for Error {
             Error::Custom(err) => Some(err.as_ref()),
             #[cfg(feature = "serde_json")]
             Error::Json(err) => Some(err),
+            #[cfg(feature = "alloc")]
+            Error::Located(err) => err.error.source(),
         }
     }
 }
@@ -92,10 +138,62 @@ impl fmt::Display for Error {
             Error::Custom(err) => err.fmt(f),
             #[cfg(feature = "serde_json")]
             Error::Json(err) => err.fmt(f),
+            #[cfg(feature = "alloc")]
+            Error::Located(err) => err.fmt(f),
         }
     }
 }
 
+/// An [`Error`] together with the location in the template where it happened
+///
+/// See [`Error::Located`].
+#[cfg(feature = "alloc")]
+#[derive(Debug)]
+pub struct LocatedError {
+    error: Error,
+    path: &'static str,
+    row: usize,
+    column: usize,
+}
+
+#[cfg(feature = "alloc")]
+impl LocatedError {
+    /// The error that happened
+    #[inline]
+    pub fn error(&self) -> &Error {
+        &self.error
+    }
+
+    /// The path of the template, relative to the crate root
+    #[inline]
+    pub fn path(&self) -> &'static str {
+        self.path
+    }
+
+    /// The line in the template, starting at 1
+    #[inline]
+    pub fn row(&self) -> usize {
+        self.row
+    }
+
+    /// The column in the line, starting at 1
+    #[inline]
+    pub fn column(&self) -> usize {
+        self.column
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl fmt::Display for LocatedError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}:{}:{}: {}",
+            self.path, self.row, self.column, self.error
+        )
+    }
+}
+
 impl From<Error> for fmt::Error {
     #[inline]
     fn from(_: Error) -> Self {
@@ -252,8 +350,8 @@ impl From<Infallible> for Error {
 
 #[cfg(test)]
 const _: () = {
-    trait AssertSendSyncStatic: Send + Sync + 'static {}
-    impl AssertSendSyncStatic for Error {}
+    const fn assert_send_sync_static<T: Send + Sync + 'static>() {}
+    assert_send_sync_static::<Error>();
 };
 
 /// Helper trait to convert a custom `?` call into a [`crate::Result`]
@@ -5,6 +5,7 @@ use core::pin::Pin;
 use core::str;
 
 use crate::Values;
+use crate::helpers::{BytesSink, Sink};
 
 /// Marks a string (or other `Display` type) as safe
 ///
@@ -85,6 +86,11 @@ impl<T: FastWritable, E: Escaper> FastWritable for EscapeDisplay<T, E> {
     fn write_into<W: fmt::Write + ?Sized>(&self, dest: &mut W) -> crate::Result<()> {
         self.0.write_into(&mut EscapeWriter(dest, self.1))
     }
+
+    #[inline]
+    fn write_bytes_into<B: ByteSink + ?Sized>(&self, dest: &mut B) -> crate::Result<()> {
+        self.0.write_into(&mut EscapeBytesWriter(dest, self.1))
+    }
 }
 
 struct EscapeWriter<W, E>(W, E);
@@ -101,6 +107,15 @@ impl<W: Write, E: Escaper> Write for EscapeWriter<W, E> {
     }
 }
 
+struct EscapeBytesWriter<'a, B: ?Sized, E>(&'a mut B, E);
+
+impl<B: ByteSink + ?Sized, E: Escaper> Write for EscapeBytesWriter<'_, B, E> {
+    #[inline]
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.1.write_escaped_bytes(self.0, s)
+    }
+}
+
 /// Alias for [`escape()`]
 ///
 /// ```
@@ -146,6 +161,11 @@ impl Escaper for Html {
     fn write_escaped_char<W: Write>(&self, dest: W, c: char) -> fmt::Result {
         crate::html::write_escaped_char(dest, c)
     }
+
+    #[inline]
+    fn write_escaped_bytes<B: ByteSink + ?Sized>(&self, dest: &mut B, string: &str) -> fmt::Result {
+        crate::html::write_escaped_bytes(|bytes| dest.write_bytes(bytes), string)
+    }
 }
 
 /// Don't escape the input but return in verbatim
@@ -162,6 +182,11 @@ impl Escaper for Text {
     fn write_escaped_char<W: Write>(&self, mut dest: W, c: char) -> fmt::Result {
         dest.write_char(c)
     }
+
+    #[inline]
+    fn write_escaped_bytes<B: ByteSink + ?Sized>(&self, dest: &mut B, string: &str) -> fmt::Result {
+        dest.write_bytes(string.as_bytes())
+    }
 }
 
 /// Escapers are used to make generated text safe for printing in some context.
@@ -177,6 +202,12 @@ pub trait Escaper: Copy {
     fn write_escaped_char<W: Write>(&self, dest: W, c: char) -> fmt::Result {
         self.write_escaped_str(dest, c.encode_utf8(&mut [0; 4]))
     }
+
+    /// Escaped the input string `string` into the byte buffer `dest`
+    #[inline]
+    fn write_escaped_bytes<B: ByteSink + ?Sized>(&self, dest: &mut B, string: &str) -> fmt::Result {
+        self.write_escaped_str(BytesSink(dest), string)
+    }
 }
 
 /// Used internally by rinja to select the appropriate escaper
@@ -310,6 +341,15 @@ const _: () = {
             };
             inner.write_into(dest)
         }
+
+        #[inline]
+        fn write_bytes_into<B: ByteSink + ?Sized>(&self, dest: &mut B) -> crate::Result<()> {
+            let inner = match self {
+                MaybeSafe::Safe(inner) => inner,
+                MaybeSafe::NeedsEscaping(inner) => inner,
+            };
+            inner.write_bytes_into(dest)
+        }
     }
 
     macro_rules! add_ref {
@@ -344,6 +384,13 @@ const _: () = {
                 Wrapped::NeedsEscaping(t, e) => EscapeDisplay(t, e).write_into(dest),
             }
         }
+
+        fn write_bytes_into<B: ByteSink + ?Sized>(&self, dest: &mut B) -> crate::Result<()> {
+            match *self {
+                Wrapped::Safe(t) => t.write_bytes_into(dest),
+                Wrapped::NeedsEscaping(t, e) => EscapeDisplay(t, e).write_bytes_into(dest),
+            }
+        }
     }
 
     impl<T: fmt::Display + ?Sized, E: Escaper> fmt::Display for Wrapped<'_, T, E> {
@@ -412,6 +459,11 @@ const _: () = {
         fn write_into<W: fmt::Write + ?Sized>(&self, dest: &mut W) -> crate::Result<()> {
             self.0.write_into(dest)
         }
+
+        #[inline]
+        fn write_bytes_into<B: ByteSink + ?Sized>(&self, dest: &mut B) -> crate::Result<()> {
+            self.0.write_bytes_into(dest)
+        }
     }
 
     macro_rules! add_ref {
@@ -470,6 +522,7 @@ mark_html_safe! {
 
 impl<T: HtmlSafe> HtmlSafe for core::num::Wrapping<T> {}
 impl<T: fmt::Display> HtmlSafe for HtmlSafeOutput<T> {}
+impl<L: HtmlSafe, R: HtmlSafe> HtmlSafe for crate::helpers::Either<L, R> {}
 
 #[cfg(feature = "alloc")]
 impl<T> HtmlSafe for alloc::borrow::Cow<'_, T>
@@ -491,11 +544,8 @@ pub struct Writable<'a, S: ?Sized>(pub &'a S);
 /// Used internally by rinja to select the appropriate [`write!()`] mechanism
 pub trait WriteWritable {
     /// Used internally by rinja to select the appropriate [`write!()`] mechanism
-    fn rinja_write<W: fmt::Write + ?Sized>(
-        &self,
-        dest: &mut W,
-        values: &dyn Values,
-    ) -> crate::Result<()>;
+    fn rinja_write<W: Sink + ?Sized>(&self, dest: &mut W, values: &dyn Values)
+    -> crate::Result<()>;
 }
 
 /// Used internally by rinja to speed up writing some types.
@@ -504,6 +554,57 @@ pub trait WriteWritable {
 pub trait FastWritable {
     /// Used internally by rinja to speed up writing some types.
     fn write_into<W: fmt::Write + ?Sized>(&self, dest: &mut W) -> crate::Result<()>;
+
+    /// Used internally by rinja to write some types into a byte buffer.
+    ///
+    /// The default implementation goes through [`FastWritable::write_into()`].
+    #[inline]
+    fn write_bytes_into<B: ByteSink + ?Sized>(&self, dest: &mut B) -> crate::Result<()> {
+        self.write_into(&mut BytesSink(dest))
+    }
+}
+
+/// A byte buffer that templates can be rendered into without going through [`fmt::Write`]
+///
+/// It is implemented for `&mut [u8]`, for [`Vec<u8>`][alloc::vec::Vec], and if the feature
+/// `"bytes"` is enabled, for [`bytes::BytesMut`](https://docs.rs/bytes/1/bytes/struct.BytesMut.html).
+pub trait ByteSink {
+    /// Appends `bytes` to the buffer, or fails if they don't fit into it
+    fn write_bytes(&mut self, bytes: &[u8]) -> fmt::Result;
+}
+
+/// Fills the slice from the front and advances it past the written bytes, like
+/// [`std::io::Write`](https://doc.rust-lang.org/stable/std/io/trait.Write.html#impl-Write-for-%26mut+%5Bu8%5D),
+/// but fails instead of writing only a part of `bytes`.
+impl ByteSink for &mut [u8] {
+    #[inline]
+    fn write_bytes(&mut self, bytes: &[u8]) -> fmt::Result {
+        if bytes.len() > self.len() {
+            return Err(fmt::Error);
+        }
+        let (head, tail) = core::mem::take(self).split_at_mut(bytes.len());
+        head.copy_from_slice(bytes);
+        *self = tail;
+        Ok(())
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl ByteSink for alloc::vec::Vec<u8> {
+    #[inline]
+    fn write_bytes(&mut self, bytes: &[u8]) -> fmt::Result {
+        self.extend_from_slice(bytes);
+        Ok(())
+    }
+}
+
+#[cfg(feature = "bytes")]
+impl ByteSink for bytes::BytesMut {
+    #[inline]
+    fn write_bytes(&mut self, bytes: &[u8]) -> fmt::Result {
+        self.extend_from_slice(bytes);
+        Ok(())
+    }
 }
 
 const _: () = {
@@ -513,6 +614,11 @@ const _: () = {
             fn write_into<W: fmt::Write + ?Sized>(&self, dest: &mut W) -> crate::Result<()> {
                 <T>::write_into(self, dest)
             }
+
+            #[inline]
+            fn write_bytes_into<B: ByteSink + ?Sized>(&self, dest: &mut B) -> crate::Result<()> {
+                <T>::write_bytes_into(self, dest)
+            }
         }
     }
 
@@ -525,6 +631,11 @@ const _: () = {
         fn write_into<W: fmt::Write + ?Sized>(&self, dest: &mut W) -> crate::Result<()> {
             self.as_ref().get_ref().write_into(dest)
         }
+
+        #[inline]
+        fn write_bytes_into<B: ByteSink + ?Sized>(&self, dest: &mut B) -> crate::Result<()> {
+            self.as_ref().get_ref().write_bytes_into(dest)
+        }
     }
 
     #[cfg(feature = "alloc")]
@@ -533,6 +644,11 @@ const _: () = {
         fn write_into<W: fmt::Write + ?Sized>(&self, dest: &mut W) -> crate::Result<()> {
             T::write_into(self.as_ref(), dest)
         }
+
+        #[inline]
+        fn write_bytes_into<B: ByteSink + ?Sized>(&self, dest: &mut B) -> crate::Result<()> {
+            T::write_bytes_into(self.as_ref(), dest)
+        }
     }
 
     // implement FastWritable for a list of types
@@ -543,6 +659,14 @@ const _: () = {
                 fn write_into<W: fmt::Write + ?Sized>(&self, dest: &mut W) -> crate::Result<()> {
                     itoa::Buffer::new().format(*self).write_into(dest)
                 }
+
+                #[inline]
+                fn write_bytes_into<B: ByteSink + ?Sized>(
+                    &self,
+                    dest: &mut B,
+                ) -> crate::Result<()> {
+                    Ok(dest.write_bytes(itoa::Buffer::new().format(*self).as_bytes())?)
+                }
             }
         )* };
     }
@@ -560,6 +684,14 @@ const _: () = {
                 fn write_into<W: fmt::Write + ?Sized>(&self, dest: &mut W) -> crate::Result<()> {
                     self.get().write_into(dest)
                 }
+
+                #[inline]
+                fn write_bytes_into<B: ByteSink + ?Sized>(
+                    &self,
+                    dest: &mut B,
+                ) -> crate::Result<()> {
+                    self.get().write_bytes_into(dest)
+                }
             }
         )* };
     }
@@ -574,6 +706,11 @@ const _: () = {
         fn write_into<W: fmt::Write + ?Sized>(&self, dest: &mut W) -> crate::Result<()> {
             Ok(dest.write_str(self)?)
         }
+
+        #[inline]
+        fn write_bytes_into<B: ByteSink + ?Sized>(&self, dest: &mut B) -> crate::Result<()> {
+            Ok(dest.write_bytes(self.as_bytes())?)
+        }
     }
 
     #[cfg(feature = "alloc")]
@@ -582,6 +719,11 @@ const _: () = {
         fn write_into<W: fmt::Write + ?Sized>(&self, dest: &mut W) -> crate::Result<()> {
             self.as_str().write_into(dest)
         }
+
+        #[inline]
+        fn write_bytes_into<B: ByteSink + ?Sized>(&self, dest: &mut B) -> crate::Result<()> {
+            self.as_str().write_bytes_into(dest)
+        }
     }
 
     impl FastWritable for bool {
@@ -612,33 +754,25 @@ const _: () = {
 
     impl<S: crate::Template + ?Sized> WriteWritable for &Writable<'_, S> {
         #[inline]
-        fn rinja_write<W: fmt::Write + ?Sized>(
+        fn rinja_write<W: Sink + ?Sized>(
             &self,
             dest: &mut W,
             values: &dyn Values,
         ) -> crate::Result<()> {
-            self.0.render_into_with_values(dest, values)
+            self.0.render_into_sink(dest, values)
         }
     }
 
     impl<S: FastWritable + ?Sized> WriteWritable for &&Writable<'_, S> {
         #[inline]
-        fn rinja_write<W: fmt::Write + ?Sized>(
-            &self,
-            dest: &mut W,
-            _: &dyn Values,
-        ) -> crate::Result<()> {
-            self.0.write_into(dest)
+        fn rinja_write<W: Sink + ?Sized>(&self, dest: &mut W, _: &dyn Values) -> crate::Result<()> {
+            dest.write_fast(self.0)
         }
     }
 
     impl<S: fmt::Display + ?Sized> WriteWritable for &&&Writable<'_, S> {
         #[inline]
-        fn rinja_write<W: fmt::Write + ?Sized>(
-            &self,
-            dest: &mut W,
-            _: &dyn Values,
-        ) -> crate::Result<()> {
+        fn rinja_write<W: Sink + ?Sized>(&self, dest: &mut W, _: &dyn Values) -> crate::Result<()> {
             Ok(write!(dest, "{}", self.0)?)
         }
     }
@@ -12,8 +12,27 @@ use core::iter::{Enumerate, Peekable};
 use core::ops::Deref;
 use core::pin::Pin;
 
+#[cfg(feature = "with-actix-web")]
+pub mod actix_web;
+#[cfg(feature = "with-axum")]
+pub mod axum;
+mod contains;
+mod map;
+#[cfg(feature = "with-poem")]
+pub mod poem;
+#[cfg(feature = "with-rocket")]
+pub mod rocket;
+#[cfg(feature = "with-salvo")]
+pub mod salvo;
+#[cfg(feature = "with-warp")]
+pub mod warp;
+
+pub use self::contains::{Contains, In, InContains, StrPattern};
+#[cfg(feature = "serde_json")]
+pub use self::map::SerializeEntries;
+pub use self::map::{DisplayEntries, Map, MapEntries, MapEntry, MapIter};
 pub use crate::error::{ErrorMarker, ResultConverter};
-use crate::filters::FastWritable;
+use crate::filters::{ByteSink, FastWritable};
 pub use crate::values::get_value;
 
 pub struct TemplateLoop<I>
@@ -102,6 +121,23 @@ where
     }
 }
 
+/// Convert `err` into an [`Error`](crate::Error), and attach its location in the template
+#[cold]
+pub fn located_error<E: Into<crate::Error>>(
+    err: E,
+    path: &'static str,
+    row: usize,
+    column: usize,
+) -> crate::Error {
+    #[cfg(feature = "alloc")]
+    return err.into().with_location(path, row, column);
+    #[cfg(not(feature = "alloc"))]
+    {
+        let _ = (path, row, column);
+        err.into()
+    }
+}
+
 #[inline]
 pub fn get_primitive_value<T: PrimitiveType>(value: T) -> T::Value {
     value.get()
@@ -250,6 +286,69 @@ impl FastWritable for Empty {
     }
 }
 
+/// The value of an inline `then if cond` expression without `else` branch: nothing will be
+/// written if the condition did not hold.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct MaybeEmpty<T>(pub Option<T>);
+
+impl<T: fmt::Display> fmt::Display for MaybeEmpty<T> {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            Some(ref value) => value.fmt(f),
+            None => Ok(()),
+        }
+    }
+}
+
+impl<T: FastWritable> FastWritable for MaybeEmpty<T> {
+    #[inline]
+    fn write_into<W: fmt::Write + ?Sized>(&self, dest: &mut W) -> crate::Result<()> {
+        match self.0 {
+            Some(ref value) => value.write_into(dest),
+            None => Ok(()),
+        }
+    }
+}
+
+/// The value of an inline `then if cond else otherwise` expression, so that both branches can
+/// have different types.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Either<L, R> {
+    /// The `then` branch was taken
+    Left(L),
+    /// The `otherwise` branch was taken
+    Right(R),
+}
+
+impl<L: fmt::Display, R: fmt::Display> fmt::Display for Either<L, R> {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Either::Left(value) => value.fmt(f),
+            Either::Right(value) => value.fmt(f),
+        }
+    }
+}
+
+impl<L: FastWritable, R: FastWritable> FastWritable for Either<L, R> {
+    #[inline]
+    fn write_into<W: fmt::Write + ?Sized>(&self, dest: &mut W) -> crate::Result<()> {
+        match self {
+            Either::Left(value) => value.write_into(dest),
+            Either::Right(value) => value.write_into(dest),
+        }
+    }
+
+    #[inline]
+    fn write_bytes_into<B: ByteSink + ?Sized>(&self, dest: &mut B) -> crate::Result<()> {
+        match self {
+            Either::Left(value) => value.write_bytes_into(dest),
+            Either::Right(value) => value.write_bytes_into(dest),
+        }
+    }
+}
+
 #[inline]
 pub fn as_bool<T: PrimitiveType<Value = bool>>(value: T) -> bool {
     value.get()
@@ -280,3 +379,106 @@ pub trait EnumVariantTemplate {
         values: &dyn crate::Values,
     ) -> crate::Result<()>;
 }
+
+/// Learns the typical length of the output of a template at runtime
+pub struct SizeHintCache {
+    #[cfg(target_has_atomic = "ptr")]
+    value: core::sync::atomic::AtomicUsize,
+    #[cfg(not(target_has_atomic = "ptr"))]
+    value: usize,
+}
+
+impl SizeHintCache {
+    #[inline]
+    pub const fn new(size_hint: usize) -> Self {
+        Self {
+            #[cfg(target_has_atomic = "ptr")]
+            value: core::sync::atomic::AtomicUsize::new(size_hint),
+            #[cfg(not(target_has_atomic = "ptr"))]
+            value: size_hint,
+        }
+    }
+
+    #[inline]
+    pub fn get(&self) -> usize {
+        #[cfg(target_has_atomic = "ptr")]
+        return self.value.load(core::sync::atomic::Ordering::Relaxed);
+        #[cfg(not(target_has_atomic = "ptr"))]
+        return self.value;
+    }
+
+    /// Grows the estimate to a longer output immediately, but only slowly shrinks it
+    #[inline]
+    pub fn update(&self, len: usize) {
+        #[cfg(target_has_atomic = "ptr")]
+        {
+            let old = self.get();
+            let new = match len > old {
+                true => len,
+                false => old - (old - len) / 8,
+            };
+            if new != old {
+                self.value.store(new, core::sync::atomic::Ordering::Relaxed);
+            }
+        }
+        #[cfg(not(target_has_atomic = "ptr"))]
+        let _ = len;
+    }
+}
+
+/// Used internally by rinja as the output of the generated template code
+///
+/// It allows writing [`FastWritable`] values directly into a [`ByteSink`] if a template is
+/// rendered into bytes.
+pub trait Sink: fmt::Write {
+    fn write_fast<T: FastWritable + ?Sized>(&mut self, value: &T) -> crate::Result<()>;
+}
+
+/// Renders a template into an [`fmt::Write`] object
+pub struct FmtSink<'a, W: ?Sized>(pub &'a mut W);
+
+impl<W: fmt::Write + ?Sized> fmt::Write for FmtSink<'_, W> {
+    #[inline]
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.0.write_str(s)
+    }
+
+    #[inline]
+    fn write_char(&mut self, c: char) -> fmt::Result {
+        self.0.write_char(c)
+    }
+
+    #[inline]
+    fn write_fmt(&mut self, args: fmt::Arguments<'_>) -> fmt::Result {
+        self.0.write_fmt(args)
+    }
+}
+
+impl<W: fmt::Write + ?Sized> Sink for FmtSink<'_, W> {
+    #[inline]
+    fn write_fast<T: FastWritable + ?Sized>(&mut self, value: &T) -> crate::Result<()> {
+        value.write_into(self.0)
+    }
+}
+
+/// Renders a template into a [`ByteSink`]
+pub struct BytesSink<'a, B: ?Sized>(pub &'a mut B);
+
+impl<B: ByteSink + ?Sized> fmt::Write for BytesSink<'_, B> {
+    #[inline]
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.0.write_bytes(s.as_bytes())
+    }
+
+    #[inline]
+    fn write_char(&mut self, c: char) -> fmt::Result {
+        self.0.write_bytes(c.encode_utf8(&mut [0; 4]).as_bytes())
+    }
+}
+
+impl<B: ByteSink + ?Sized> Sink for BytesSink<'_, B> {
+    #[inline]
+    fn write_fast<T: FastWritable + ?Sized>(&mut self, value: &T) -> crate::Result<()> {
+        value.write_bytes_into(self.0)
+    }
+}
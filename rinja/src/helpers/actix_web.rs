@@ -0,0 +1,16 @@
+pub use ::actix_web::body::BoxBody;
+use ::actix_web::error::ErrorInternalServerError;
+pub use ::actix_web::{HttpRequest, HttpResponse, Responder};
+
+use crate::Template;
+
+/// Render `tmpl` into a response with the given `Content-Type`
+///
+/// If rendering failed, the error is returned as an [`actix_web::Error`](::actix_web::Error)
+/// with the status code 500, which is shown e.g. by actix-web's `Logger` middleware.
+pub fn respond<T: Template + ?Sized>(tmpl: &T, content_type: &'static str) -> HttpResponse {
+    match tmpl.render() {
+        Ok(body) => HttpResponse::Ok().content_type(content_type).body(body),
+        Err(err) => HttpResponse::from_error(ErrorInternalServerError(err)),
+    }
+}
@@ -0,0 +1,21 @@
+pub use ::axum_core::response::{IntoResponse, Response};
+use ::http::StatusCode;
+use ::http::header::{CONTENT_TYPE, HeaderValue};
+
+use crate::Template;
+
+/// Render `tmpl` into a response with the given `Content-Type`
+///
+/// If rendering failed, the error is logged, and a 500 is returned.
+pub fn into_response<T: Template + ?Sized>(tmpl: &T, content_type: &'static str) -> Response {
+    match tmpl.render() {
+        Ok(body) => {
+            let headers = [(CONTENT_TYPE, HeaderValue::from_static(content_type))];
+            (headers, body).into_response()
+        }
+        Err(err) => {
+            ::log::error!("could not render template: {err}");
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
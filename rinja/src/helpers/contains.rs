@@ -0,0 +1,304 @@
+//! Implementation of the `in` and `not in` operators.
+
+#[cfg(feature = "alloc")]
+use alloc::borrow::Cow;
+#[cfg(feature = "alloc")]
+use alloc::boxed::Box;
+#[cfg(feature = "alloc")]
+use alloc::collections::{BTreeMap, BTreeSet, VecDeque};
+#[cfg(feature = "alloc")]
+use alloc::string::String;
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+#[cfg(feature = "alloc")]
+use core::borrow::Borrow;
+#[cfg(feature = "std")]
+use core::hash::{BuildHasher, Hash};
+use core::ops::{Range, RangeBounds, RangeFrom, RangeInclusive, RangeTo, RangeToInclusive};
+#[cfg(feature = "std")]
+use std::collections::{HashMap, HashSet};
+
+/// The operands of an `in` or `not in` operator.
+///
+/// `needle in haystack` is generated as
+/// `(&&rinja::helpers::In(&(haystack), &(needle))).rinja_contains()`.
+/// If the haystack does not implement [`Contains`] for the type of the needle, and the needle is
+/// a reference, e.g. a `&String` loop variable, then the dereferenced needle is used instead.
+pub struct In<'a, H: ?Sized, N: ?Sized>(pub &'a H, pub &'a N);
+
+/// Implemented for [`In`] to evaluate the `in` operator, see there.
+pub trait InContains {
+    fn rinja_contains(&self) -> bool;
+}
+
+impl<H: Contains<N> + ?Sized, N: ?Sized> InContains for &In<'_, H, N> {
+    #[inline]
+    fn rinja_contains(&self) -> bool {
+        self.0.rinja_contains(self.1)
+    }
+}
+
+impl<H: Contains<N> + ?Sized, N: ?Sized> InContains for &&In<'_, H, &N> {
+    #[inline]
+    fn rinja_contains(&self) -> bool {
+        self.0.rinja_contains(*self.1)
+    }
+}
+
+/// A collection that can be the right-hand side of an `in` or `not in` operator.
+///
+/// * Slices, arrays, [`Vec`] and [`VecDeque`] compare each element with [`PartialEq`].
+/// * Strings search for a substring or a [`char`], see [`StrPattern`].
+/// * [`HashMap`], [`HashSet`], [`BTreeMap`] and [`BTreeSet`] look up a key, which can be
+///   [borrowed][Borrow] from the needle, like [`HashMap::contains_key()`].
+/// * Ranges test if the needle lies within their bounds, like [`Range::contains()`].
+pub trait Contains<N: ?Sized> {
+    /// Returns `true` if `needle` is contained in `self`.
+    fn rinja_contains(&self, needle: &N) -> bool;
+}
+
+impl<N: ?Sized, T: Contains<N> + ?Sized> Contains<N> for &T {
+    #[inline]
+    fn rinja_contains(&self, needle: &N) -> bool {
+        T::rinja_contains(self, needle)
+    }
+}
+
+impl<N: ?Sized, T: Contains<N> + ?Sized> Contains<N> for &mut T {
+    #[inline]
+    fn rinja_contains(&self, needle: &N) -> bool {
+        T::rinja_contains(self, needle)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<N: ?Sized, T: Contains<N> + ?Sized> Contains<N> for Box<T> {
+    #[inline]
+    fn rinja_contains(&self, needle: &N) -> bool {
+        T::rinja_contains(self, needle)
+    }
+}
+
+impl<N: ?Sized, T: PartialEq<N>> Contains<N> for [T] {
+    #[inline]
+    fn rinja_contains(&self, needle: &N) -> bool {
+        self.iter().any(|elem| elem == needle)
+    }
+}
+
+impl<N: ?Sized, T: PartialEq<N>, const M: usize> Contains<N> for [T; M] {
+    #[inline]
+    fn rinja_contains(&self, needle: &N) -> bool {
+        self.as_slice().rinja_contains(needle)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<N: ?Sized, T: PartialEq<N>> Contains<N> for Vec<T> {
+    #[inline]
+    fn rinja_contains(&self, needle: &N) -> bool {
+        self.as_slice().rinja_contains(needle)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<N: ?Sized, T: PartialEq<N>> Contains<N> for VecDeque<T> {
+    #[inline]
+    fn rinja_contains(&self, needle: &N) -> bool {
+        self.iter().any(|elem| elem == needle)
+    }
+}
+
+/// A value that can be searched for in a string with the `in` operator.
+pub trait StrPattern {
+    /// Returns `true` if `self` is contained in `haystack`.
+    fn rinja_is_in(&self, haystack: &str) -> bool;
+}
+
+impl<T: StrPattern + ?Sized> StrPattern for &T {
+    #[inline]
+    fn rinja_is_in(&self, haystack: &str) -> bool {
+        T::rinja_is_in(self, haystack)
+    }
+}
+
+impl StrPattern for str {
+    #[inline]
+    fn rinja_is_in(&self, haystack: &str) -> bool {
+        haystack.contains(self)
+    }
+}
+
+impl StrPattern for char {
+    #[inline]
+    fn rinja_is_in(&self, haystack: &str) -> bool {
+        haystack.contains(*self)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl StrPattern for String {
+    #[inline]
+    fn rinja_is_in(&self, haystack: &str) -> bool {
+        haystack.contains(self.as_str())
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl StrPattern for Cow<'_, str> {
+    #[inline]
+    fn rinja_is_in(&self, haystack: &str) -> bool {
+        haystack.contains(&**self)
+    }
+}
+
+impl<N: StrPattern + ?Sized> Contains<N> for str {
+    #[inline]
+    fn rinja_contains(&self, needle: &N) -> bool {
+        needle.rinja_is_in(self)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<N: StrPattern + ?Sized> Contains<N> for String {
+    #[inline]
+    fn rinja_contains(&self, needle: &N) -> bool {
+        needle.rinja_is_in(self)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<N: StrPattern + ?Sized> Contains<N> for Cow<'_, str> {
+    #[inline]
+    fn rinja_contains(&self, needle: &N) -> bool {
+        needle.rinja_is_in(self)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<N, K, V, S> Contains<N> for HashMap<K, V, S>
+where
+    N: Hash + Eq + ?Sized,
+    K: Borrow<N> + Hash + Eq,
+    S: BuildHasher,
+{
+    #[inline]
+    fn rinja_contains(&self, needle: &N) -> bool {
+        self.contains_key(needle)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<N, K, S> Contains<N> for HashSet<K, S>
+where
+    N: Hash + Eq + ?Sized,
+    K: Borrow<N> + Hash + Eq,
+    S: BuildHasher,
+{
+    #[inline]
+    fn rinja_contains(&self, needle: &N) -> bool {
+        self.contains(needle)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<N, K, V> Contains<N> for BTreeMap<K, V>
+where
+    N: Ord + ?Sized,
+    K: Borrow<N> + Ord,
+{
+    #[inline]
+    fn rinja_contains(&self, needle: &N) -> bool {
+        self.contains_key(needle)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<N, K> Contains<N> for BTreeSet<K>
+where
+    N: Ord + ?Sized,
+    K: Borrow<N> + Ord,
+{
+    #[inline]
+    fn rinja_contains(&self, needle: &N) -> bool {
+        self.contains(needle)
+    }
+}
+
+macro_rules! impl_for_ranges {
+    ($($ty:ident)*) => { $(
+        impl<N, T> Contains<N> for $ty<T>
+        where
+            N: PartialOrd<T> + ?Sized,
+            T: PartialOrd<N>,
+        {
+            #[inline]
+            fn rinja_contains(&self, needle: &N) -> bool {
+                RangeBounds::contains(self, needle)
+            }
+        }
+    )* };
+}
+
+impl_for_ranges!(Range RangeFrom RangeInclusive RangeTo RangeToInclusive);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_slices() {
+        assert!(["admin", "owner"].rinja_contains(&"admin"));
+        assert!(!["admin", "owner"].rinja_contains(&"guest"));
+        assert!([1, 2, 3][..].rinja_contains(&2));
+        #[cfg(feature = "alloc")]
+        {
+            let roles = alloc::vec![String::from("admin")];
+            assert!(roles.rinja_contains(&"admin"));
+        }
+    }
+
+    #[test]
+    fn test_strings() {
+        assert!("hello world".rinja_contains(&"lo w"));
+        assert!("hello world".rinja_contains(&'w'));
+        assert!(!"hello world".rinja_contains(&"World"));
+        #[cfg(feature = "alloc")]
+        {
+            let s = String::from("hello");
+            assert!(s.rinja_contains(&"ell"));
+            assert!("hello".rinja_contains(&&&String::from("llo")));
+        }
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_maps_and_sets() {
+        let map: HashMap<String, u32> = [(String::from("a"), 1)].into_iter().collect();
+        assert!(map.rinja_contains("a"));
+        assert!(!map.rinja_contains("b"));
+        let set: BTreeSet<&str> = ["a", "b"].into_iter().collect();
+        assert!(set.rinja_contains(&"b"));
+        assert!(!set.rinja_contains(&"c"));
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_deref_needle() {
+        let tags = alloc::vec![String::from("a")];
+        let tag = &tags[0];
+        assert!((&&In(&tags, &tag)).rinja_contains());
+        let set: HashSet<String> = tags.iter().cloned().collect();
+        assert!((&&In(&set, &"a")).rinja_contains());
+        assert!(!(&&In(&set, &"b")).rinja_contains());
+    }
+
+    #[test]
+    fn test_ranges() {
+        assert!((1..3).rinja_contains(&1));
+        assert!(!(1..3).rinja_contains(&3));
+        assert!((1..=3).rinja_contains(&3));
+        assert!((1..).rinja_contains(&u32::MAX));
+        assert!((..=1.5).rinja_contains(&1.5));
+    }
+}
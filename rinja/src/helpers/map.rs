@@ -0,0 +1,194 @@
+//! Implementation of map literals `{"key": value, ...}`.
+
+use core::fmt;
+use core::iter::FusedIterator;
+
+/// A map literal `{"key": value, ...}`.
+///
+/// `{"a": x, "b": 2}` is generated as
+/// `rinja::helpers::Map(MapEntry("a", &(x), MapEntry("b", 2, ())))`, i.e. the entries are
+/// stored in a linked list of [`MapEntry`]s that is terminated by `()`. A map literal does not
+/// allocate, every value keeps its own type, and the entries keep the order they were written in.
+///
+/// Accessing an entry with `map.key` is resolved at compile time. A map can be serialized, e.g.
+/// with the `json` filter, and if all values implement [`Display`][fmt::Display], then it can be
+/// iterated over as `(key, value)` pairs.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Map<E>(pub E);
+
+/// An entry of a [`Map`]: its key, its value, and the following entries.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MapEntry<V, N>(pub &'static str, pub V, pub N);
+
+/// The entries of a [`Map`], i.e. a [`MapEntry`] or `()`.
+pub trait MapEntries {
+    /// The number of entries.
+    const LEN: usize;
+}
+
+impl MapEntries for () {
+    const LEN: usize = 0;
+}
+
+impl<V, N: MapEntries> MapEntries for MapEntry<V, N> {
+    const LEN: usize = N::LEN + 1;
+}
+
+/// The entries of a [`Map`], if all values implement [`Display`][fmt::Display].
+pub trait DisplayEntries {
+    /// Returns the first key and value, and the remaining entries, if any.
+    fn split_first(&self) -> Option<(&'static str, &dyn fmt::Display, &dyn DisplayEntries)>;
+}
+
+impl DisplayEntries for () {
+    #[inline]
+    fn split_first(&self) -> Option<(&'static str, &dyn fmt::Display, &dyn DisplayEntries)> {
+        None
+    }
+}
+
+impl<V: fmt::Display, N: DisplayEntries> DisplayEntries for MapEntry<V, N> {
+    #[inline]
+    fn split_first(&self) -> Option<(&'static str, &dyn fmt::Display, &dyn DisplayEntries)> {
+        Some((self.0, &self.1, &self.2))
+    }
+}
+
+impl<E: MapEntries> Map<E> {
+    /// Returns the number of entries.
+    #[inline]
+    pub const fn len(&self) -> usize {
+        E::LEN
+    }
+
+    /// Returns `true` if the map has no entries.
+    #[inline]
+    pub const fn is_empty(&self) -> bool {
+        E::LEN == 0
+    }
+}
+
+impl<E: MapEntries + DisplayEntries> Map<E> {
+    /// Iterates over the keys and values in the order they were written in.
+    #[inline]
+    pub fn iter(&self) -> MapIter<'_> {
+        MapIter {
+            entries: &self.0,
+            len: E::LEN,
+        }
+    }
+}
+
+impl<'a, E: MapEntries + DisplayEntries> IntoIterator for &'a Map<E> {
+    type Item = (&'static str, &'a dyn fmt::Display);
+    type IntoIter = MapIter<'a>;
+
+    #[inline]
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// An iterator over the keys and values of a [`Map`], returned by [`Map::iter()`].
+pub struct MapIter<'a> {
+    entries: &'a dyn DisplayEntries,
+    len: usize,
+}
+
+impl<'a> Iterator for MapIter<'a> {
+    type Item = (&'static str, &'a dyn fmt::Display);
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        let (key, value, entries) = self.entries.split_first()?;
+        self.entries = entries;
+        self.len -= 1;
+        Some((key, value))
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl ExactSizeIterator for MapIter<'_> {}
+
+impl FusedIterator for MapIter<'_> {}
+
+#[cfg(feature = "serde_json")]
+mod serialize {
+    use serde::ser::{Serialize, SerializeMap, Serializer};
+
+    use super::{Map, MapEntries, MapEntry};
+
+    /// The entries of a [`Map`], if all values implement [`Serialize`].
+    pub trait SerializeEntries {
+        /// Serializes all entries into `map`.
+        fn serialize_entries<M: SerializeMap>(&self, map: &mut M) -> Result<(), M::Error>;
+    }
+
+    impl SerializeEntries for () {
+        #[inline]
+        fn serialize_entries<M: SerializeMap>(&self, _: &mut M) -> Result<(), M::Error> {
+            Ok(())
+        }
+    }
+
+    impl<V: Serialize, N: SerializeEntries> SerializeEntries for MapEntry<V, N> {
+        #[inline]
+        fn serialize_entries<M: SerializeMap>(&self, map: &mut M) -> Result<(), M::Error> {
+            map.serialize_entry(self.0, &self.1)?;
+            self.2.serialize_entries(map)
+        }
+    }
+
+    impl<E: MapEntries + SerializeEntries> Serialize for Map<E> {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            let mut map = serializer.serialize_map(Some(E::LEN))?;
+            self.0.serialize_entries(&mut map)?;
+            map.end()
+        }
+    }
+}
+
+#[cfg(feature = "serde_json")]
+pub use serialize::SerializeEntries;
+
+#[cfg(all(test, feature = "alloc"))]
+mod tests {
+    use alloc::string::ToString;
+
+    use super::*;
+
+    #[test]
+    fn test_iter() {
+        let map = Map(MapEntry("a", 1, MapEntry("b", "x", ())));
+        assert_eq!(map.len(), 2);
+        assert!(!map.is_empty());
+        assert!(Map(()).is_empty());
+
+        let mut iter = map.iter();
+        assert_eq!(iter.len(), 2);
+        let (key, value) = iter.next().unwrap();
+        assert_eq!((key, value.to_string().as_str()), ("a", "1"));
+        let (key, value) = iter.next().unwrap();
+        assert_eq!((key, value.to_string().as_str()), ("b", "x"));
+        assert!(iter.next().is_none());
+    }
+
+    #[cfg(feature = "serde_json")]
+    #[test]
+    fn test_serialize() {
+        let map = Map(MapEntry(
+            "b",
+            [1, 2],
+            MapEntry("a", Map(MapEntry("c", true, ())), ()),
+        ));
+        assert_eq!(
+            serde_json::to_string(&map).unwrap(),
+            r#"{"b":[1,2],"a":{"c":true}}"#
+        );
+        assert_eq!(serde_json::to_string(&Map(())).unwrap(), "{}");
+    }
+}
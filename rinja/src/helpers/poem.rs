@@ -0,0 +1,17 @@
+use ::poem::http::StatusCode;
+pub use ::poem::{IntoResponse, Response};
+
+use crate::Template;
+
+/// Render `tmpl` into a response with the given `Content-Type`
+///
+/// If rendering failed, the error is logged, and a 500 is returned.
+pub fn into_response<T: Template + ?Sized>(tmpl: &T, content_type: &'static str) -> Response {
+    match tmpl.render() {
+        Ok(body) => Response::builder().content_type(content_type).body(body),
+        Err(err) => {
+            ::log::error!("could not render template: {err}");
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
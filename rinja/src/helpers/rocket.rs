@@ -0,0 +1,22 @@
+use std::io::Cursor;
+
+use ::rocket::http::{Header, Status};
+pub use ::rocket::request::Request;
+pub use ::rocket::response::{Responder, Result};
+
+use crate::Template;
+
+/// Render `tmpl` into a response with the given `Content-Type`
+///
+/// If rendering failed, the error is logged, and the status 500 is forwarded to rocket's error
+/// catcher.
+pub fn respond<T: Template + ?Sized>(tmpl: &T, content_type: &'static str) -> Result<'static> {
+    let body = tmpl.render().map_err(|err| {
+        ::log::error!("could not render template: {err}");
+        Status::InternalServerError
+    })?;
+    ::rocket::Response::build()
+        .header(Header::new("content-type", content_type))
+        .sized_body(body.len(), Cursor::new(body))
+        .ok()
+}
@@ -0,0 +1,22 @@
+use ::salvo_core::http::StatusError;
+use ::salvo_core::http::header::{CONTENT_TYPE, HeaderValue};
+pub use ::salvo_core::{Response, Scribe};
+
+use crate::Template;
+
+/// Render `tmpl` into `res` with the given `Content-Type`
+///
+/// If rendering failed, the error is logged, and a 500 error is rendered into `res`.
+pub fn render<T: Template + ?Sized>(tmpl: &T, content_type: &'static str, res: &mut Response) {
+    match tmpl.render() {
+        Ok(body) => {
+            res.headers_mut()
+                .insert(CONTENT_TYPE, HeaderValue::from_static(content_type));
+            res.body(body);
+        }
+        Err(err) => {
+            ::log::error!("could not render template: {err}");
+            res.render(StatusError::internal_server_error());
+        }
+    }
+}
@@ -0,0 +1,24 @@
+use ::warp::http::StatusCode;
+use ::warp::http::header::{CONTENT_TYPE, HeaderValue};
+pub use ::warp::reply::{Reply, Response};
+
+use crate::Template;
+
+/// Render `tmpl` into a response with the given `Content-Type`
+///
+/// If rendering failed, the error is logged, and a 500 is returned.
+pub fn into_response<T: Template + ?Sized>(tmpl: &T, content_type: &'static str) -> Response {
+    match tmpl.render() {
+        Ok(body) => {
+            let mut response = Response::new(body.into());
+            response
+                .headers_mut()
+                .insert(CONTENT_TYPE, HeaderValue::from_static(content_type));
+            response
+        }
+        Err(err) => {
+            ::log::error!("could not render template: {err}");
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
@@ -7,22 +7,206 @@ use core::{fmt, str};
 
 #[allow(unused)]
 pub(crate) fn write_escaped_str(mut dest: impl fmt::Write, src: &str) -> fmt::Result {
-    // This implementation reads one byte after another.
-    // It's not very fast, but should work well enough until portable SIMD gets stabilized.
+    escape_chunks(src, |chunk| dest.write_str(chunk))
+}
 
+/// Like [`write_escaped_str()`], but the output is appended to a byte buffer
+#[allow(unused)]
+pub(crate) fn write_escaped_bytes(
+    mut dest: impl FnMut(&[u8]) -> fmt::Result,
+    src: &str,
+) -> fmt::Result {
+    escape_chunks(src, |chunk| dest(chunk.as_bytes()))
+}
+
+/// Calls `write` with each non-empty chunk of the escaped `src`
+#[inline]
+fn escape_chunks<E>(src: &str, mut write: impl FnMut(&str) -> Result<(), E>) -> Result<(), E> {
+    let bytes = src.as_bytes();
     let mut escaped_buf = ESCAPED_BUF_INIT;
     let mut last = 0;
 
-    for (index, byte) in src.bytes().enumerate() {
-        if let Some(escaped) = get_escaped(byte) {
+    while let Some(offset) = find_escaped(&bytes[last..]) {
+        let index = last + offset;
+        if let Some(escaped) = get_escaped(bytes[index]) {
             [escaped_buf[2], escaped_buf[3]] = escaped;
-            write_str_if_nonempty(&mut dest, &src[last..index])?;
+            if last < index {
+                write(&src[last..index])?;
+            }
             // SAFETY: the content of `escaped_buf` is pure ASCII
-            dest.write_str(unsafe { str::from_utf8_unchecked(&escaped_buf[..ESCAPED_BUF_LEN]) })?;
-            last = index + 1;
+            write(unsafe { str::from_utf8_unchecked(&escaped_buf[..ESCAPED_BUF_LEN]) })?;
+        }
+        last = index + 1;
+    }
+    if last < src.len() {
+        write(&src[last..])?;
+    }
+    Ok(())
+}
+
+/// Returns the index of the first byte in `bytes` that needs escaping
+///
+/// On x86 and x86_64 with SSE2 (or AVX2), and on aarch64 with NEON, 16 (or 32) bytes are tested
+/// at once. On other platforms, 8 bytes are tested at once using integer operations.
+///
+/// AVX2 is used if it is enabled at compile time, e.g. with `-C target-cpu=native`. Otherwise,
+/// if the feature `"std"` is enabled, it is used if the CPU supports it at runtime.
+#[cfg(all(
+    any(target_arch = "x86", target_arch = "x86_64"),
+    target_feature = "sse2",
+))]
+#[inline]
+fn find_escaped(bytes: &[u8]) -> Option<usize> {
+    #[cfg(target_feature = "avx2")]
+    // SAFETY: the target feature `avx2` is enabled
+    return unsafe { find_escaped_avx2(bytes) };
+
+    #[cfg(all(feature = "std", not(target_feature = "avx2")))]
+    if bytes.len() >= 32 && std::is_x86_feature_detected!("avx2") {
+        // SAFETY: the CPU supports `avx2`
+        return unsafe { find_escaped_avx2(bytes) };
+    }
+
+    #[cfg(not(target_feature = "avx2"))]
+    find_escaped_sse2(bytes, 0)
+}
+
+/// AVX2 implementation of [`find_escaped()`], which tests 32 bytes at once
+///
+/// # Safety
+///
+/// The CPU must support the target feature `avx2`.
+#[cfg(all(
+    any(target_arch = "x86", target_arch = "x86_64"),
+    target_feature = "sse2",
+    any(feature = "std", target_feature = "avx2"),
+))]
+#[target_feature(enable = "avx2")]
+unsafe fn find_escaped_avx2(bytes: &[u8]) -> Option<usize> {
+    #[cfg(target_arch = "x86")]
+    use core::arch::x86::*;
+    #[cfg(target_arch = "x86_64")]
+    use core::arch::x86_64::*;
+
+    let mut index = 0;
+    let needles = CHARS.map(|c| _mm256_set1_epi8(c as i8));
+    while index + 32 <= bytes.len() {
+        // SAFETY: `index + 32 <= bytes.len()`
+        let chunk = unsafe { _mm256_loadu_si256(bytes.as_ptr().add(index).cast()) };
+        let mut found = _mm256_setzero_si256();
+        for needle in needles {
+            found = _mm256_or_si256(found, _mm256_cmpeq_epi8(chunk, needle));
+        }
+        let mask = _mm256_movemask_epi8(found) as u32;
+        if mask != 0 {
+            return Some(index + mask.trailing_zeros() as usize);
+        }
+        index += 32;
+    }
+    find_escaped_sse2(bytes, index)
+}
+
+/// SSE2 implementation of [`find_escaped()`], which tests 16 bytes at once, starting at `index`
+#[cfg(all(
+    any(target_arch = "x86", target_arch = "x86_64"),
+    target_feature = "sse2",
+))]
+#[inline]
+fn find_escaped_sse2(bytes: &[u8], mut index: usize) -> Option<usize> {
+    #[cfg(target_arch = "x86")]
+    use core::arch::x86::*;
+    #[cfg(target_arch = "x86_64")]
+    use core::arch::x86_64::*;
+
+    // SAFETY: the target feature `sse2` is enabled, and `index + 16 <= bytes.len()`
+    unsafe {
+        let needles = CHARS.map(|c| _mm_set1_epi8(c as i8));
+        while index + 16 <= bytes.len() {
+            let chunk = _mm_loadu_si128(bytes.as_ptr().add(index).cast());
+            let mut found = _mm_setzero_si128();
+            for needle in needles {
+                found = _mm_or_si128(found, _mm_cmpeq_epi8(chunk, needle));
+            }
+            let mask = _mm_movemask_epi8(found) as u32;
+            if mask != 0 {
+                return Some(index + mask.trailing_zeros() as usize);
+            }
+            index += 16;
+        }
+    }
+
+    find_escaped_swar(&bytes[index..]).map(|offset| index + offset)
+}
+
+/// Returns the index of the first byte in `bytes` that needs escaping
+///
+/// See the x86 implementation for a description.
+#[cfg(all(target_arch = "aarch64", target_feature = "neon"))]
+#[inline]
+fn find_escaped(bytes: &[u8]) -> Option<usize> {
+    use core::arch::aarch64::*;
+
+    let mut index = 0;
+    // SAFETY: the target feature `neon` is enabled, and `index + 16 <= bytes.len()`
+    unsafe {
+        let needles = CHARS.map(|c| vdupq_n_u8(c));
+        while index + 16 <= bytes.len() {
+            let chunk = vld1q_u8(bytes.as_ptr().add(index));
+            let mut found = vdupq_n_u8(0);
+            for needle in needles {
+                found = vorrq_u8(found, vceqq_u8(chunk, needle));
+            }
+            if vmaxvq_u8(found) != 0 {
+                // the exact position is found by the integer implementation
+                return find_escaped_swar(&bytes[index..index + 16]).map(|offset| index + offset);
+            }
+            index += 16;
+        }
+    }
+
+    find_escaped_swar(&bytes[index..]).map(|offset| index + offset)
+}
+
+/// Returns the index of the first byte in `bytes` that needs escaping
+///
+/// See the x86 implementation for a description.
+#[cfg(not(any(
+    all(
+        any(target_arch = "x86", target_arch = "x86_64"),
+        target_feature = "sse2",
+    ),
+    all(target_arch = "aarch64", target_feature = "neon"),
+)))]
+#[inline]
+fn find_escaped(bytes: &[u8]) -> Option<usize> {
+    find_escaped_swar(bytes)
+}
+
+/// Portable implementation of `find_escaped()` that tests 8 bytes at once ("SIMD within a
+/// register")
+#[inline]
+fn find_escaped_swar(bytes: &[u8]) -> Option<usize> {
+    const LO: u64 = u64::from_ne_bytes([0x01; 8]);
+    const HI: u64 = u64::from_ne_bytes([0x80; 8]);
+
+    let mut chunks = bytes.chunks_exact(8);
+    for (index, chunk) in chunks.by_ref().enumerate() {
+        let word = u64::from_ne_bytes(chunk.try_into().unwrap());
+        let mut found = 0;
+        for c in CHARS {
+            // a byte in `diff` is zero iff the byte in `word` equals `c`
+            let diff = word ^ (LO * *c as u64);
+            found |= diff.wrapping_sub(LO) & !diff & HI;
+        }
+        if found != 0 {
+            let offset = chunk.iter().position(|&b| get_escaped(b).is_some());
+            return offset.map(|offset| index * 8 + offset);
         }
     }
-    write_str_if_nonempty(&mut dest, &src[last..])
+
+    let rest = chunks.remainder();
+    let offset = rest.iter().position(|&b| get_escaped(b).is_some())?;
+    Some(bytes.len() - rest.len() + offset)
 }
 
 #[allow(unused)]
@@ -49,17 +233,8 @@ fn get_escaped(byte: u8) -> Option<[u8; 2]> {
     }
 }
 
-#[inline(always)]
-fn write_str_if_nonempty(output: &mut impl fmt::Write, input: &str) -> fmt::Result {
-    if !input.is_empty() {
-        output.write_str(input)
-    } else {
-        Ok(())
-    }
-}
-
 /// List of characters that need HTML escaping, not necessarily in ordinal order.
-const CHARS: &[u8] = br#""&'<>"#;
+const CHARS: &[u8; 5] = br#""&'<>"#;
 
 /// The character with the lowest codepoint that needs HTML escaping.
 const MIN_CHAR: u8 = {
@@ -166,3 +341,59 @@ fn test_simple_html_string_escaping() {
     write_escaped_str(&mut buf, "s<cripcripcripcripcripcripcripcripcripcrip>t").unwrap();
     assert_eq!(buf, "s&#60;cripcripcripcripcripcripcripcripcripcrip&#62;t");
 }
+
+#[test]
+#[cfg(feature = "alloc")]
+fn test_simple_html_bytes_escaping() {
+    extern crate alloc;
+
+    fn escape(src: &str) -> alloc::vec::Vec<u8> {
+        let mut buf = alloc::vec::Vec::new();
+        write_escaped_bytes(
+            |s| {
+                buf.extend_from_slice(s);
+                Ok(())
+            },
+            src,
+        )
+        .unwrap();
+        buf
+    }
+
+    assert_eq!(escape("<script>"), b"&#60;script&#62;");
+    assert_eq!(
+        escape("a 'grüße' & \"b\""),
+        "a &#39;grüße&#39; &#38; &#34;b&#34;".as_bytes()
+    );
+}
+
+#[test]
+fn test_find_escaped() {
+    let text = b"0123456789abcdefghijklmnopqrstuvwxyz0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZ";
+    assert_eq!(find_escaped(text), None);
+    assert_eq!(find_escaped_swar(text), None);
+    assert_eq!(find_escaped(b""), None);
+    assert_eq!(find_escaped_swar(b""), None);
+
+    for &c in CHARS {
+        for index in 0..text.len() {
+            let mut text = *text;
+            text[index] = c;
+            assert_eq!(find_escaped(&text), Some(index));
+            assert_eq!(find_escaped_swar(&text), Some(index));
+            assert_eq!(find_escaped(&text[..index]), None);
+            assert_eq!(find_escaped_swar(&text[..index]), None);
+        }
+    }
+
+    // bytes that differ from a special character by a single bit
+    for &c in CHARS {
+        for bit in 0..8 {
+            let text = [c ^ (1 << bit); 40];
+            if get_escaped(text[0]).is_none() {
+                assert_eq!(find_escaped(&text), None);
+                assert_eq!(find_escaped_swar(&text), None);
+            }
+        }
+    }
+}
@@ -74,6 +74,8 @@ mod values;
 
 #[cfg(feature = "alloc")]
 use alloc::string::String;
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
 use core::fmt;
 #[cfg(feature = "std")]
 use std::io;
@@ -83,6 +85,8 @@ pub use rinja_derive::Template;
 
 #[doc(hidden)]
 pub use crate as shared;
+#[cfg(feature = "alloc")]
+pub use crate::error::LocatedError;
 pub use crate::error::{Error, Result};
 pub use crate::helpers::PrimitiveType;
 pub use crate::values::{NO_VALUES, Value, Values, get_value};
@@ -99,7 +103,9 @@ pub use crate::values::{NO_VALUES, Value, Values, get_value};
 /// * [`.render_into()`][Template::render_into] (to render the content into an [`fmt::Write`]
 ///   object, e.g. [`String`]) or
 /// * [`.write_into()`][Template::write_into] (to render the content into an [`io::Write`] object,
-///   e.g. [`Vec<u8>`][alloc::vec::Vec])
+///   e.g. [`Vec<u8>`][alloc::vec::Vec]) or
+/// * [`.render_into_vec()`][Template::render_into_vec] (to append the content to a reusable
+///   [`Vec<u8>`][alloc::vec::Vec])
 ///
 /// over [`.to_string()`][std::string::ToString::to_string] or [`format!()`][alloc::format].
 /// While `.to_string()` and `format!()` give you the same result, they generally perform much worse
@@ -121,11 +127,48 @@ pub trait Template: fmt::Display + filters::FastWritable {
     #[cfg(feature = "alloc")]
     fn render_with_values(&self, values: &dyn Values) -> Result<String> {
         let mut buf = String::new();
-        let _ = buf.try_reserve(Self::SIZE_HINT);
+        let _ = buf.try_reserve(<Self as Template>::adaptive_size_hint());
         self.render_into_with_values(&mut buf, values)?;
+        if let Some(cache) = Self::size_hint_cache() {
+            cache.update(buf.len());
+        }
         Ok(buf)
     }
 
+    /// Renders the template, appending the output to `buf`.
+    ///
+    /// Unlike [`.write_into()`][Template::write_into], the output is written directly into the
+    /// vector without going through [`io::Write`] or [`fmt::Write`], so you can cheaply reuse
+    /// (pooled) buffers for multiple renderings.
+    ///
+    /// If the rendering fails, `buf` is truncated to its original length.
+    #[inline]
+    #[cfg(feature = "alloc")]
+    fn render_into_vec(&self, buf: &mut Vec<u8>) -> Result<()> {
+        self.render_into_vec_with_values(buf, NO_VALUES)
+    }
+
+    /// Renders the template with provided [`Values`], appending the output to `buf`.
+    ///
+    /// See [`.render_into_vec()`][Template::render_into_vec].
+    #[cfg(feature = "alloc")]
+    fn render_into_vec_with_values(&self, buf: &mut Vec<u8>, values: &dyn Values) -> Result<()> {
+        let start = buf.len();
+        let _ = buf.try_reserve(<Self as Template>::adaptive_size_hint());
+        match self.render_into_bytes_with_values(buf, values) {
+            Ok(()) => {
+                if let Some(cache) = Self::size_hint_cache() {
+                    cache.update(buf.len() - start);
+                }
+                Ok(())
+            }
+            Err(err) => {
+                buf.truncate(start);
+                Err(err)
+            }
+        }
+    }
+
     /// Renders the template to the given `writer` fmt buffer.
     #[inline]
     fn render_into<W: fmt::Write + ?Sized>(&self, writer: &mut W) -> Result<()> {
@@ -139,6 +182,42 @@ pub trait Template: fmt::Display + filters::FastWritable {
         values: &dyn Values,
     ) -> Result<()>;
 
+    /// Renders the template, appending the output to the byte buffer `dest`.
+    ///
+    /// Unlike [`.render_into()`][Template::render_into], derived templates write their literal
+    /// text and the output of [`FastWritable`][filters::FastWritable] values directly into the
+    /// buffer, and escape it byte-wise, without going through [`fmt::Write`].
+    #[inline]
+    fn render_into_bytes<B: filters::ByteSink + ?Sized>(&self, dest: &mut B) -> Result<()> {
+        self.render_into_bytes_with_values(dest, NO_VALUES)
+    }
+
+    /// Renders the template with provided [`Values`], appending the output to the byte buffer
+    /// `dest`.
+    ///
+    /// See [`.render_into_bytes()`][Template::render_into_bytes].
+    #[inline]
+    fn render_into_bytes_with_values<B: filters::ByteSink + ?Sized>(
+        &self,
+        dest: &mut B,
+        values: &dyn Values,
+    ) -> Result<()> {
+        self.render_into_sink(&mut helpers::BytesSink(dest), values)
+    }
+
+    /// Used internally by rinja to render the template into a [`fmt::Write`] or a byte buffer
+    ///
+    /// Derived templates implement this method instead of `render_into_with_values()`.
+    #[doc(hidden)]
+    #[inline]
+    fn render_into_sink<S: helpers::Sink + ?Sized>(
+        &self,
+        sink: &mut S,
+        values: &dyn Values,
+    ) -> Result<()> {
+        self.render_into_with_values(sink, values)
+    }
+
     /// Renders the template to the given `writer` io buffer.
     #[inline]
     #[cfg(feature = "std")]
@@ -174,7 +253,7 @@ pub trait Template: fmt::Display + filters::FastWritable {
             Ok(())
         } else {
             let err = wrapped.err.take();
-            Err(err.unwrap_or_else(|| io::Error::new(io::ErrorKind::Other, fmt::Error)))
+            Err(err.unwrap_or_else(|| io::Error::other(fmt::Error)))
         }
     }
 
@@ -189,6 +268,68 @@ pub trait Template: fmt::Display + filters::FastWritable {
     /// [`write_into`]: Template::write_into
     /// [`ToString::to_string`]: alloc::string::ToString::to_string
     const SIZE_HINT: usize;
+
+    /// Provides an estimate of the expanded length of the rendered template that is learned at
+    /// runtime
+    ///
+    /// It starts as [`SIZE_HINT`], grows to the length of the longest output of [`render`] and
+    /// [`render_into_vec`], and slowly shrinks again if the outputs get shorter. This value is
+    /// used to pre-allocate the output buffer in these methods. The estimate is shared between
+    /// all instantiations of a generic template.
+    ///
+    /// If the `Template` is not derived, then this is simply [`SIZE_HINT`].
+    ///
+    /// [`SIZE_HINT`]: Template::SIZE_HINT
+    /// [`render`]: Template::render
+    /// [`render_into_vec`]: Template::render_into_vec
+    #[inline]
+    fn adaptive_size_hint() -> usize {
+        match Self::size_hint_cache() {
+            Some(cache) => cache.get(),
+            None => Self::SIZE_HINT,
+        }
+    }
+
+    /// Storage for [`Template::adaptive_size_hint()`], implemented by the derive macro
+    #[doc(hidden)]
+    #[inline]
+    fn size_hint_cache() -> Option<&'static helpers::SizeHintCache> {
+        None
+    }
+
+    /// The extension of the template file, e.g. `Some("html")` for `"hello.html"`
+    ///
+    /// It is derived from the `path` or `ext` argument of the template. Jinja extensions are
+    /// skipped, i.e. the extension of `"hello.html.j2"` is `Some("html")` as well.
+    /// For a template `enum` whose variants have different extensions, it is `None`.
+    const EXTENSION: Option<&'static str> = None;
+
+    /// The MIME type of the rendered template, e.g. `"text/html; charset=utf-8"` for HTML templates
+    ///
+    /// It is derived from the `escape` argument or from the extension of the template.
+    /// You can add or override the used MIME types in the [configuration file].
+    /// For a template `enum` whose variants have different MIME types, the default
+    /// `"text/plain; charset=utf-8"` is used.
+    ///
+    /// [configuration file]: https://rinja.readthedocs.io/en/latest/configuration.html
+    const MIME_TYPE: &'static str = "text/plain; charset=utf-8";
+
+    /// The rendered template, if it only consists of literal text
+    ///
+    /// A template without any expressions, blocks or control statements renders the same output
+    /// every time, so it is computed at compile time. Use it to serve static pages without
+    /// rendering them for every request.
+    const RENDERED: Option<&'static str> = None;
+
+    /// The gzip compressed content of [`Template::RENDERED`]
+    ///
+    /// The output is only compressed if the feature `"gzip"` is enabled.
+    const RENDERED_GZIP: Option<&'static [u8]> = None;
+
+    /// The brotli compressed content of [`Template::RENDERED`]
+    ///
+    /// The output is only compressed if the feature `"brotli"` is enabled.
+    const RENDERED_BROTLI: Option<&'static [u8]> = None;
 }
 
 impl<T: Template + ?Sized> Template for &T {
@@ -218,6 +359,15 @@ impl<T: Template + ?Sized> Template for &T {
         <T as Template>::render_into_with_values(self, writer, values)
     }
 
+    #[inline]
+    fn render_into_sink<S: helpers::Sink + ?Sized>(
+        &self,
+        sink: &mut S,
+        values: &dyn Values,
+    ) -> Result<()> {
+        <T as Template>::render_into_sink(self, sink, values)
+    }
+
     #[inline]
     #[cfg(feature = "std")]
     fn write_into<W: io::Write + ?Sized>(&self, writer: &mut W) -> io::Result<()> {
@@ -235,6 +385,21 @@ impl<T: Template + ?Sized> Template for &T {
     }
 
     const SIZE_HINT: usize = T::SIZE_HINT;
+
+    #[inline]
+    fn size_hint_cache() -> Option<&'static helpers::SizeHintCache> {
+        T::size_hint_cache()
+    }
+
+    const EXTENSION: Option<&'static str> = T::EXTENSION;
+
+    const MIME_TYPE: &'static str = T::MIME_TYPE;
+
+    const RENDERED: Option<&'static str> = T::RENDERED;
+
+    const RENDERED_GZIP: Option<&'static [u8]> = T::RENDERED_GZIP;
+
+    const RENDERED_BROTLI: Option<&'static [u8]> = T::RENDERED_BROTLI;
 }
 
 /// [`dyn`-compatible] wrapper trait around [`Template`] implementers
@@ -251,6 +416,33 @@ pub trait DynTemplate {
     #[cfg(feature = "alloc")]
     fn dyn_render_with_values(&self, values: &dyn Values) -> Result<String>;
 
+    /// Renders the template, appending the output to `buf`.
+    ///
+    /// If the rendering fails, `buf` is truncated to its original length.
+    #[inline]
+    #[cfg(feature = "alloc")]
+    fn dyn_render_into_vec(&self, buf: &mut Vec<u8>) -> Result<()> {
+        self.dyn_render_into_vec_with_values(buf, NO_VALUES)
+    }
+
+    /// Renders the template with provided [`Values`], appending the output to `buf`.
+    ///
+    /// If the rendering fails, `buf` is truncated to its original length.
+    #[cfg(feature = "alloc")]
+    fn dyn_render_into_vec_with_values(
+        &self,
+        buf: &mut Vec<u8>,
+        values: &dyn Values,
+    ) -> Result<()> {
+        let start = buf.len();
+        let _ = buf.try_reserve(self.size_hint());
+        let result = self.dyn_render_into_with_values(&mut helpers::BytesSink(buf), values);
+        if result.is_err() {
+            buf.truncate(start);
+        }
+        result
+    }
+
     /// Renders the template to the given `writer` fmt buffer.
     fn dyn_render_into(&self, writer: &mut dyn fmt::Write) -> Result<()>;
 
@@ -289,6 +481,21 @@ impl<T: Template> DynTemplate for T {
         <Self as Template>::render_with_values(self, values)
     }
 
+    #[inline]
+    #[cfg(feature = "alloc")]
+    fn dyn_render_into_vec(&self, buf: &mut Vec<u8>) -> Result<()> {
+        <Self as Template>::render_into_vec(self, buf)
+    }
+
+    #[cfg(feature = "alloc")]
+    fn dyn_render_into_vec_with_values(
+        &self,
+        buf: &mut Vec<u8>,
+        values: &dyn Values,
+    ) -> Result<()> {
+        <Self as Template>::render_into_vec_with_values(self, buf, values)
+    }
+
     #[inline]
     fn dyn_render_into(&self, writer: &mut dyn fmt::Write) -> Result<()> {
         <Self as Template>::render_into(self, writer)
@@ -319,7 +526,7 @@ impl<T: Template> DynTemplate for T {
 
     #[inline]
     fn size_hint(&self) -> usize {
-        <Self as Template>::SIZE_HINT
+        <Self as Template>::adaptive_size_hint()
     }
 }
 
@@ -425,5 +632,154 @@ mod tests {
         let mut vec = alloc::vec![];
         test.dyn_write_into(&mut vec).unwrap();
         assert_eq!(vec, alloc::vec![b't', b'e', b's', b't']);
+
+        let mut vec = alloc::vec![];
+        test.dyn_render_into_vec(&mut vec).unwrap();
+        assert_eq!(vec, b"test");
+
+        assert_eq!(test.size_hint(), 4);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn dyn_template_default_methods() {
+        // Only implements the required methods, the `dyn_render_into_vec*` methods use
+        // `dyn_render_into_with_values()`.
+        struct Test(bool);
+
+        impl DynTemplate for Test {
+            fn dyn_render(&self) -> Result<String> {
+                self.dyn_render_with_values(NO_VALUES)
+            }
+
+            fn dyn_render_with_values(&self, values: &dyn Values) -> Result<String> {
+                let mut buf = String::new();
+                self.dyn_render_into_with_values(&mut buf, values)?;
+                Ok(buf)
+            }
+
+            fn dyn_render_into(&self, writer: &mut dyn fmt::Write) -> Result<()> {
+                self.dyn_render_into_with_values(writer, NO_VALUES)
+            }
+
+            fn dyn_render_into_with_values(
+                &self,
+                writer: &mut dyn fmt::Write,
+                _values: &dyn Values,
+            ) -> Result<()> {
+                writer.write_str("test")?;
+                match self.0 {
+                    true => Ok(()),
+                    false => Err(Error::Fmt),
+                }
+            }
+
+            fn dyn_write_into(&self, writer: &mut dyn io::Write) -> io::Result<()> {
+                writer.write_all(self.dyn_render()?.as_bytes())
+            }
+
+            fn dyn_write_into_with_values(
+                &self,
+                writer: &mut dyn io::Write,
+                values: &dyn Values,
+            ) -> io::Result<()> {
+                writer.write_all(self.dyn_render_with_values(values)?.as_bytes())
+            }
+
+            fn size_hint(&self) -> usize {
+                4
+            }
+        }
+
+        let mut vec = alloc::vec![b'>'];
+        Test(true).dyn_render_into_vec(&mut vec).unwrap();
+        assert_eq!(vec, b">test");
+
+        Test(false).dyn_render_into_vec(&mut vec).unwrap_err();
+        assert_eq!(vec, b">test");
+    }
+
+    #[cfg(feature = "bytes")]
+    #[test]
+    fn render_into_bytes_mut() {
+        use crate::filters::{Html, escape};
+
+        struct Test;
+
+        impl Template for Test {
+            fn render_into_with_values<W: fmt::Write + ?Sized>(
+                &self,
+                writer: &mut W,
+                _values: &dyn Values,
+            ) -> Result<()> {
+                Ok(write!(writer, "{}", escape("<test>", Html)?)?)
+            }
+
+            const SIZE_HINT: usize = 4;
+        }
+
+        impl fmt::Display for Test {
+            #[inline]
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                self.render_into(f).map_err(|_| fmt::Error {})
+            }
+        }
+
+        impl filters::FastWritable for Test {
+            #[inline]
+            fn write_into<W: fmt::Write + ?Sized>(&self, f: &mut W) -> crate::Result<()> {
+                self.render_into(f)
+            }
+        }
+
+        let mut buf = bytes::BytesMut::new();
+        Test.render_into_bytes(&mut buf).unwrap();
+        assert_eq!(&buf[..], b"&#60;test&#62;");
+    }
+
+    #[test]
+    fn render_into_byte_slice() {
+        use crate::filters::{Html, escape};
+
+        struct Test;
+
+        impl Template for Test {
+            fn render_into_with_values<W: fmt::Write + ?Sized>(
+                &self,
+                writer: &mut W,
+                _values: &dyn Values,
+            ) -> Result<()> {
+                Ok(write!(writer, "{}", escape("<test>", Html)?)?)
+            }
+
+            const SIZE_HINT: usize = 4;
+        }
+
+        impl fmt::Display for Test {
+            #[inline]
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                self.render_into(f).map_err(|_| fmt::Error {})
+            }
+        }
+
+        impl filters::FastWritable for Test {
+            #[inline]
+            fn write_into<W: fmt::Write + ?Sized>(&self, f: &mut W) -> crate::Result<()> {
+                self.render_into(f)
+            }
+        }
+
+        let mut buf = [0; 16];
+        let mut dest = &mut buf[..];
+        Test.render_into_bytes(&mut dest).unwrap();
+        assert_eq!(dest.len(), 2);
+        assert_eq!(&buf[..14], b"&#60;test&#62;");
+
+        // the output does not fit
+        let mut buf = [0; 8];
+        assert!(matches!(
+            Test.render_into_bytes(&mut &mut buf[..]),
+            Err(Error::Fmt)
+        ));
     }
 }
@@ -7,11 +7,12 @@ use std::sync::{Arc, OnceLock};
 use std::{env, fs};
 
 use parser::node::Whitespace;
-use parser::{ParseError, Parsed, Syntax, SyntaxBuilder};
+use parser::{Dialect, ParseError, Parsed, Syntax, SyntaxBuilder};
 use proc_macro2::Span;
 #[cfg(feature = "config")]
 use serde_derive::Deserialize;
 
+use crate::minify::Minify;
 use crate::{CompileError, FileInfo, OnceMap};
 
 #[derive(Debug)]
@@ -20,7 +21,14 @@ pub(crate) struct Config {
     pub(crate) syntaxes: BTreeMap<String, SyntaxAndCache<'static>>,
     pub(crate) default_syntax: &'static str,
     pub(crate) escapers: Vec<(Vec<Cow<'static, str>>, Cow<'static, str>)>,
+    pub(crate) mime_types: Vec<(Vec<Cow<'static, str>>, Cow<'static, str>)>,
     pub(crate) whitespace: Whitespace,
+    pub(crate) minify: Minify,
+    pub(crate) validate_html: bool,
+    pub(crate) warn_unused: bool,
+    pub(crate) error_locations: bool,
+    pub(crate) runtime_error_locations: bool,
+    pub(crate) dump_dir: Option<PathBuf>,
     // `Config` is self referential and `_key` owns it data, so it must come last
     _key: OwnedConfigKey,
 }
@@ -101,34 +109,47 @@ impl Config {
         let root = manifest_root();
         let default_dirs = vec![root.join("templates")];
 
-        let mut syntaxes = BTreeMap::new();
-        syntaxes.insert(DEFAULT_SYNTAX_NAME.to_string(), SyntaxAndCache::default());
-
         let raw = if s.is_empty() {
             RawConfig::default()
         } else {
             RawConfig::from_toml_str(s)?
         };
 
-        let (dirs, default_syntax, whitespace) = match raw.general {
-            Some(General {
-                dirs,
-                default_syntax,
-                whitespace,
-            }) => (
-                dirs.map_or(default_dirs, |v| {
-                    v.into_iter().map(|dir| root.join(dir)).collect()
-                }),
-                default_syntax.unwrap_or(DEFAULT_SYNTAX_NAME),
-                whitespace,
-            ),
-            None => (default_dirs, DEFAULT_SYNTAX_NAME, Whitespace::default()),
-        };
+        let General {
+            dirs,
+            default_syntax,
+            whitespace,
+            minify,
+            validate_html,
+            warn_unused,
+            error_locations,
+            runtime_error_locations,
+            dump_dir,
+            dialect,
+        } = raw.general.unwrap_or_default();
+        let dirs = dirs.map_or(default_dirs, |v| {
+            v.into_iter().map(|dir| root.join(dir)).collect()
+        });
+        let default_syntax = default_syntax.unwrap_or(DEFAULT_SYNTAX_NAME);
+        let dump_dir = dump_dir.map(|dir| root.join(dir));
         let file_info = config_path.map(|path| FileInfo::new(Path::new(path), None, None));
         let whitespace = key.0.template_whitespace.unwrap_or(whitespace);
 
+        // The `dialect` of the `[general]` section applies to all syntaxes that don't override it.
+        let mut syntaxes = BTreeMap::new();
+        let default = SyntaxBuilder {
+            name: DEFAULT_SYNTAX_NAME,
+            dialect: Some(dialect),
+            ..SyntaxBuilder::default()
+        };
+        syntaxes.insert(
+            DEFAULT_SYNTAX_NAME.to_string(),
+            SyntaxAndCache::new(default.to_syntax().unwrap()),
+        );
+
         if let Some(raw_syntaxes) = raw.syntax {
-            for raw_s in raw_syntaxes {
+            for mut raw_s in raw_syntaxes {
+                raw_s.dialect = raw_s.dialect.or(Some(dialect));
                 let name = raw_s.name;
                 match syntaxes.entry(name.to_string()) {
                     Entry::Vacant(entry) => {
@@ -166,16 +187,42 @@ impl Config {
             ));
         }
 
+        let mut mime_types = Vec::new();
+        if let Some(configured) = raw.mime_type {
+            for mime_type in configured {
+                mime_types.push((str_set(&mime_type.extensions), mime_type.ty.into()));
+            }
+        }
+        for (extensions, mime_type) in DEFAULT_MIME_TYPES {
+            mime_types.push((str_set(extensions), (*mime_type).into()));
+        }
+
         Ok(Config {
             dirs,
             syntaxes,
             default_syntax,
             escapers,
+            mime_types,
             whitespace,
+            minify,
+            validate_html,
+            warn_unused,
+            error_locations,
+            runtime_error_locations,
+            dump_dir,
             _key: key,
         })
     }
 
+    /// The MIME type of templates with the extension `ext`, if one is known
+    pub(crate) fn mime_type(&self, ext: &str) -> Option<&str> {
+        self.mime_types.iter().find_map(|(extensions, mime_type)| {
+            extensions
+                .contains(&Cow::Borrowed(ext))
+                .then_some(mime_type.as_ref())
+        })
+    }
+
     pub(crate) fn find_template(
         &self,
         path: &str,
@@ -262,7 +309,7 @@ impl<'a> SyntaxAndCache<'a> {
         &self,
         source: Arc<str>,
         source_path: Option<Arc<Path>>,
-    ) -> Result<Arc<Parsed>, ParseError> {
+    ) -> Result<Arc<Parsed>, Vec<ParseError>> {
         self.cache.get_or_try_insert(
             &SyntaxAndCacheKey {
                 source: Cow::Owned(source),
@@ -295,6 +342,7 @@ struct RawConfig<'a> {
     general: Option<General<'a>>,
     syntax: Option<Vec<SyntaxBuilder<'a>>>,
     escaper: Option<Vec<RawEscaper<'a>>>,
+    mime_type: Option<Vec<RawMimeType<'a>>>,
 }
 
 impl RawConfig<'_> {
@@ -315,12 +363,26 @@ impl RawConfig<'_> {
 }
 
 #[cfg_attr(feature = "config", derive(Deserialize))]
+#[derive(Default)]
 struct General<'a> {
     #[cfg_attr(feature = "config", serde(borrow))]
     dirs: Option<Vec<&'a str>>,
     default_syntax: Option<&'a str>,
     #[cfg_attr(feature = "config", serde(default))]
     whitespace: Whitespace,
+    #[cfg_attr(feature = "config", serde(default))]
+    minify: Minify,
+    #[cfg_attr(feature = "config", serde(default))]
+    validate_html: bool,
+    #[cfg_attr(feature = "config", serde(default))]
+    warn_unused: bool,
+    #[cfg_attr(feature = "config", serde(default))]
+    error_locations: bool,
+    #[cfg_attr(feature = "config", serde(default))]
+    runtime_error_locations: bool,
+    dump_dir: Option<&'a str>,
+    #[cfg_attr(feature = "config", serde(default))]
+    dialect: Dialect,
 }
 
 #[cfg_attr(feature = "config", derive(Deserialize))]
@@ -329,6 +391,13 @@ struct RawEscaper<'a> {
     extensions: Vec<&'a str>,
 }
 
+#[cfg_attr(feature = "config", derive(Deserialize))]
+struct RawMimeType<'a> {
+    #[cfg_attr(feature = "config", serde(rename = "type"))]
+    ty: &'a str,
+    extensions: Vec<&'a str>,
+}
+
 pub(crate) fn read_config_file(
     config_path: Option<&str>,
     span: Option<Span>,
@@ -376,6 +445,24 @@ static DEFAULT_ESCAPERS: &[(&[&str], &str)] = &[
     (&["md", "none", "txt", "yml", ""], "Text"),
 ];
 
+static DEFAULT_MIME_TYPES: &[(&[&str], &str)] = &[
+    (
+        &["askama", "htm", "html", "j2", "jinja", "jinja2", "rinja"],
+        "text/html; charset=utf-8",
+    ),
+    (&["css"], "text/css; charset=utf-8"),
+    (&["csv"], "text/csv; charset=utf-8"),
+    (&["js", "mjs"], "text/javascript; charset=utf-8"),
+    (&["json"], "application/json"),
+    (&["md"], "text/markdown; charset=utf-8"),
+    (&["svg"], "image/svg+xml"),
+    (&["xml"], "application/xml"),
+    (&["txt", ""], "text/plain; charset=utf-8"),
+];
+
+/// The MIME type of templates with an unknown extension
+pub(crate) const DEFAULT_MIME_TYPE: &str = "text/plain; charset=utf-8";
+
 #[cfg(test)]
 mod tests {
     use std::env;
@@ -400,6 +487,19 @@ mod tests {
         assert_eq!(config.dirs, vec![root]);
     }
 
+    #[cfg(feature = "config")]
+    #[test]
+    fn test_config_dump_dir() {
+        let config = Config::new("", None, None, None).unwrap();
+        assert_eq!(config.dump_dir, None);
+
+        let mut root = PathBuf::from(env::var("CARGO_MANIFEST_DIR").unwrap());
+        root.push("target/rinja");
+        let config =
+            Config::new("[general]\ndump_dir = \"target/rinja\"", None, None, None).unwrap();
+        assert_eq!(config.dump_dir, Some(root));
+    }
+
     fn assert_eq_rooted(actual: &Path, expected: &str) {
         let mut root = PathBuf::from(env::var("CARGO_MANIFEST_DIR").unwrap())
             .canonicalize()
@@ -520,6 +620,41 @@ mod tests {
         assert_eq!(bar.comment_end, default_syntax.comment_end);
     }
 
+    #[cfg(feature = "config")]
+    #[test]
+    fn dialect() {
+        let raw_config = r#"
+        [general]
+        dialect = "jinja2"
+
+        [[syntax]]
+        name = "foo"
+        block_start = "{<"
+
+        [[syntax]]
+        name = "bar"
+        dialect = "rinja"
+        "#;
+
+        let config = Config::new(raw_config, None, None, None).unwrap();
+        let dialect = |name| config.syntaxes.get(name).unwrap().dialect;
+        assert_eq!(dialect(DEFAULT_SYNTAX_NAME), Dialect::Jinja2);
+        assert_eq!(dialect("foo"), Dialect::Jinja2);
+        assert_eq!(dialect("bar"), Dialect::Rinja);
+
+        let config = Config::new("", None, None, None).unwrap();
+        assert_eq!(
+            config.syntaxes.get(DEFAULT_SYNTAX_NAME).unwrap().dialect,
+            Dialect::Rinja,
+        );
+
+        let raw_config = r#"
+        [general]
+        dialect = "django"
+        "#;
+        assert!(Config::new(raw_config, None, None, None).is_err());
+    }
+
     #[cfg(feature = "config")]
     #[test]
     fn longer_delimiters() {
@@ -663,6 +798,64 @@ mod tests {
         );
     }
 
+    #[cfg(feature = "config")]
+    #[test]
+    fn mime_types() {
+        let config = Config::new(
+            r#"
+            [[mime_type]]
+            type = "text/calendar; charset=utf-8"
+            extensions = ["ics"]
+
+            [[mime_type]]
+            type = "application/xhtml+xml"
+            extensions = ["html"]
+        "#,
+            None,
+            None,
+            None,
+        )
+        .unwrap();
+        assert_eq!(
+            config.mime_type("ics"),
+            Some("text/calendar; charset=utf-8")
+        );
+        assert_eq!(config.mime_type("html"), Some("application/xhtml+xml"));
+        assert_eq!(config.mime_type("htm"), Some("text/html; charset=utf-8"));
+        assert_eq!(config.mime_type(""), Some("text/plain; charset=utf-8"));
+        assert_eq!(config.mime_type("exe"), None);
+    }
+
+    #[cfg(feature = "config")]
+    #[test]
+    fn test_minify_parsing() {
+        let config = Config::new(
+            r#"
+            [general]
+            minify = "html"
+            "#,
+            None,
+            None,
+            None,
+        )
+        .unwrap();
+        assert_eq!(config.minify, Minify::Html);
+
+        let config = Config::new(r#""#, None, None, None).unwrap();
+        assert_eq!(config.minify, Minify::None);
+
+        let config = Config::new(
+            r#"
+            [general]
+            minify = "css"
+            "#,
+            None,
+            None,
+            None,
+        );
+        assert!(config.is_err());
+    }
+
     #[cfg(feature = "config")]
     #[test]
     fn test_whitespace_parsing() {
@@ -727,4 +920,75 @@ mod tests {
         let config = Config::new(r#""#, None, Some(Whitespace::Minimize), None).unwrap();
         assert_eq!(config.whitespace, Whitespace::Minimize);
     }
+
+    #[cfg(feature = "config")]
+    #[test]
+    fn test_validate_html_parsing() {
+        let config = Config::new(r#""#, None, None, None).unwrap();
+        assert!(!config.validate_html);
+
+        let config = Config::new(
+            r#"
+            [general]
+            validate_html = true
+            "#,
+            None,
+            None,
+            None,
+        )
+        .unwrap();
+        assert!(config.validate_html);
+    }
+
+    #[cfg(feature = "config")]
+    #[test]
+    fn test_warn_unused_parsing() {
+        let config = Config::new(r#""#, None, None, None).unwrap();
+        assert!(!config.warn_unused);
+
+        let config = Config::new(
+            r#"
+            [general]
+            warn_unused = true
+            "#,
+            None,
+            None,
+            None,
+        )
+        .unwrap();
+        assert!(config.warn_unused);
+    }
+
+    #[cfg(feature = "config")]
+    #[test]
+    fn test_error_locations_parsing() {
+        let config = Config::new(r#""#, None, None, None).unwrap();
+        assert!(!config.error_locations);
+
+        let config = Config::new(
+            r#"
+            [general]
+            error_locations = true
+            "#,
+            None,
+            None,
+            None,
+        )
+        .unwrap();
+        assert!(config.error_locations);
+        assert!(!config.runtime_error_locations);
+
+        let config = Config::new(
+            r#"
+            [general]
+            runtime_error_locations = true
+            "#,
+            None,
+            None,
+            None,
+        )
+        .unwrap();
+        assert!(!config.error_locations);
+        assert!(config.runtime_error_locations);
+    }
 }
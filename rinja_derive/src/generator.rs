@@ -1,5 +1,7 @@
 mod expr;
+mod fold;
 mod node;
+mod pyformat;
 
 use std::borrow::Cow;
 use std::collections::hash_map::HashMap;
@@ -8,16 +10,21 @@ use std::path::Path;
 use std::str;
 use std::sync::Arc;
 
-use parser::node::{Macro, Whitespace};
+use parser::node::{BlockDef, Macro, Whitespace};
 use parser::{
-    CharLit, Expr, FloatKind, IntKind, MAX_RUST_KEYWORD_LEN, Num, RUST_KEYWORDS, StrLit, WithSpan,
+    CharLit, Expr, Filter, FloatKind, IntKind, MAX_RUST_KEYWORD_LEN, Num, RUST_KEYWORDS, Span,
+    StrLit, WithSpan,
 };
 use rustc_hash::FxBuildHasher;
 
 use crate::heritage::{Context, Heritage};
 use crate::html::write_escaped_str;
 use crate::input::{Source, TemplateInput};
-use crate::integration::{Buffer, impl_everything, write_header};
+use crate::integration::{
+    Buffer, impl_everything, write_content_type_consts, write_header, write_rendered_consts,
+    write_size_hint_cache,
+};
+use crate::minify::{HtmlMinifier, Minify};
 use crate::{CompileError, FileInfo};
 
 pub(crate) fn template_to_string(
@@ -44,7 +51,7 @@ pub(crate) fn template_to_string(
     }?;
 
     if tmpl_kind == TmplKind::Struct {
-        impl_everything(input.ast, buf);
+        impl_everything(input.ast, buf, &format!("{:?}", input.mime_type));
     }
     Ok(size_hint)
 }
@@ -84,6 +91,19 @@ struct Generator<'a, 'h> {
     is_in_filter_block: usize,
     /// Set of called macros we are currently in. Used to prevent (indirect) recursions.
     seen_macros: Vec<(&'a Macro<'a>, Option<FileInfo<'a>>)>,
+    /// Minifies the literal text if `minify = "html"` is used
+    minifier: Option<HtmlMinifier>,
+    /// The whole output of the template, as long as it only consists of literal text
+    constant_text: Option<String>,
+    /// Macros and blocks that were written, used to find unused definitions
+    used: UsedItems<'a>,
+}
+
+/// Definitions that were written at least once, identified by their address
+#[derive(Default)]
+struct UsedItems<'a> {
+    macros: Vec<&'a Macro<'a>>,
+    blocks: Vec<&'a BlockDef<'a>>,
 }
 
 impl<'a, 'h> Generator<'a, 'h> {
@@ -109,6 +129,9 @@ impl<'a, 'h> Generator<'a, 'h> {
             },
             is_in_filter_block,
             seen_macros: Vec::new(),
+            minifier: (input.minify == Minify::Html).then(HtmlMinifier::default),
+            constant_text: (!buf_writable_discard).then(String::new),
+            used: UsedItems::default(),
         }
     }
 
@@ -134,11 +157,35 @@ impl<'a, 'h> Generator<'a, 'h> {
             ) -> rinja::Result<()>\
             where \
                 RinjaW: rinja::helpers::core::fmt::Write + ?rinja::helpers::core::marker::Sized\
-            {\
+            {",
+        );
+        if tmpl_kind == TmplKind::Struct {
+            // the body is generic over the output, so it can write bytes or `fmt::Write`
+            buf.write(
+                "rinja::Template::render_into_sink(\
+                    self,\
+                    &mut rinja::helpers::FmtSink(__rinja_writer),\
+                    __rinja_values,\
+                )\
+            }\
+            fn render_into_sink<RinjaS>(\
+                &self,\
+                __rinja_writer: &mut RinjaS,\
+                __rinja_values: &dyn rinja::Values\
+            ) -> rinja::Result<()>\
+            where \
+                RinjaS: rinja::helpers::Sink + ?rinja::helpers::core::marker::Sized\
+            {",
+            );
+        } else {
+            buf.write("let __rinja_writer = &mut rinja::helpers::FmtSink(__rinja_writer);");
+        }
+        buf.write(
+            "\
                 #[allow(unused_imports)]\
                 use rinja::{\
                     filters::{AutoEscape as _, WriteWritable as _},\
-                    helpers::{ResultConverter as _, core::fmt::Write as _},\
+                    helpers::{InContains as _, ResultConverter as _, core::fmt::Write as _},\
                 };",
         );
 
@@ -171,10 +218,19 @@ impl<'a, 'h> Generator<'a, 'h> {
             buf.write(format_args!(
                 "const SIZE_HINT: rinja::helpers::core::primitive::usize = {size_hint}usize;",
             ));
+            write_size_hint_cache(buf, size_hint);
+            write_content_type_consts(buf, self.input.extension, self.input.mime_type);
+            if let Some(text) = &self.constant_text {
+                write_rendered_consts(buf, text);
+            }
         }
 
         buf.write('}');
 
+        if self.input.config.warn_unused {
+            self.write_unused_warnings(ctx, buf);
+        }
+
         #[cfg(feature = "blocks")]
         for block in self.input.blocks {
             self.impl_block(buf, block)?;
@@ -327,6 +383,56 @@ impl<'a, 'h> Generator<'a, 'h> {
         Ok(())
     }
 
+    /// Emits a warning for every macro that is never called, and every block that is never
+    /// rendered in the template itself.
+    fn write_unused_warnings(&self, ctx: &Context<'a>, buf: &mut Buffer) {
+        let mut unused = Vec::new();
+        for (name, def) in &ctx.macros {
+            if !self
+                .used
+                .macros
+                .iter()
+                .any(|used| std::ptr::eq(*used, *def))
+            {
+                unused.push((
+                    Span::from(def.name),
+                    format!("macro `{name}` is never called"),
+                ));
+            }
+        }
+        // when rendering a single block, the other blocks are skipped on purpose
+        if self.input.block.is_none() {
+            // blocks that are exposed with `blocks = [..]` are rendered on their own
+            #[cfg(feature = "blocks")]
+            let is_exposed = |name: &str| self.input.blocks.iter().any(|block| block.name == name);
+            #[cfg(not(feature = "blocks"))]
+            let is_exposed = |_: &str| false;
+
+            for (name, def) in &ctx.blocks {
+                let used = self
+                    .used
+                    .blocks
+                    .iter()
+                    .any(|used| std::ptr::eq(*used, *def));
+                if !used && !is_exposed(name) {
+                    unused.push((
+                        Span::from(def.name),
+                        format!("block `{name}` is never rendered"),
+                    ));
+                }
+            }
+        }
+        // sort the warnings by their position in the template
+        unused.sort_by_key(|(span, _)| span.offset_from(ctx.parsed.source()));
+
+        for (span, message) in unused {
+            buf.warn(match ctx.file_info_of(span) {
+                Some(info) => format!("{message}{info}"),
+                None => message,
+            });
+        }
+    }
+
     fn is_var_defined(&self, var_name: &str) -> bool {
         self.locals.get(var_name).is_some() || self.input.fields.iter().any(|f| f == var_name)
     }
@@ -355,7 +461,7 @@ const _: () = {
     panic!("unknown cfg!(target_pointer_width)");
 };
 
-/// In here, we inspect in the expression if it is a literal, and if it is, whether it
+/// In here, we inspect in the expression if it is a constant, and if it is, whether it
 /// can be escaped at compile time.
 fn compile_time_escape<'a>(expr: &Expr<'a>, escaper: &str) -> Option<Writable<'a>> {
     // we only optimize for known escapers
@@ -371,38 +477,47 @@ fn compile_time_escape<'a>(expr: &Expr<'a>, escaper: &str) -> Option<Writable<'a
         _ => return None,
     };
 
-    // for now, we only escape strings, chars, numbers, and bools at compile time
-    let value = match *expr {
+    // `|safe` and `|escape` on a constant expression can be resolved at compile time, too
+    let (value, escape) = match *expr {
+        Expr::Filter(Filter {
+            name: name @ ("safe" | "escape" | "e"),
+            ref arguments,
+            ref generics,
+        }) if generics.is_empty() && arguments.len() == 1 => {
+            (const_value(&arguments[0])?, name != "safe")
+        }
+        _ => (const_value(expr)?, true),
+    };
+
+    // escape the un-string-escaped input using the selected escaper
+    Some(Writable::Lit(match output {
+        OutputKind::Text => value,
+        OutputKind::Html if !escape => value,
+        OutputKind::Html => {
+            let mut escaped = String::with_capacity(value.len() + 20);
+            write_escaped_str(&mut escaped, &value).ok()?;
+            match escaped == value {
+                true => value,
+                false => Cow::Owned(escaped),
+            }
+        }
+    }))
+}
+
+/// Returns the un-escaped string representation of a literal or of a constant expression.
+fn const_value<'a>(expr: &Expr<'a>) -> Option<Cow<'a, str>> {
+    Some(match *expr {
         Expr::StrLit(StrLit {
             prefix: None,
             content,
-        }) => {
-            if content.find('\\').is_none() {
-                // if the literal does not contain any backslashes, then it does not need unescaping
-                Cow::Borrowed(content)
-            } else {
-                // the input could be string escaped if it contains any backslashes
-                let input = format!(r#""{content}""#);
-                let input = input.parse().ok()?;
-                let input = syn::parse2::<syn::LitStr>(input).ok()?;
-                Cow::Owned(input.value())
-            }
-        }
+        }) => unescape_str_lit(content)?,
         Expr::CharLit(CharLit {
             prefix: None,
             content,
-        }) => {
-            if content.find('\\').is_none() {
-                // if the literal does not contain any backslashes, then it does not need unescaping
-                Cow::Borrowed(content)
-            } else {
-                // the input could be string escaped if it contains any backslashes
-                let input = format!(r#"'{content}'"#);
-                let input = input.parse().ok()?;
-                let input = syn::parse2::<syn::LitChar>(input).ok()?;
-                Cow::Owned(input.value().to_string())
-            }
-        }
+        }) => match content.find('\\') {
+            None => Cow::Borrowed(content),
+            Some(_) => Cow::Owned(unescape_char_lit(content)?.to_string()),
+        },
         Expr::NumLit(_, value) => {
             enum NumKind {
                 Int(Option<IntKind>),
@@ -456,27 +571,80 @@ fn compile_time_escape<'a>(expr: &Expr<'a>, escaper: &str) -> Option<Writable<'a
         }
         Expr::BoolLit(true) => Cow::Borrowed("true"),
         Expr::BoolLit(false) => Cow::Borrowed("false"),
-        _ => return None,
-    };
+        _ => Cow::Owned(fold::fold_expr(expr)?.to_string()),
+    })
+}
 
-    // escape the un-string-escaped input using the selected escaper
-    Some(Writable::Lit(match output {
-        OutputKind::Text => value,
-        OutputKind::Html => {
-            let mut escaped = String::with_capacity(value.len() + 20);
-            write_escaped_str(&mut escaped, &value).ok()?;
-            match escaped == value {
-                true => value,
-                false => Cow::Owned(escaped),
-            }
-        }
-    }))
+/// Un-escapes the content of a string literal, e.g. `\n` becomes a line break.
+fn unescape_str_lit(content: &str) -> Option<Cow<'_, str>> {
+    if content.find('\\').is_none() {
+        // if the literal does not contain any backslashes, then it does not need unescaping
+        Some(Cow::Borrowed(content))
+    } else {
+        // the input could be string escaped if it contains any backslashes
+        let input = format!(r#""{content}""#);
+        let input = input.parse().ok()?;
+        let input = syn::parse2::<syn::LitStr>(input).ok()?;
+        Some(Cow::Owned(input.value()))
+    }
+}
+
+/// Un-escapes the content of a char literal.
+fn unescape_char_lit(content: &str) -> Option<char> {
+    let mut chars = content.chars();
+    if let (Some(c), None) = (chars.next(), chars.next()) {
+        // a single character does not need unescaping
+        return Some(c);
+    }
+    // the input could be string escaped if it contains any backslashes
+    let input = format!(r#"'{content}'"#);
+    let input = input.parse().ok()?;
+    let input = syn::parse2::<syn::LitChar>(input).ok()?;
+    Some(input.value())
 }
 
 #[derive(Clone, Default)]
 struct LocalMeta {
     refs: Option<String>,
     initialized: bool,
+    /// The keys of the map literal that was assigned to the variable, if any.
+    map_keys: Option<Arc<MapKeys>>,
+    /// The variable was declared with `{% let mut %}`, so assignments update it in place.
+    mutable: bool,
+}
+
+/// The keys of a map literal, in order, and the keys of the map literals they contain.
+///
+/// Since every entry of a `rinja::helpers::Map` has its own type, an attribute access like
+/// `map.key` must be resolved at compile time to the position of `key` in the map.
+#[derive(Debug, Default)]
+struct MapKeys(Vec<(String, Option<Arc<MapKeys>>)>);
+
+impl MapKeys {
+    fn from_entries(entries: &[(&str, WithSpan<'_, Expr<'_>>)]) -> Self {
+        Self(
+            entries
+                .iter()
+                .map(|(key, value)| ((*key).to_owned(), Self::from_expr(value).map(Arc::new)))
+                .collect(),
+        )
+    }
+
+    fn from_expr(expr: &Expr<'_>) -> Option<Self> {
+        match expr {
+            Expr::Map(entries) => Some(Self::from_entries(entries)),
+            Expr::Group(inner) => Self::from_expr(inner),
+            _ => None,
+        }
+    }
+
+    /// Returns the position of `key`, and the keys of its value if it is a map literal.
+    fn find(&self, key: &str) -> Option<(usize, Option<&Arc<MapKeys>>)> {
+        self.0
+            .iter()
+            .enumerate()
+            .find_map(|(index, (k, keys))| (k == key).then_some((index, keys.as_ref())))
+    }
 }
 
 impl LocalMeta {
@@ -484,6 +652,8 @@ impl LocalMeta {
         Self {
             refs: None,
             initialized: true,
+            map_keys: None,
+            mutable: false,
         }
     }
 
@@ -491,8 +661,20 @@ impl LocalMeta {
         Self {
             refs: Some(refs),
             initialized: true,
+            map_keys: None,
+            mutable: false,
         }
     }
+
+    fn with_map_keys(mut self, map_keys: Option<Arc<MapKeys>>) -> Self {
+        self.map_keys = map_keys;
+        self
+    }
+
+    fn mutable(mut self) -> Self {
+        self.mutable = true;
+        self
+    }
 }
 
 struct MapChain<'a> {
@@ -565,6 +747,8 @@ fn is_copyable_within_op(expr: &Expr<'_>, within_op: bool) -> bool {
         | Expr::BinOp(_, _, _) => true,
         Expr::Unary(.., expr) => is_copyable_within_op(expr, true),
         Expr::Range(..) => true,
+        // A closure is passed to filters and functions as is.
+        Expr::Closure { .. } => true,
         // The result of a call likely doesn't need to be borrowed,
         // as in that case the call is more likely to return a
         // reference in the first place then.
@@ -1,4 +1,5 @@
 use std::borrow::Cow;
+use std::sync::Arc;
 
 use parser::node::CondTest;
 use parser::{
@@ -7,8 +8,9 @@ use parser::{
 };
 use quote::quote;
 
+use super::pyformat::{printf_to_rust, str_format_to_rust};
 use super::{
-    DisplayWrap, FILTER_SOURCE, Generator, LocalMeta, TargetIsize, TargetUsize, Writable,
+    DisplayWrap, FILTER_SOURCE, Generator, LocalMeta, MapKeys, TargetIsize, TargetUsize, Writable,
     compile_time_escape, is_copyable, normalize_identifier,
 };
 use crate::heritage::Context;
@@ -40,6 +42,7 @@ impl<'a> Generator<'a, '_> {
             Expr::Var(s) => self.visit_var(buf, s),
             Expr::Path(ref path) => self.visit_path(buf, path),
             Expr::Array(ref elements) => self.visit_array(ctx, buf, elements)?,
+            Expr::Map(ref entries) => self.visit_map(ctx, buf, entries)?,
             Expr::Attr(ref obj, ref attr) => self.visit_attr(ctx, buf, obj, attr)?,
             Expr::Index(ref obj, ref key) => self.visit_index(ctx, buf, obj, key)?,
             Expr::Filter(Filter {
@@ -48,6 +51,11 @@ impl<'a> Generator<'a, '_> {
                 ref generics,
             }) => self.visit_filter(ctx, buf, name, arguments, generics, expr.span())?,
             Expr::Unary(op, ref inner) => self.visit_unary(ctx, buf, op, inner)?,
+            Expr::BinOp("%", ref left, ref right)
+                if matches!(***left, Expr::StrLit(StrLit { prefix: None, .. })) =>
+            {
+                self.visit_printf(ctx, buf, left, right, expr.span())?
+            }
             Expr::BinOp(op, ref left, ref right) => self.visit_binop(ctx, buf, op, left, right)?,
             Expr::Range(op, ref left, ref right) => {
                 self.visit_range(ctx, buf, op, left.as_deref(), right.as_deref())?
@@ -59,7 +67,7 @@ impl<'a> Generator<'a, '_> {
                 ref generics,
             } => self.visit_call(ctx, buf, path, args, generics)?,
             Expr::RustMacro(ref path, args) => self.visit_rust_macro(buf, path, args),
-            Expr::Try(ref expr) => self.visit_try(ctx, buf, expr)?,
+            Expr::Try(ref inner) => self.visit_try(ctx, buf, inner, expr.span())?,
             Expr::Tuple(ref exprs) => self.visit_tuple(ctx, buf, exprs)?,
             Expr::NamedArgument(_, ref expr) => self.visit_named_argument(ctx, buf, expr)?,
             Expr::FilterSource => self.visit_filter_source(buf),
@@ -68,6 +76,15 @@ impl<'a> Generator<'a, '_> {
             Expr::As(ref expr, target) => self.visit_as(ctx, buf, expr, target)?,
             Expr::Concat(ref exprs) => self.visit_concat(ctx, buf, exprs)?,
             Expr::LetCond(ref cond) => self.visit_let_cond(ctx, buf, cond)?,
+            Expr::IfElse {
+                ref then,
+                ref cond,
+                ref otherwise,
+            } => self.visit_if_else(ctx, buf, then, cond, otherwise.as_deref())?,
+            Expr::Closure {
+                ref params,
+                ref body,
+            } => self.visit_closure(ctx, buf, params, body)?,
         })
     }
 
@@ -203,6 +220,107 @@ impl<'a> Generator<'a, '_> {
         }
     }
 
+    /// Writes the condition of an `if` or an inline `then if cond else otherwise` expression,
+    /// binding `target` with `let` if present.
+    pub(super) fn visit_cond_test(
+        &mut self,
+        ctx: &Context<'_>,
+        buf: &mut Buffer,
+        target: Option<&Target<'a>>,
+        expr: &WithSpan<'_, Expr<'a>>,
+    ) -> Result<(), CompileError> {
+        let Some(target) = target else {
+            return self.visit_condition(ctx, buf, expr);
+        };
+
+        let mut expr_buf = Buffer::new();
+        buf.write("let ");
+        // If this is a chain condition, then we need to declare the variable after the
+        // left expression has been handled but before the right expression is handled
+        // but this one should have access to the let-bound variable.
+        match &**expr {
+            Expr::BinOp(op @ ("||" | "&&"), left, right) => {
+                let display_wrap = self.visit_expr_first(ctx, &mut expr_buf, left)?;
+                self.visit_target(buf, true, true, target);
+                self.visit_expr_not_first(ctx, &mut expr_buf, left, display_wrap)?;
+                buf.write(format_args!("= &{expr_buf}"));
+                buf.write(format_args!(" {op} "));
+                self.visit_condition(ctx, buf, right)?;
+            }
+            _ => {
+                let display_wrap = self.visit_expr_first(ctx, &mut expr_buf, expr)?;
+                self.visit_target(buf, true, true, target);
+                self.visit_expr_not_first(ctx, &mut expr_buf, expr, display_wrap)?;
+                buf.write(format_args!("= &{expr_buf}"));
+            }
+        }
+        Ok(())
+    }
+
+    fn visit_if_else(
+        &mut self,
+        ctx: &Context<'_>,
+        buf: &mut Buffer,
+        then: &WithSpan<'_, Expr<'a>>,
+        cond: &WithSpan<'_, CondTest<'a>>,
+        otherwise: Option<&WithSpan<'_, Expr<'a>>>,
+    ) -> Result<DisplayWrap, CompileError> {
+        // Like in `{% if %}`, a branch that is never taken is not generated at all,
+        // so e.g. `x if x is defined` does not reference an undefined `x`.
+        if cond.target.is_none() {
+            match (self.evaluate_inline_condition(&cond.expr), otherwise) {
+                (Some(true), _) => return self.visit_expr(ctx, buf, then),
+                (Some(false), Some(otherwise)) => return self.visit_expr(ctx, buf, otherwise),
+                (Some(false), None) => {
+                    buf.write("rinja::helpers::Empty");
+                    return Ok(DisplayWrap::Unwrapped);
+                }
+                (None, _) => {}
+            }
+        }
+
+        if otherwise.is_none() {
+            buf.write("rinja::helpers::MaybeEmpty(");
+        }
+        // The variables bound by the condition are only visible in the `then` branch.
+        let mut then_buf = Buffer::new();
+        let then_wrap = self.push_locals(|this| {
+            buf.write("if ");
+            this.visit_cond_test(ctx, buf, cond.target.as_ref(), &cond.expr)?;
+            this.visit_expr(ctx, &mut then_buf, then)
+        })?;
+        let Some(otherwise) = otherwise else {
+            buf.write(format_args!(
+                " {{ rinja::helpers::core::option::Option::Some({then_buf}) }} \
+                else {{ rinja::helpers::core::option::Option::None }})",
+            ));
+            return Ok(then_wrap);
+        };
+
+        // The branches are wrapped in an `Either`, so they can have different types.
+        let mut otherwise_buf = Buffer::new();
+        let otherwise_wrap = self.visit_expr(ctx, &mut otherwise_buf, otherwise)?;
+        let mut then = format!("rinja::helpers::Either::Left({then_buf})");
+        let mut otherwise = format!("rinja::helpers::Either::Right({otherwise_buf})");
+        let display_wrap = match (then_wrap, otherwise_wrap) {
+            (DisplayWrap::Wrapped, DisplayWrap::Wrapped) => DisplayWrap::Wrapped,
+            (DisplayWrap::Unwrapped, DisplayWrap::Unwrapped) => DisplayWrap::Unwrapped,
+            // Only the unwrapped branch must be escaped, which is decided at runtime.
+            (DisplayWrap::Wrapped, DisplayWrap::Unwrapped) => {
+                then = format!("rinja::filters::MaybeSafe::Safe({then})");
+                otherwise = format!("rinja::filters::MaybeSafe::NeedsEscaping({otherwise})");
+                DisplayWrap::Unwrapped
+            }
+            (DisplayWrap::Unwrapped, DisplayWrap::Wrapped) => {
+                then = format!("rinja::filters::MaybeSafe::NeedsEscaping({then})");
+                otherwise = format!("rinja::filters::MaybeSafe::Safe({otherwise})");
+                DisplayWrap::Unwrapped
+            }
+        };
+        buf.write(format_args!(" {{ {then} }} else {{ {otherwise} }}"));
+        Ok(display_wrap)
+    }
+
     fn visit_let_cond(
         &mut self,
         ctx: &Context<'_>,
@@ -224,10 +342,13 @@ impl<'a> Generator<'a, '_> {
         ctx: &Context<'_>,
         buf: &mut Buffer,
         expr: &WithSpan<'_, Expr<'a>>,
+        node: Span<'_>,
     ) -> Result<DisplayWrap, CompileError> {
         buf.write("match (");
         self.visit_expr(ctx, buf, expr)?;
-        buf.write(") { res => (&&rinja::helpers::ErrorMarker::of(&res)).rinja_conv_result(res)? }");
+        buf.write(") { res => (&&rinja::helpers::ErrorMarker::of(&res)).rinja_conv_result(res)");
+        self.write_question_mark(ctx, buf, node);
+        buf.write(" }");
         Ok(DisplayWrap::Unwrapped)
     }
 
@@ -261,6 +382,7 @@ impl<'a> Generator<'a, '_> {
             "linebreaksbr" => Self::_visit_linebreaksbr_filter,
             "paragraphbreaks" => Self::_visit_paragraphbreaks_filter,
             "pluralize" => Self::_visit_pluralize_filter,
+            "pyformat" => Self::_visit_pyformat_filter,
             "ref" => Self::_visit_ref_filter,
             "safe" => Self::_visit_safe_filter,
             "urlencode" => Self::_visit_urlencode_filter,
@@ -294,7 +416,8 @@ impl<'a> Generator<'a, '_> {
         self.visit_call_generics(buf, generics);
         buf.write('(');
         self._visit_args(ctx, buf, args)?;
-        buf.write(")?");
+        buf.write(')');
+        self.write_question_mark(ctx, buf, node);
         Ok(DisplayWrap::Unwrapped)
     }
 
@@ -316,7 +439,8 @@ impl<'a> Generator<'a, '_> {
         self.visit_call_generics(buf, generics);
         buf.write('(');
         self._visit_args(ctx, buf, args)?;
-        buf.write(")?");
+        buf.write(')');
+        self.write_question_mark(ctx, buf, node);
         Ok(DisplayWrap::Unwrapped)
     }
 
@@ -419,7 +543,8 @@ impl<'a> Generator<'a, '_> {
                 buf.write(',');
                 self._visit_auto_escaped_arg(ctx, buf, value)?;
             }
-            buf.write(")?");
+            buf.write(')');
+            self.write_question_mark(ctx, buf, node);
         }
         Ok(DisplayWrap::Wrapped)
     }
@@ -474,7 +599,8 @@ impl<'a> Generator<'a, '_> {
         ));
         self._visit_args(ctx, buf, args)?;
         // The input is always HTML escaped, regardless of the selected escaper:
-        buf.write("), rinja::filters::Html)).rinja_auto_escape()?)?");
+        buf.write("), rinja::filters::Html)).rinja_auto_escape()?)");
+        self.write_question_mark(ctx, buf, node);
         // The output is marked as HTML safe, not safe in all contexts:
         Ok(DisplayWrap::Unwrapped)
     }
@@ -532,7 +658,8 @@ impl<'a> Generator<'a, '_> {
         };
         buf.write(format_args!("rinja::filters::{filter}("));
         self._visit_args(ctx, buf, args)?;
-        buf.write(")?");
+        buf.write(')');
+        self.write_question_mark(ctx, buf, node);
         Ok(DisplayWrap::Unwrapped)
     }
 
@@ -548,7 +675,8 @@ impl<'a> Generator<'a, '_> {
         }
         buf.write("rinja::filters::safe(");
         self._visit_args(ctx, buf, args)?;
-        buf.write(format_args!(", {})?", self.input.escaper));
+        buf.write(format_args!(", {})", self.input.escaper));
+        self.write_question_mark(ctx, buf, node);
         Ok(DisplayWrap::Wrapped)
     }
 
@@ -608,7 +736,8 @@ impl<'a> Generator<'a, '_> {
         };
         buf.write("rinja::filters::escape(");
         self._visit_args(ctx, buf, &args[..1])?;
-        buf.write(format_args!(", {escaper})?"));
+        buf.write(format_args!(", {escaper})"));
+        self.write_question_mark(ctx, buf, node);
         Ok(DisplayWrap::Wrapped)
     }
 
@@ -635,6 +764,71 @@ impl<'a> Generator<'a, '_> {
         Err(ctx.generate_error(r#"use filter format like `"a={} b={}"|format(a, b)`"#, node))
     }
 
+    fn _visit_pyformat_filter(
+        &mut self,
+        ctx: &Context<'_>,
+        buf: &mut Buffer,
+        args: &[WithSpan<'_, Expr<'a>>],
+        node: Span<'_>,
+    ) -> Result<DisplayWrap, CompileError> {
+        ensure_filter_has_feature_alloc(ctx, "pyformat", node)?;
+        if let Some((fmt, args)) = args.split_first() {
+            if let Expr::StrLit(StrLit {
+                prefix: None,
+                content,
+            }) = **fmt
+            {
+                let fmt = str_format_to_rust(content, args.len())
+                    .map_err(|err| ctx.generate_error(err, node))?;
+                return self.write_format(ctx, buf, &fmt, args);
+            }
+        }
+        Err(ctx.generate_error(r#"use filter pyformat like `"{} items"|pyformat(n)`"#, node))
+    }
+
+    /// `"%s items" % n` and `"%s of %s" % (a, b)` on a string literal
+    fn visit_printf(
+        &mut self,
+        ctx: &Context<'_>,
+        buf: &mut Buffer,
+        fmt: &WithSpan<'_, Expr<'a>>,
+        args: &WithSpan<'_, Expr<'a>>,
+        node: Span<'_>,
+    ) -> Result<DisplayWrap, CompileError> {
+        let Expr::StrLit(StrLit { content, .. }) = **fmt else {
+            unreachable!("only called for string literals");
+        };
+        if !cfg!(feature = "alloc") {
+            return Err(ctx.generate_error(
+                "the `%` operator on a string literal requires the `alloc` feature to be enabled",
+                node,
+            ));
+        }
+        let args = match **args {
+            Expr::Tuple(ref elements) => elements.as_slice(),
+            _ => std::slice::from_ref(args),
+        };
+        let fmt =
+            printf_to_rust(content, args.len()).map_err(|err| ctx.generate_error(err, node))?;
+        self.write_format(ctx, buf, &fmt, args)
+    }
+
+    fn write_format(
+        &mut self,
+        ctx: &Context<'_>,
+        buf: &mut Buffer,
+        fmt: &str,
+        args: &[WithSpan<'_, Expr<'a>>],
+    ) -> Result<DisplayWrap, CompileError> {
+        buf.write(format_args!("rinja::helpers::alloc::format!(\"{fmt}\""));
+        if !args.is_empty() {
+            buf.write(',');
+            self._visit_args(ctx, buf, args)?;
+        }
+        buf.write(')');
+        Ok(DisplayWrap::Unwrapped)
+    }
+
     fn _visit_fmt_filter(
         &mut self,
         ctx: &Context<'_>,
@@ -662,7 +856,7 @@ impl<'a> Generator<'a, '_> {
         ctx: &Context<'_>,
         buf: &mut Buffer,
         args: &[WithSpan<'_, Expr<'a>>],
-        _node: Span<'_>,
+        node: Span<'_>,
     ) -> Result<DisplayWrap, CompileError> {
         buf.write("rinja::filters::join((&");
         for (i, arg) in args.iter().enumerate() {
@@ -674,7 +868,8 @@ impl<'a> Generator<'a, '_> {
                 buf.write(").into_iter()");
             }
         }
-        buf.write(")?");
+        buf.write(')');
+        self.write_question_mark(ctx, buf, node);
         Ok(DisplayWrap::Unwrapped)
     }
 
@@ -812,17 +1007,72 @@ impl<'a> Generator<'a, '_> {
                 } else if attr.name == "last" {
                     buf.write("_loop_item.last");
                     return Ok(DisplayWrap::Unwrapped);
+                } else if matches!(attr.name, "length" | "revindex" | "revindex0") {
+                    // Jinja's loop variables that need the length of the iterator
+                    return Err(ctx.generate_error(
+                        format_args!(
+                            "`loop.{}` is not supported, because the length of the iterator is \
+                             not known in advance; you can compute it before the loop, e.g. \
+                             with `{{% let length = items.len() %}}`",
+                            attr.name,
+                        ),
+                        obj.span(),
+                    ));
+                } else if matches!(attr.name, "previtem" | "nextitem") {
+                    return Err(ctx.generate_error(
+                        format_args!(
+                            "`loop.{}` is not supported; you can index the iterated items with \
+                             `loop.index0` instead",
+                            attr.name,
+                        ),
+                        obj.span(),
+                    ));
                 } else {
                     return Err(ctx.generate_error("unknown loop variable", obj.span()));
                 }
             }
         }
+        if attr.generics.is_empty() {
+            if let Some(map_keys) = self.map_keys(obj) {
+                let Some((index, _)) = map_keys.find(attr.name) else {
+                    return Err(ctx.generate_error(
+                        format_args!("map has no key `{}`", attr.name),
+                        obj.span(),
+                    ));
+                };
+                // `Map(MapEntry(key, value, next))`
+                self.visit_expr(ctx, buf, obj)?;
+                buf.write(".0");
+                for _ in 0..index {
+                    buf.write(".2");
+                }
+                buf.write(".1");
+                return Ok(DisplayWrap::Unwrapped);
+            }
+        }
         self.visit_expr(ctx, buf, obj)?;
         buf.write(format_args!(".{}", normalize_identifier(attr.name)));
         self.visit_call_generics(buf, &attr.generics);
         Ok(DisplayWrap::Unwrapped)
     }
 
+    /// Returns the keys of `expr` if it is known to be a map literal.
+    pub(super) fn map_keys(&self, expr: &Expr<'_>) -> Option<Arc<MapKeys>> {
+        match expr {
+            Expr::Map(entries) => Some(Arc::new(MapKeys::from_entries(entries))),
+            Expr::Group(inner) => self.map_keys(inner),
+            Expr::Var(name) => self
+                .locals
+                .get(normalize_identifier(name))?
+                .map_keys
+                .clone(),
+            Expr::Attr(obj, attr) if attr.generics.is_empty() => {
+                self.map_keys(obj)?.find(attr.name)?.1.cloned()
+            }
+            _ => None,
+        }
+    }
+
     fn visit_call_generics(&mut self, buf: &mut Buffer, generics: &[WithSpan<'_, TyGenerics<'_>>]) {
         if generics.is_empty() {
             return;
@@ -943,6 +1193,12 @@ impl<'a> Generator<'a, '_> {
                         Some(resolved) => buf.write(resolved),
                         None => buf.write(format_args!("self.{}", normalize_identifier(name))),
                     },
+                    // A method of `rinja::helpers::Map`, like `len()`, and not one of its entries.
+                    Expr::Attr(obj, attr) if self.map_keys(obj).is_some() => {
+                        self.visit_expr(ctx, buf, obj)?;
+                        buf.write(format_args!(".{}", normalize_identifier(attr.name)));
+                        self.visit_call_generics(buf, &attr.generics);
+                    }
                     _ => {
                         self.visit_expr(ctx, buf, left)?;
                     }
@@ -994,6 +1250,18 @@ impl<'a> Generator<'a, '_> {
         left: &WithSpan<'_, Expr<'a>>,
         right: &WithSpan<'_, Expr<'a>>,
     ) -> Result<DisplayWrap, CompileError> {
+        if let "in" | "not in" = op {
+            if op == "not in" {
+                buf.write('!');
+            }
+            buf.write("(&&rinja::helpers::In(&(");
+            self.visit_expr(ctx, buf, right)?;
+            buf.write("), &(");
+            self.visit_expr(ctx, buf, left)?;
+            buf.write("))).rinja_contains()");
+            return Ok(DisplayWrap::Unwrapped);
+        }
+
         self.visit_expr(ctx, buf, left)?;
         buf.write(format_args!(" {op} "));
         self.visit_expr(ctx, buf, right)?;
@@ -1057,6 +1325,61 @@ impl<'a> Generator<'a, '_> {
         Ok(DisplayWrap::Unwrapped)
     }
 
+    fn visit_map(
+        &mut self,
+        ctx: &Context<'_>,
+        buf: &mut Buffer,
+        entries: &[(&str, WithSpan<'_, Expr<'a>>)],
+    ) -> Result<DisplayWrap, CompileError> {
+        buf.write("rinja::helpers::Map(");
+        for (key, value) in entries {
+            buf.write(format_args!("rinja::helpers::MapEntry(\"{key}\","));
+            // Only places like variables and fields are borrowed. Everything else is stored by
+            // value: before Rust 1.89 a temporary borrowed in the arguments of the `MapEntry`
+            // constructor would not live as long as the map.
+            if is_place(value) {
+                buf.write("&(");
+                self.visit_expr(ctx, buf, value)?;
+                buf.write(')');
+            } else {
+                self.visit_expr(ctx, buf, value)?;
+            }
+            buf.write(',');
+        }
+        buf.write("()");
+        for _ in entries {
+            buf.write(')');
+        }
+        buf.write(')');
+        Ok(DisplayWrap::Unwrapped)
+    }
+
+    fn visit_closure(
+        &mut self,
+        ctx: &Context<'_>,
+        buf: &mut Buffer,
+        params: &[&'a str],
+        body: &WithSpan<'_, Expr<'a>>,
+    ) -> Result<DisplayWrap, CompileError> {
+        self.push_locals(|this| {
+            buf.write('|');
+            for (i, param) in params.iter().copied().enumerate() {
+                if i > 0 {
+                    buf.write(',');
+                }
+                let param = normalize_identifier(param);
+                if param != "_" {
+                    this.locals
+                        .insert(Cow::Borrowed(param), LocalMeta::initialized());
+                }
+                buf.write(param);
+            }
+            buf.write("| ");
+            this.visit_expr(ctx, buf, body)
+        })?;
+        Ok(DisplayWrap::Unwrapped)
+    }
+
     fn visit_path(&mut self, buf: &mut Buffer, path: &[&str]) -> DisplayWrap {
         for (i, part) in path.iter().copied().enumerate() {
             if i > 0 {
@@ -1226,6 +1549,17 @@ impl<'a> Generator<'a, '_> {
     }
 }
 
+/// Returns `true` if `expr` is generated as a place, i.e. a variable or one of its fields, so
+/// borrowing it does not create a temporary.
+fn is_place(expr: &Expr<'_>) -> bool {
+    match expr {
+        // `loop.index` etc. are computed values.
+        Expr::Var(name) => *name != "loop",
+        Expr::Attr(obj, _) | Expr::Group(obj) => is_place(obj),
+        _ => false,
+    }
+}
+
 fn ensure_filter_has_feature_alloc(
     ctx: &Context<'_>,
     name: &str,
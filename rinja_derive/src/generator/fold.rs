@@ -0,0 +1,297 @@
+//! Compile-time evaluation of constant expressions.
+//!
+//! Expressions that only consist of literals, operators on literals, concatenations and a few
+//! well-known filters can be evaluated while generating the code. The result is emitted as
+//! part of the adjacent literal text, or used to prune branches that can never be taken.
+
+use std::borrow::Cow;
+use std::fmt;
+
+use parser::{CharLit, Expr, Filter, FloatKind, IntKind, Num, StrLit};
+
+use super::{TargetIsize, TargetUsize, unescape_char_lit, unescape_str_lit};
+
+/// The value of an expression that could be evaluated at compile time.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Const<'a> {
+    Bool(bool),
+    Char(char),
+    Str(Cow<'a, str>),
+    /// An integer, `None` if the literal was unsuffixed, i.e. an `i32`.
+    Int(i128, Option<IntKind>),
+    /// A float, `None` if the literal was unsuffixed, i.e. an `f64`.
+    Float(f64, Option<FloatKind>),
+}
+
+impl fmt::Display for Const<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Const::Bool(value) => value.fmt(f),
+            Const::Char(value) => value.fmt(f),
+            Const::Str(value) => value.fmt(f),
+            Const::Int(value, _) => value.fmt(f),
+            Const::Float(value, Some(FloatKind::F32)) => (*value as f32).fmt(f),
+            Const::Float(value, _) => value.fmt(f),
+        }
+    }
+}
+
+/// Tries to evaluate `expr` at compile time.
+///
+/// Returns `None` if the expression is not constant, or if its evaluation would not be
+/// well-defined, e.g. an integer overflow or an operation on mismatched types. In that case
+/// the generated code is left for `rustc` to check.
+pub(crate) fn fold_expr<'a>(expr: &Expr<'a>) -> Option<Const<'a>> {
+    match *expr {
+        Expr::BoolLit(value) => Some(Const::Bool(value)),
+        Expr::CharLit(CharLit {
+            prefix: None,
+            content,
+        }) => unescape_char_lit(content).map(Const::Char),
+        Expr::StrLit(StrLit {
+            prefix: None,
+            content,
+        }) => unescape_str_lit(content).map(Const::Str),
+        Expr::NumLit(_, num) => fold_num(num),
+        Expr::Group(ref inner) => fold_expr(inner),
+        Expr::Unary(op, ref inner) => fold_unary(op, fold_expr(inner)?),
+        Expr::BinOp(op, ref left, ref right) => {
+            // `&&` and `||` are short-circuiting, the right side may not be evaluated at all
+            match (op, fold_expr(left)?) {
+                ("&&", Const::Bool(false)) => Some(Const::Bool(false)),
+                ("||", Const::Bool(true)) => Some(Const::Bool(true)),
+                ("&&" | "||", Const::Bool(_)) => match fold_expr(right)? {
+                    right @ Const::Bool(_) => Some(right),
+                    _ => None,
+                },
+                (op, left) => fold_binop(op, left, fold_expr(right)?),
+            }
+        }
+        Expr::IfElse {
+            ref then,
+            ref cond,
+            ref otherwise,
+        } if cond.target.is_none() => {
+            let Const::Bool(cond) = fold_expr(&cond.expr)? else {
+                return None;
+            };
+            // like in the generated code, the branch that is not taken does not matter
+            match (cond, otherwise) {
+                (true, _) => fold_expr(then),
+                (false, Some(otherwise)) => fold_expr(otherwise),
+                (false, None) => Some(Const::Str(Cow::Borrowed(""))),
+            }
+        }
+        Expr::Concat(ref exprs) => {
+            let mut result = String::new();
+            for expr in exprs {
+                fold_display(expr, &mut result)?;
+            }
+            Some(Const::Str(Cow::Owned(result)))
+        }
+        Expr::Filter(Filter {
+            name,
+            ref arguments,
+            ref generics,
+        }) if generics.is_empty() && arguments.len() == 1 => {
+            let value = fold_expr(&arguments[0])?;
+            let value = match value {
+                Const::Str(value) => value,
+                value => Cow::Owned(value.to_string()),
+            };
+            let value = match name {
+                "capitalize" => {
+                    let mut chars = value.chars();
+                    match chars.next() {
+                        Some(c) => {
+                            let mut result = String::with_capacity(value.len());
+                            result.extend(c.to_uppercase());
+                            result.push_str(&chars.as_str().to_lowercase());
+                            result
+                        }
+                        None => String::new(),
+                    }
+                }
+                "lower" | "lowercase" => value.to_lowercase(),
+                "upper" | "uppercase" => value.to_uppercase(),
+                "trim" => match value {
+                    Cow::Borrowed(value) => return Some(Const::Str(Cow::Borrowed(value.trim()))),
+                    Cow::Owned(value) => value.trim().to_owned(),
+                },
+                _ => return None,
+            };
+            Some(Const::Str(Cow::Owned(value)))
+        }
+        _ => None,
+    }
+}
+
+fn fold_display(expr: &Expr<'_>, dest: &mut String) -> Option<()> {
+    use std::fmt::Write;
+
+    write!(dest, "{}", fold_expr(expr)?).ok()
+}
+
+fn fold_num(num: Num<'_>) -> Option<Const<'_>> {
+    let strip = |value: &str| value.chars().filter(|&c| c != '_').collect::<String>();
+    match num {
+        Num::Int(value, kind) => {
+            let value = strip(value).parse::<i128>().ok()?;
+            int_in_range(value, kind).then_some(Const::Int(value, kind))
+        }
+        Num::Float(value, kind @ (None | Some(FloatKind::F64))) => {
+            Some(Const::Float(strip(value).parse::<f64>().ok()?, kind))
+        }
+        Num::Float(value, kind @ Some(FloatKind::F32)) => {
+            Some(Const::Float(strip(value).parse::<f32>().ok()?.into(), kind))
+        }
+        // FIXME: implement once `f16` and `f128` are available
+        Num::Float(_, Some(FloatKind::F16 | FloatKind::F128)) => None,
+    }
+}
+
+fn fold_unary<'a>(op: &str, value: Const<'a>) -> Option<Const<'a>> {
+    match (op, value) {
+        ("!", Const::Bool(value)) => Some(Const::Bool(!value)),
+        ("-", Const::Int(value, kind)) if !is_unsigned(kind) => {
+            let value = value.checked_neg()?;
+            int_in_range(value, kind).then_some(Const::Int(value, kind))
+        }
+        ("-", Const::Float(value, kind)) => Some(Const::Float(-value, kind)),
+        _ => None,
+    }
+}
+
+fn fold_binop<'a>(op: &str, left: Const<'a>, right: Const<'a>) -> Option<Const<'a>> {
+    use std::cmp::Ordering;
+
+    // `in` and `not in` on strings search for a substring
+    if let "in" | "not in" = op {
+        let Const::Str(haystack) = right else {
+            return None;
+        };
+        let found = match left {
+            Const::Str(needle) => haystack.contains(&*needle),
+            Const::Char(needle) => haystack.contains(needle),
+            _ => return None,
+        };
+        return Some(Const::Bool(found == (op == "in")));
+    }
+
+    // the comparison operators work on any two values of the same type
+    let ordering = match (&left, &right) {
+        (Const::Bool(l), Const::Bool(r)) => l.partial_cmp(r),
+        (Const::Char(l), Const::Char(r)) => l.partial_cmp(r),
+        (Const::Str(l), Const::Str(r)) => l.partial_cmp(r),
+        (&Const::Int(l, lk), &Const::Int(r, rk)) => {
+            unify(lk, rk)?;
+            l.partial_cmp(&r)
+        }
+        (&Const::Float(l, lk), &Const::Float(r, rk)) => {
+            unify(lk, rk)?;
+            l.partial_cmp(&r)
+        }
+        _ => return None,
+    };
+    let compared = match op {
+        "==" => Some(ordering == Some(Ordering::Equal)),
+        "!=" => Some(ordering != Some(Ordering::Equal)),
+        "<" => Some(ordering == Some(Ordering::Less)),
+        "<=" => Some(matches!(ordering, Some(Ordering::Less | Ordering::Equal))),
+        ">" => Some(ordering == Some(Ordering::Greater)),
+        ">=" => Some(matches!(
+            ordering,
+            Some(Ordering::Greater | Ordering::Equal)
+        )),
+        _ => None,
+    };
+    if let Some(compared) = compared {
+        return Some(Const::Bool(compared));
+    }
+
+    match (left, right) {
+        (Const::Int(l, lk), Const::Int(r, rk)) => {
+            let kind = unify(lk, rk)?;
+            if !int_in_range(l, kind) || !int_in_range(r, kind) {
+                return None;
+            }
+            let value = match op {
+                "+" => l.checked_add(r)?,
+                "-" => l.checked_sub(r)?,
+                "*" => l.checked_mul(r)?,
+                "/" => l.checked_div(r)?,
+                "%" => l.checked_rem(r)?,
+                _ => return None,
+            };
+            int_in_range(value, kind).then_some(Const::Int(value, kind))
+        }
+        (Const::Float(l, lk), Const::Float(r, rk)) => {
+            let kind = unify(lk, rk)?;
+            let value = if kind == Some(FloatKind::F32) {
+                let (l, r) = (l as f32, r as f32);
+                f64::from(match op {
+                    "+" => l + r,
+                    "-" => l - r,
+                    "*" => l * r,
+                    "/" => l / r,
+                    _ => return None,
+                })
+            } else {
+                match op {
+                    "+" => l + r,
+                    "-" => l - r,
+                    "*" => l * r,
+                    "/" => l / r,
+                    _ => return None,
+                }
+            };
+            Some(Const::Float(value, kind))
+        }
+        _ => None,
+    }
+}
+
+/// Unsuffixed literals take the type of the other operand, suffixed literals must match.
+fn unify<T: PartialEq>(left: Option<T>, right: Option<T>) -> Option<Option<T>> {
+    match (left, right) {
+        (Some(l), Some(r)) => (l == r).then_some(Some(l)),
+        (Some(kind), None) | (None, Some(kind)) => Some(Some(kind)),
+        (None, None) => Some(None),
+    }
+}
+
+fn is_unsigned(kind: Option<IntKind>) -> bool {
+    matches!(
+        kind,
+        Some(
+            IntKind::U8
+                | IntKind::U16
+                | IntKind::U32
+                | IntKind::U64
+                | IntKind::U128
+                | IntKind::Usize
+        )
+    )
+}
+
+fn int_in_range(value: i128, kind: Option<IntKind>) -> bool {
+    fn check<T: TryFrom<i128>>(value: i128) -> bool {
+        T::try_from(value).is_ok()
+    }
+
+    match kind {
+        Some(IntKind::I8) => check::<i8>(value),
+        Some(IntKind::I16) => check::<i16>(value),
+        // unsuffixed integer literals default to `i32`
+        Some(IntKind::I32) | None => check::<i32>(value),
+        Some(IntKind::I64) => check::<i64>(value),
+        Some(IntKind::I128) => true,
+        Some(IntKind::Isize) => check::<TargetIsize>(value),
+        Some(IntKind::U8) => check::<u8>(value),
+        Some(IntKind::U16) => check::<u16>(value),
+        Some(IntKind::U32) => check::<u32>(value),
+        Some(IntKind::U64) => check::<u64>(value),
+        Some(IntKind::U128) => check::<u128>(value),
+        Some(IntKind::Usize) => check::<TargetUsize>(value),
+    }
+}
@@ -4,12 +4,13 @@ use std::fmt::{self, Write};
 use std::mem;
 
 use parser::node::{
-    Call, Comment, Cond, CondTest, FilterBlock, If, Include, Let, Lit, Loop, Macro, Match,
-    Whitespace, Ws,
+    Call, Comment, Cond, CondTest, FilterBlock, If, Include, Let, LetValueOrBlock, Lit, Loop,
+    Macro, Match, While, Whitespace, With, Ws,
 };
 use parser::{Expr, Filter, Node, Span, Target, WithSpan};
 use rustc_hash::FxBuildHasher;
 
+use super::fold::{Const, fold_expr};
 use super::{
     DisplayWrap, FILTER_SOURCE, Generator, LocalMeta, MapChain, compile_time_escape, is_copyable,
     normalize_identifier,
@@ -17,7 +18,8 @@ use super::{
 use crate::generator::Writable;
 use crate::heritage::{Context, Heritage};
 use crate::integration::Buffer;
-use crate::{CompileError, FileInfo, fmt_left, fmt_right};
+use crate::minify::HtmlMinifier;
+use crate::{CompileError, ErrorInfo, FileInfo, fmt_left, fmt_right, generate_error_info};
 
 impl<'a> Generator<'a, '_> {
     pub(super) fn impl_template_inner(
@@ -36,7 +38,7 @@ impl<'a> Generator<'a, '_> {
         Ok(size_hint)
     }
 
-    fn push_locals<T, F>(&mut self, callback: F) -> Result<T, CompileError>
+    pub(super) fn push_locals<T, F>(&mut self, callback: F) -> Result<T, CompileError>
     where
         F: FnOnce(&mut Self) -> Result<T, CompileError>,
     {
@@ -57,6 +59,7 @@ impl<'a> Generator<'a, '_> {
         self.locals.scopes.push(HashMap::default());
 
         let buf_writable = mem::take(&mut self.buf_writable);
+        let used = mem::take(&mut self.used);
         let locals = mem::replace(&mut self.locals, MapChain::new_empty());
 
         let mut child = Generator::new(
@@ -68,10 +71,14 @@ impl<'a> Generator<'a, '_> {
             self.is_in_filter_block,
         );
         child.buf_writable = buf_writable;
+        child.used = used;
+        child.minifier = self.minifier;
         let res = callback(&mut child);
         Generator {
             locals: self.locals,
             buf_writable: self.buf_writable,
+            used: self.used,
+            minifier: self.minifier,
             ..
         } = child;
 
@@ -88,6 +95,18 @@ impl<'a> Generator<'a, '_> {
     ) -> Result<usize, CompileError> {
         let mut size_hint = 0;
         for n in nodes {
+            if !matches!(
+                n,
+                Node::Lit(_)
+                    | Node::Comment(_)
+                    | Node::Expr(..)
+                    | Node::Raw(_)
+                    | Node::Macro(_)
+                    | Node::Import(_)
+            ) {
+                // only literals and expressions that are known at compile time are constant
+                self.constant_text = None;
+            }
             match *n {
                 Node::Lit(ref lit) => {
                     self.write_lit(lit);
@@ -110,6 +129,12 @@ impl<'a> Generator<'a, '_> {
                 Node::Loop(ref loop_block) => {
                     size_hint += self.write_loop(ctx, buf, loop_block)?;
                 }
+                Node::While(ref while_block) => {
+                    size_hint += self.write_while(ctx, buf, while_block)?;
+                }
+                Node::With(ref with) => {
+                    size_hint += self.write_with(ctx, buf, with)?;
+                }
                 Node::BlockDef(ref b) => {
                     size_hint +=
                         self.write_block(ctx, buf, Some(b.name), Ws(b.ws1.0, b.ws2.1), b.span())?;
@@ -181,6 +206,16 @@ impl<'a> Generator<'a, '_> {
         Ok(size_hint)
     }
 
+    /// Evaluates the condition of an inline `then if cond else otherwise` expression at compile
+    /// time, if possible.
+    pub(super) fn evaluate_inline_condition(&self, expr: &WithSpan<'a, Expr<'a>>) -> Option<bool> {
+        match self.evaluate_condition(expr.clone(), &mut true).0 {
+            EvaluatedResult::AlwaysTrue => Some(true),
+            EvaluatedResult::AlwaysFalse => Some(false),
+            EvaluatedResult::Unknown => None,
+        }
+    }
+
     fn evaluate_condition(
         &self,
         expr: WithSpan<'a, Expr<'a>>,
@@ -188,6 +223,24 @@ impl<'a> Generator<'a, '_> {
     ) -> (EvaluatedResult, WithSpan<'a, Expr<'a>>) {
         let (expr, span) = expr.deconstruct();
 
+        // conditions like `{% if 1 + 1 == 2 %}` can be evaluated at compile time,
+        // groups are handled below to keep their parentheses
+        match fold_expr(&expr).filter(|_| !matches!(expr, Expr::Group(_))) {
+            Some(Const::Bool(true)) => {
+                return (
+                    EvaluatedResult::AlwaysTrue,
+                    WithSpan::new(Expr::BoolLit(true), ""),
+                );
+            }
+            Some(Const::Bool(false)) => {
+                return (
+                    EvaluatedResult::AlwaysFalse,
+                    WithSpan::new(Expr::BoolLit(false), ""),
+                );
+            }
+            _ => {}
+        }
+
         match expr {
             Expr::NumLit(_, _)
             | Expr::StrLit(_)
@@ -195,6 +248,8 @@ impl<'a> Generator<'a, '_> {
             | Expr::Var(_)
             | Expr::Path(_)
             | Expr::Array(_)
+            | Expr::Map(_)
+            | Expr::Closure { .. }
             | Expr::Attr(_, _)
             | Expr::Index(_, _)
             | Expr::Filter(_)
@@ -207,7 +262,8 @@ impl<'a> Generator<'a, '_> {
             | Expr::FilterSource
             | Expr::As(_, _)
             | Expr::Concat(_)
-            | Expr::LetCond(_) => {
+            | Expr::LetCond(_)
+            | Expr::IfElse { .. } => {
                 *only_contains_is_defined = false;
                 (EvaluatedResult::Unknown, WithSpan::new(expr, span))
             }
@@ -350,6 +406,9 @@ impl<'a> Generator<'a, '_> {
             self.handle_ws(ws_before);
         }
 
+        // Every branch starts with the same minifier state.
+        let minifier = self.minifier;
+        let mut branch_minifiers = Vec::new();
         let mut iter = conds.conds.iter().enumerate().peekable();
         while let Some((pos, cond_info)) = iter.next() {
             let cond = cond_info.cond;
@@ -378,33 +437,8 @@ impl<'a> Generator<'a, '_> {
                         has_else = true;
                     }
 
-                    if let Some(target) = target {
-                        let mut expr_buf = Buffer::new();
-                        buf.write("let ");
-                        // If this is a chain condition, then we need to declare the variable after the
-                        // left expression has been handled but before the right expression is handled
-                        // but this one should have access to the let-bound variable.
-                        match &**expr {
-                            Expr::BinOp(op @ ("||" | "&&"), ref left, ref right) => {
-                                let display_wrap =
-                                    this.visit_expr_first(ctx, &mut expr_buf, left)?;
-                                this.visit_target(buf, true, true, target);
-                                this.visit_expr_not_first(ctx, &mut expr_buf, left, display_wrap)?;
-                                buf.write(format_args!("= &{expr_buf}"));
-                                buf.write(format_args!(" {op} "));
-                                this.visit_condition(ctx, buf, right)?;
-                            }
-                            _ => {
-                                let display_wrap =
-                                    this.visit_expr_first(ctx, &mut expr_buf, expr)?;
-                                this.visit_target(buf, true, true, target);
-                                this.visit_expr_not_first(ctx, &mut expr_buf, expr, display_wrap)?;
-                                buf.write(format_args!("= &{expr_buf}"));
-                            }
-                        }
-                        buf.write("{");
-                    } else if cond_info.generate_condition {
-                        this.visit_condition(ctx, buf, expr)?;
+                    if target.is_some() || cond_info.generate_condition {
+                        this.visit_cond_test(ctx, buf, target.as_ref(), expr)?;
                         buf.write('{');
                     }
                 } else if pos != 0 {
@@ -412,10 +446,12 @@ impl<'a> Generator<'a, '_> {
                     has_else = true;
                 }
 
+                this.minifier = minifier;
                 if cond_info.generate_content {
                     arm_size += this.handle(ctx, &cond.nodes, buf, AstLevel::Nested)?;
                 }
                 arm_sizes.push(arm_size);
+                branch_minifiers.push(this.minifier);
 
                 if let Some((_, cond_info)) = iter.peek() {
                     let cond = cond_info.cond;
@@ -426,7 +462,12 @@ impl<'a> Generator<'a, '_> {
                     if let Some(ws_after) = conds.ws_after {
                         this.handle_ws(ws_after);
                     }
-                    this.handle_ws(if_.ws);
+                    this.flush_ws(if_.ws);
+                    if !has_else {
+                        branch_minifiers.push(minifier);
+                    }
+                    this.minifier = join_minifiers(mem::take(&mut branch_minifiers));
+                    this.prepare_ws(if_.ws);
                     flushed += this.write_buf_writable(ctx, buf)?;
                 }
                 Ok(0)
@@ -462,8 +503,11 @@ impl<'a> Generator<'a, '_> {
         let mut arm_sizes = Vec::new();
 
         let expr_code = self.visit_expr_root(ctx, expr)?;
+        let expr_code = self.locate_expr(ctx, buf, expr.span(), expr_code);
         buf.write(format_args!("match &{expr_code} {{"));
 
+        let minifier = self.minifier;
+        let mut arm_minifiers = Vec::new();
         let mut arm_size = 0;
         let mut iter = arms.iter().enumerate().peekable();
         while let Some((i, arm)) = iter.next() {
@@ -480,7 +524,9 @@ impl<'a> Generator<'a, '_> {
                 }
                 buf.write(" => {");
 
+                this.minifier = minifier;
                 arm_size = this.handle(ctx, &arm.nodes, buf, AstLevel::Nested)?;
+                arm_minifiers.push(this.minifier);
 
                 if let Some((_, arm)) = iter.peek() {
                     this.handle_ws(arm.ws);
@@ -488,7 +534,9 @@ impl<'a> Generator<'a, '_> {
 
                     buf.write('}');
                 } else {
-                    this.handle_ws(ws2);
+                    this.flush_ws(ws2);
+                    this.minifier = join_minifiers(mem::take(&mut arm_minifiers));
+                    this.prepare_ws(ws2);
                     arm_sizes.push(arm_size + this.write_buf_writable(ctx, buf)?);
                     buf.write('}');
                 }
@@ -522,6 +570,12 @@ impl<'a> Generator<'a, '_> {
             match &*loop_block.iter {
                 Expr::Range(_, _, _) => buf.write(format_args!("let _iter = {expr_code};")),
                 Expr::Array(..) => buf.write(format_args!("let _iter = {expr_code}.iter();")),
+                // The map has to outlive the iterator, which borrows it.
+                Expr::Map(..) => {
+                    buf.write(format_args!(
+                        "let _map = {expr_code}; let _iter = _map.iter();"
+                    ));
+                }
                 // If `iter` is a call then we assume it's something that returns
                 // an iterator. If not then the user can explicitly add the needed
                 // call without issues.
@@ -552,6 +606,9 @@ impl<'a> Generator<'a, '_> {
                 })?;
             }
 
+            // The body can be repeated, and the `else` block replaces it.
+            let minifier = this.minifier;
+            let mut branch_minifiers = vec![minifier];
             let size_hint1 = this.push_locals(|this| {
                 buf.write("for (");
                 this.visit_target(buf, true, true, &loop_block.var);
@@ -560,9 +617,14 @@ impl<'a> Generator<'a, '_> {
                 if has_else_nodes {
                     buf.write("_did_loop = true;");
                 }
-                let mut size_hint1 = this.handle(ctx, &loop_block.body, buf, AstLevel::Nested)?;
-                this.handle_ws(loop_block.ws2);
-                size_hint1 += this.write_buf_writable(ctx, buf)?;
+                let size_hint1 = this.write_repeated(buf, |this, buf| {
+                    let mut size_hint =
+                        this.handle(ctx, &loop_block.body, buf, AstLevel::Nested)?;
+                    this.handle_ws(loop_block.ws2);
+                    size_hint += this.write_buf_writable(ctx, buf)?;
+                    Ok(size_hint)
+                })?;
+                branch_minifiers.push(this.minifier);
                 Ok(size_hint1)
             })?;
             buf.write('}');
@@ -571,15 +633,21 @@ impl<'a> Generator<'a, '_> {
             if has_else_nodes {
                 buf.write("if !_did_loop {");
                 size_hint2 = this.push_locals(|this| {
+                    this.minifier = minifier;
                     let mut size_hint =
                         this.handle(ctx, &loop_block.else_nodes, buf, AstLevel::Nested)?;
-                    this.handle_ws(loop_block.ws3);
+                    branch_minifiers.push(this.minifier);
+                    this.flush_ws(loop_block.ws3);
+                    this.minifier = join_minifiers(mem::take(&mut branch_minifiers));
+                    this.prepare_ws(loop_block.ws3);
                     size_hint += this.write_buf_writable(ctx, buf)?;
                     Ok(size_hint)
                 })?;
                 buf.write('}');
             } else {
-                this.handle_ws(loop_block.ws3);
+                this.flush_ws(loop_block.ws3);
+                this.minifier = join_minifiers(branch_minifiers);
+                this.prepare_ws(loop_block.ws3);
                 size_hint2 = this.write_buf_writable(ctx, buf)?;
             }
 
@@ -588,6 +656,61 @@ impl<'a> Generator<'a, '_> {
         })
     }
 
+    fn write_while(
+        &mut self,
+        ctx: &Context<'a>,
+        buf: &mut Buffer,
+        while_block: &'a WithSpan<'_, While<'_>>,
+    ) -> Result<usize, CompileError> {
+        self.handle_ws(while_block.ws1);
+        let flushed = self.write_buf_writable(ctx, buf)?;
+        let minifier = self.minifier;
+        let size_hint = self.push_locals(|this| {
+            let CondTest { target, expr, .. } = &*while_block.cond;
+            buf.write("while ");
+            this.visit_cond_test(ctx, buf, target.as_ref(), expr)?;
+            buf.write('{');
+            let size_hint = this.write_repeated(buf, |this, buf| {
+                let mut size_hint = this.handle(ctx, &while_block.nodes, buf, AstLevel::Nested)?;
+                this.handle_ws(Ws(while_block.ws2.0, None));
+                size_hint += this.write_buf_writable(ctx, buf)?;
+                Ok(size_hint)
+            })?;
+            // the body can be repeated any number of times
+            this.minifier = join_minifiers([minifier, this.minifier]);
+            buf.write('}');
+            Ok(size_hint)
+        })?;
+        self.handle_ws(Ws(None, while_block.ws2.1));
+        Ok(flushed + size_hint * 3 / 2)
+    }
+
+    /// Writes the body of a loop with `write_body`
+    ///
+    /// The body can be repeated, so its literal text is minified from a state that is valid
+    /// both before the first iteration and after any other one. If the body ends in a state it
+    /// was not minified for, it is written again, starting in the joined state.
+    fn write_repeated(
+        &mut self,
+        buf: &mut Buffer,
+        mut write_body: impl FnMut(&mut Self, &mut Buffer) -> Result<usize, CompileError>,
+    ) -> Result<usize, CompileError> {
+        let (next_ws, skip_ws) = (self.next_ws, self.skip_ws);
+        let mark = buf.get_mark();
+        loop {
+            let minifier = self.minifier;
+            let size_hint = self.push_locals(|this| write_body(this, buf))?;
+            let joined = join_minifiers([minifier, self.minifier]);
+            if joined == minifier {
+                return Ok(size_hint);
+            }
+            buf.truncate(mark);
+            self.minifier = joined;
+            self.next_ws = next_ws;
+            self.skip_ws = skip_ws;
+        }
+    }
+
     fn write_call(
         &mut self,
         ctx: &Context<'a>,
@@ -638,6 +761,9 @@ impl<'a> Generator<'a, '_> {
         } else {
             self.seen_macros.push((def, ctx.file_info_of(call.span())));
         }
+        if !self.used.macros.iter().any(|used| std::ptr::eq(*used, def)) {
+            self.used.macros.push(def);
+        }
 
         self.flush_ws(ws); // Cannot handle_ws() here: whitespace from macro definition comes first
         let size_hint = self.push_locals(|this| {
@@ -720,8 +846,11 @@ impl<'a> Generator<'a, '_> {
                     // to avoid moving non-copyable values.
                     Expr::Var(name) if *name != "self" => {
                         let var = this.locals.resolve_or_self(name);
-                        this.locals
-                            .insert(Cow::Borrowed(arg), LocalMeta::with_ref(var));
+                        let map_keys = this.map_keys(expr);
+                        this.locals.insert(
+                            Cow::Borrowed(arg),
+                            LocalMeta::with_ref(var).with_map_keys(map_keys),
+                        );
                     }
                     Expr::Attr(obj, attr) => {
                         let mut attr_buf = Buffer::new();
@@ -729,8 +858,11 @@ impl<'a> Generator<'a, '_> {
 
                         let attr = attr_buf.into_string();
                         let var = this.locals.resolve(&attr).unwrap_or(attr);
-                        this.locals
-                            .insert(Cow::Borrowed(arg), LocalMeta::with_ref(var));
+                        let map_keys = this.map_keys(expr);
+                        this.locals.insert(
+                            Cow::Borrowed(arg),
+                            LocalMeta::with_ref(var).with_map_keys(map_keys),
+                        );
                     }
                     // Everything else still needs to become variables,
                     // to avoid having the same logic be executed
@@ -748,7 +880,11 @@ impl<'a> Generator<'a, '_> {
                         // locals in the normalized manner
                         let normalized_arg = normalize_identifier(arg);
                         buf.write(format_args!("let {} = {before}{value}{after};", normalized_arg));
-                        this.locals.insert_with_default(Cow::Borrowed(normalized_arg));
+                        let map_keys = this.map_keys(expr);
+                        this.locals.insert(
+                            Cow::Borrowed(normalized_arg),
+                            LocalMeta::default().with_map_keys(map_keys),
+                        );
                     }
                 }
             }
@@ -780,7 +916,8 @@ impl<'a> Generator<'a, '_> {
         // build `FmtCell` that contains the inner block
         buf.write(format_args!(
             "let {FILTER_SOURCE} = rinja::helpers::FmtCell::new(\
-                |__rinja_writer: &mut rinja::helpers::core::fmt::Formatter<'_>| -> rinja::Result<()> {{"
+                |__rinja_writer: &mut rinja::helpers::core::fmt::Formatter<'_>| -> rinja::Result<()> {{\
+                    let __rinja_writer = &mut rinja::helpers::FmtSink(__rinja_writer);"
         ));
         let size_hint = self.push_locals(|this| {
             this.prepare_ws(filter.ws1);
@@ -934,43 +1071,225 @@ impl<'a> Generator<'a, '_> {
 
     fn write_let(
         &mut self,
-        ctx: &Context<'_>,
+        ctx: &Context<'a>,
         buf: &mut Buffer,
         l: &'a WithSpan<'_, Let<'_>>,
     ) -> Result<(), CompileError> {
-        self.handle_ws(l.ws);
-
-        let Some(val) = &l.val else {
-            self.write_buf_writable(ctx, buf)?;
-            buf.write("let ");
-            self.visit_target(buf, false, true, &l.var);
-            buf.write(';');
-            return Ok(());
+        let val = match &l.val {
+            Some(LetValueOrBlock::Value(val)) => val,
+            Some(LetValueOrBlock::Block { nodes, filters, ws }) => {
+                return self.write_let_block(ctx, buf, l, nodes, filters.as_ref(), *ws);
+            }
+            None => {
+                self.handle_ws(l.ws);
+                self.write_buf_writable(ctx, buf)?;
+                buf.write(if l.is_mutable { "let mut " } else { "let " });
+                self.visit_target(buf, false, true, &l.var);
+                buf.write(';');
+                if let (true, &Target::Name(name)) = (l.is_mutable, &l.var) {
+                    self.locals.insert(
+                        Cow::Borrowed(normalize_identifier(name)),
+                        LocalMeta::default().mutable(),
+                    );
+                }
+                return Ok(());
+            }
         };
+        self.handle_ws(l.ws);
 
         let mut expr_buf = Buffer::new();
         self.visit_expr(ctx, &mut expr_buf, val)?;
 
+        if l.is_mutable || self.is_mutable_variable(&l.var) {
+            // A mutable variable holds its value and not a reference to it, so it can be
+            // reassigned in a nested scope, e.g. in a loop.
+            self.write_buf_writable(ctx, buf)?;
+            let expr_code = self.locate_expr(ctx, buf, val.span(), expr_buf.into_string());
+            let Target::Name(name) = l.var else {
+                unreachable!("only a variable name can be declared `mut`");
+            };
+            let name = normalize_identifier(name);
+            if l.is_mutable {
+                buf.write(format_args!("let mut {name} = {expr_code};"));
+                self.locals
+                    .insert(Cow::Borrowed(name), LocalMeta::initialized().mutable());
+            } else {
+                // `{% set total = total + 1 %}` cannot be written as `total += 1` in a template
+                buf.write(format_args!(
+                    "#[allow(clippy::assign_op_pattern)] {{ {name} = {expr_code}; }}"
+                ));
+            }
+            return Ok(());
+        }
+
         let shadowed = self.is_shadowing_variable(ctx, &l.var, l.span())?;
         if shadowed {
             // Need to flush the buffer if the variable is being shadowed,
             // to ensure the old variable is used.
             self.write_buf_writable(ctx, buf)?;
         }
+        let expr_code = self.locate_expr(ctx, buf, val.span(), expr_buf.into_string());
         if shadowed
             || !matches!(l.var, Target::Name(_))
             || matches!(&l.var, Target::Name(name) if self.locals.get(name).is_none())
         {
             buf.write("let ");
         }
+        self.write_assignment(buf, &l.var, val, &expr_code);
+        Ok(())
+    }
 
-        self.visit_target(buf, true, true, &l.var);
+    /// Returns `true` if `target` is a variable that was declared with `{% let mut %}`.
+    fn is_mutable_variable(&self, target: &Target<'_>) -> bool {
+        match *target {
+            Target::Name(name) => self
+                .locals
+                .get(normalize_identifier(name))
+                .is_some_and(|meta| meta.mutable),
+            _ => false,
+        }
+    }
+
+    /// Writes `target = value;`, without the `let`, and adds the variables of `target` to the
+    /// current scope.
+    fn write_assignment(
+        &mut self,
+        buf: &mut Buffer,
+        target: &Target<'a>,
+        val: &WithSpan<'_, Expr<'a>>,
+        expr_code: &str,
+    ) {
+        self.visit_target(buf, true, true, target);
+        if let Target::Name(name) = *target {
+            if let Some(map_keys) = self.map_keys(val) {
+                self.locals.insert(
+                    Cow::Borrowed(normalize_identifier(name)),
+                    LocalMeta::initialized().with_map_keys(Some(map_keys)),
+                );
+            }
+        }
         let (before, after) = if !is_copyable(val) {
             ("&(", ")")
         } else {
             ("", "")
         };
-        buf.write(format_args!(" = {before}{expr_buf}{after};"));
+        buf.write(format_args!(" = {before}{expr_code}{after};"));
+    }
+
+    fn write_with(
+        &mut self,
+        ctx: &Context<'a>,
+        buf: &mut Buffer,
+        with: &'a WithSpan<'_, With<'_>>,
+    ) -> Result<usize, CompileError> {
+        self.handle_ws(with.ws1);
+        let flushed = self.write_buf_writable(ctx, buf)?;
+        buf.write('{');
+        let size_hint = self.push_locals(|this| {
+            // Every variable can use the ones declared before it.
+            for (target, val) in &with.vars {
+                let expr_code = this.visit_expr_root(ctx, val)?;
+                let expr_code = this.locate_expr(ctx, buf, val.span(), expr_code);
+                buf.write("let ");
+                this.write_assignment(buf, target, val, &expr_code);
+            }
+            let mut size_hint = this.handle(ctx, &with.nodes, buf, AstLevel::Nested)?;
+            this.flush_ws(with.ws2);
+            size_hint += this.write_buf_writable(ctx, buf)?;
+            Ok(size_hint)
+        })?;
+        buf.write('}');
+        self.prepare_ws(with.ws2);
+        Ok(flushed + size_hint)
+    }
+
+    /// Renders the body of a block assignment into a string, which is then stored in the variable.
+    fn write_let_block(
+        &mut self,
+        ctx: &Context<'a>,
+        buf: &mut Buffer,
+        l: &'a WithSpan<'_, Let<'_>>,
+        nodes: &'a [Node<'_>],
+        filters: Option<&'a Filter<'_>>,
+        end_ws: Ws,
+    ) -> Result<(), CompileError> {
+        if !cfg!(feature = "alloc") {
+            return Err(ctx.generate_error(
+                "a block assignment requires the `alloc` feature to be enabled",
+                l.span(),
+            ));
+        }
+        let Target::Name(name) = l.var else {
+            return Err(ctx.generate_error(
+                "a block assignment can only assign to a variable name",
+                l.span(),
+            ));
+        };
+        let name = normalize_identifier(name);
+
+        self.flush_ws(l.ws);
+        self.write_buf_writable(ctx, buf)?;
+        let reassigned = !l.is_mutable && self.is_mutable_variable(&l.var);
+        if l.is_mutable {
+            buf.write("let mut ");
+        } else if !reassigned
+            && (self.is_shadowing_variable(ctx, &l.var, l.span())?
+                || self.locals.get(name).is_none())
+        {
+            buf.write("let ");
+        }
+
+        // The variable is only introduced after its body, which may still use a previous
+        // variable of the same name.
+        buf.write(format_args!(
+            "{name} = {{\
+                let mut {FILTER_SOURCE} = rinja::helpers::alloc::string::String::new();\
+                {{\
+                    let __rinja_writer = &mut rinja::helpers::FmtSink(&mut {FILTER_SOURCE});"
+        ));
+        self.push_locals(|this| {
+            this.prepare_ws(l.ws);
+            this.handle(ctx, nodes, buf, AstLevel::Nested)?;
+            this.flush_ws(end_ws);
+            this.write_buf_writable(ctx, buf)
+        })?;
+        buf.write('}');
+
+        match filters {
+            None => buf.write(format_args!("rinja::filters::Safe({FILTER_SOURCE})")),
+            Some(filters) => {
+                // the result is the same as the output of a `{% filter %}` block
+                let mut filter_buf = Buffer::new();
+                let display_wrap = self.visit_filter(
+                    ctx,
+                    &mut filter_buf,
+                    filters.name,
+                    &filters.arguments,
+                    &filters.generics,
+                    l.span(),
+                )?;
+                let filter_buf = match display_wrap {
+                    DisplayWrap::Wrapped => fmt_left!("{filter_buf}"),
+                    DisplayWrap::Unwrapped => fmt_right!(
+                        "(&&rinja::filters::AutoEscaper::new(&({filter_buf}), {})).rinja_auto_escape()?",
+                        self.input.escaper,
+                    ),
+                };
+                buf.write(format_args!(
+                    "let mut __rinja_value = rinja::helpers::alloc::string::String::new();\
+                    rinja::helpers::core::write!(__rinja_value, \"{{}}\", {filter_buf})?;\
+                    rinja::filters::Safe(__rinja_value)"
+                ));
+            }
+        }
+        buf.write("};");
+
+        if !reassigned {
+            let meta = LocalMeta::initialized();
+            let meta = if l.is_mutable { meta.mutable() } else { meta };
+            self.locals.insert(Cow::Borrowed(name), meta);
+        }
+        self.prepare_ws(end_ws);
         Ok(())
     }
 
@@ -1035,6 +1354,19 @@ impl<'a> Generator<'a, '_> {
                 node,
             )
         })?;
+        if !self.used.blocks.iter().any(|used| std::ptr::eq(*used, def)) {
+            self.used.blocks.push(def);
+        }
+
+        // If we only render a single block, then the output of other blocks is discarded anyway.
+        // Unless they contain the block we want to render, we don't need to generate them at all.
+        if let Some((target, _)) = self.input.block {
+            if buf.is_discard() && !block_may_contain(heritage, &def.nodes, target) {
+                self.prepare_ws(outer);
+                buf.set_discard(prev_buf_discard);
+                return Ok(0);
+            }
+        }
 
         // We clone the context of the child in order to preserve their macros and imports.
         // But also add all the imports and macros from this template that don't override the
@@ -1107,6 +1439,17 @@ impl<'a> Generator<'a, '_> {
     ) -> Result<usize, CompileError> {
         let mut size_hint = 0;
         let items = mem::take(&mut self.buf_writable.buf);
+        if let Some(text) = &mut self.constant_text {
+            for item in &items {
+                match item {
+                    Writable::Lit(s) => text.push_str(s),
+                    Writable::Expr(_) => {
+                        self.constant_text = None;
+                        break;
+                    }
+                }
+            }
+        }
         let mut it = items.iter().enumerate().peekable();
 
         while let Some((_, Writable::Lit(s))) = it.peek() {
@@ -1117,13 +1460,13 @@ impl<'a> Generator<'a, '_> {
             return Ok(size_hint);
         }
 
+        let mut exprs = Buffer::new();
         let mut targets = Buffer::new();
         let mut lines = Buffer::new();
         let mut expr_cache = HashMap::with_capacity(self.buf_writable.len());
         // the `last_line` contains any sequence of trailing simple `writer.write_str()` calls
         let mut trailing_simple_lines = Vec::new();
 
-        buf.write("match (");
         while let Some((idx, s)) = it.next() {
             match s {
                 Writable::Lit(s) => {
@@ -1157,26 +1500,30 @@ impl<'a> Generator<'a, '_> {
                         match expr_cache.entry(expr) {
                             Entry::Occupied(e) => *e.get(),
                             Entry::Vacant(e) => {
-                                buf.write(format_args!("&({}),", e.key()));
+                                let expr = self.locate_expr(ctx, buf, s.span(), e.key().clone());
+                                exprs.write(format_args!("&({expr}),"));
                                 targets.write(format_args!("expr{idx},"));
                                 e.insert(idx);
                                 idx
                             }
                         }
                     } else {
-                        buf.write(format_args!("&({expr}),"));
+                        let expr = self.locate_expr(ctx, buf, s.span(), expr);
+                        exprs.write(format_args!("&({expr}),"));
                         targets.write(format_args!("expr{idx}, "));
                         idx
                     };
                     lines.write(format_args!(
                         "(&&&rinja::filters::Writable(expr{idx})).\
-                             rinja_write(__rinja_writer, __rinja_values)?;",
+                             rinja_write(__rinja_writer, __rinja_values)",
                     ));
+                    self.write_question_mark(ctx, &mut lines, s.span());
+                    lines.write(';');
                 }
             }
         }
         buf.write(format_args!(
-            ") {{\
+            "match ({exprs}) {{\
                 ({targets}) => {{\
                     {lines}\
                 }}\
@@ -1190,6 +1537,45 @@ impl<'a> Generator<'a, '_> {
         Ok(size_hint)
     }
 
+    /// If `error_locations` is enabled in the config, `expr_code` is wrapped in a local
+    /// `macro_rules!` that is named after the location of `span` in the template. The macro
+    /// definition is written into `buf`, and its invocation is returned.
+    ///
+    /// If rustc rejects the expression, the name of the macro shows up in its
+    /// "this error originates in the macro" note, so the user knows where to look.
+    fn locate_expr(
+        &self,
+        ctx: &Context<'_>,
+        buf: &mut Buffer,
+        span: Span<'_>,
+        expr_code: String,
+    ) -> String {
+        if !self.input.config.error_locations {
+            return expr_code;
+        }
+        let Some(name) = expr_location_name(ctx, span) else {
+            return expr_code;
+        };
+        buf.write(format_args!(
+            "macro_rules! {name} {{ () => {{ {expr_code} }} }}"
+        ));
+        format!("{name}!()")
+    }
+
+    /// Writes the `?` operator to propagate the error of a fallible call. If
+    /// `runtime_error_locations` is enabled in the config, the location of `span` in the
+    /// template is attached to the error first.
+    pub(super) fn write_question_mark(&self, ctx: &Context<'_>, buf: &mut Buffer, span: Span<'_>) {
+        if self.input.config.runtime_error_locations {
+            if let Some((path, row, column)) = runtime_location(ctx, span) {
+                buf.write(format_args!(
+                    ".map_err(|err| rinja::helpers::located_error(err, {path:?}, {row}, {column}))",
+                ));
+            }
+        }
+        buf.write('?');
+    }
+
     fn write_comment(&mut self, comment: &'a WithSpan<'_, Comment<'_>>) {
         self.handle_ws(comment.ws);
     }
@@ -1220,7 +1606,11 @@ impl<'a> Generator<'a, '_> {
 
         if !val.is_empty() {
             self.skip_ws = Whitespace::Preserve;
-            self.buf_writable.push(Writable::Lit(Cow::Borrowed(val)));
+            let val = match &mut self.minifier {
+                Some(minifier) => minifier.minify(val),
+                None => Cow::Borrowed(val),
+            };
+            self.buf_writable.push(Writable::Lit(val));
         }
 
         if !rws.is_empty() {
@@ -1238,7 +1628,16 @@ impl<'a> Generator<'a, '_> {
     }
 
     fn should_trim_ws(&self, ws: Option<Whitespace>) -> Whitespace {
-        ws.unwrap_or(self.input.config.whitespace)
+        match ws {
+            Some(ws) => ws,
+            // Minifying collapses whitespace unless it is e.g. inside a `<pre>` element.
+            None => match (self.input.config.whitespace, &self.minifier) {
+                (Whitespace::Preserve, Some(minifier)) if minifier.may_collapse_whitespace() => {
+                    Whitespace::Minimize
+                }
+                (whitespace, _) => whitespace,
+            },
+        }
     }
 
     // If the previous literal left some trailing whitespace in `next_ws` and the
@@ -1324,7 +1723,9 @@ impl<'a> Conds<'a> {
             {
                 let mut only_contains_is_defined = true;
 
-                let (evaluated_result, cond_expr) = if *contains_bool_lit_or_is_defined {
+                let (evaluated_result, cond_expr) = if *contains_bool_lit_or_is_defined
+                    || matches!(fold_expr(expr), Some(Const::Bool(_)))
+                {
                     let (evaluated_result, expr) =
                         generator.evaluate_condition(expr.clone(), &mut only_contains_is_defined);
                     (evaluated_result, Some(expr))
@@ -1405,6 +1806,52 @@ impl<'a> Conds<'a> {
     }
 }
 
+/// Returns `false` if rendering `nodes` can never render the block `target`.
+///
+/// Macro calls and includes are not inspected, so they are assumed to contain the block.
+fn block_may_contain(heritage: &Heritage<'_, '_>, nodes: &[Node<'_>], target: &str) -> bool {
+    nodes.iter().any(|node| match node {
+        Node::BlockDef(b) => {
+            b.name == target
+                || heritage.blocks.get(b.name).is_some_and(|defs| {
+                    defs.first()
+                        .is_some_and(|(_, def)| block_may_contain(heritage, &def.nodes, target))
+                })
+        }
+        Node::If(i) => i
+            .branches
+            .iter()
+            .any(|cond| block_may_contain(heritage, &cond.nodes, target)),
+        Node::Match(m) => m
+            .arms
+            .iter()
+            .any(|arm| block_may_contain(heritage, &arm.nodes, target)),
+        Node::Loop(l) => {
+            block_may_contain(heritage, &l.body, target)
+                || block_may_contain(heritage, &l.else_nodes, target)
+        }
+        Node::While(w) => block_may_contain(heritage, &w.nodes, target),
+        Node::With(w) => block_may_contain(heritage, &w.nodes, target),
+        Node::FilterBlock(f) => block_may_contain(heritage, &f.nodes, target),
+        Node::Let(l) => match &l.val {
+            Some(LetValueOrBlock::Block { nodes, .. }) => {
+                block_may_contain(heritage, nodes, target)
+            }
+            _ => false,
+        },
+        Node::Call(_) | Node::Include(_) => true,
+        Node::Lit(_)
+        | Node::Comment(_)
+        | Node::Expr(_, _)
+        | Node::Extends(_)
+        | Node::Import(_)
+        | Node::Macro(_)
+        | Node::Raw(_)
+        | Node::Break(_)
+        | Node::Continue(_) => false,
+    })
+}
+
 fn median(sizes: &mut [usize]) -> usize {
     if sizes.is_empty() {
         return 0;
@@ -1417,6 +1864,16 @@ fn median(sizes: &mut [usize]) -> usize {
     }
 }
 
+/// Returns the minifier state after one of the branches, which ended in the given states
+fn join_minifiers(
+    branches: impl IntoIterator<Item = Option<HtmlMinifier>>,
+) -> Option<HtmlMinifier> {
+    branches
+        .into_iter()
+        .reduce(|a, b| Some(a?.join(b?)))
+        .flatten()
+}
+
 fn macro_call_ensure_arg_count(
     call: &WithSpan<'_, Call<'_>>,
     def: &Macro<'_>,
@@ -1516,6 +1973,32 @@ enum AstLevel {
     Nested,
 }
 
+/// Returns an identifier like `__rinja_expr__templates_hello_html__line_3__col_5` for the
+/// location of `span` in the template of `ctx`.
+///
+/// The identifier contains the path of the template as shown to the user, so templates with the
+/// same file name in different directories get different names.
+fn expr_location_name(ctx: &Context<'_>, span: Span<'_>) -> Option<String> {
+    let (path, row, column) = runtime_location(ctx, span)?;
+
+    let mut name = String::from("__rinja_expr__");
+    name.extend(
+        path.chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' }),
+    );
+    write!(name, "__line_{row}__col_{column}").unwrap();
+    Some(name)
+}
+
+/// Returns the path of the template of `ctx`, as shown to the user, and the row and column of
+/// `span` in it, both starting at 1.
+fn runtime_location(ctx: &Context<'_>, span: Span<'_>) -> Option<(String, usize, usize)> {
+    let source = ctx.parsed.source();
+    let node_source = span.as_suffix_of(source)?;
+    let (ErrorInfo { row, column, .. }, path) = generate_error_info(source, node_source, ctx.path?);
+    Some((path, row, column + 1))
+}
+
 /// Returns `true` if the outcome of this expression may be used multiple times in the same
 /// `write!()` call, without evaluating the expression again, i.e. the expression should be
 /// side-effect free.
@@ -1531,6 +2014,7 @@ fn is_cacheable(expr: &WithSpan<'_, Expr<'_>>) -> bool {
         Expr::Path(_) => true,
         // Check recursively:
         Expr::Array(args) => args.iter().all(is_cacheable),
+        Expr::Map(entries) => entries.iter().all(|(_, value)| is_cacheable(value)),
         Expr::Attr(lhs, _) => is_cacheable(lhs),
         Expr::Index(lhs, rhs) => is_cacheable(lhs) && is_cacheable(rhs),
         Expr::Filter(Filter { arguments, .. }) => arguments.iter().all(is_cacheable),
@@ -1548,7 +2032,17 @@ fn is_cacheable(expr: &WithSpan<'_, Expr<'_>>) -> bool {
         Expr::Try(expr) => is_cacheable(expr),
         Expr::Concat(args) => args.iter().all(is_cacheable),
         // Doesn't make sense in this context.
-        Expr::LetCond(_) => false,
+        Expr::LetCond(_) | Expr::Closure { .. } => false,
+        Expr::IfElse {
+            then,
+            cond,
+            otherwise,
+        } => {
+            cond.target.is_none()
+                && is_cacheable(&cond.expr)
+                && is_cacheable(then)
+                && otherwise.as_ref().map_or(true, |v| is_cacheable(v))
+        }
         // We have too little information to tell if the expression is pure:
         Expr::Call { .. } => false,
         Expr::RustMacro(_, _) => false,
@@ -0,0 +1,311 @@
+//! Translation of Python format strings into [`format!()`] format strings.
+//!
+//! Both the printf-style `"%s items" % n` and the `str.format()` style
+//! `"{:.2f}"|pyformat(x)` are translated at compile time, so an invalid format string or a
+//! wrong number of arguments is reported as a compile error. The content of the string
+//! literal is kept as written, i.e. escape sequences like `\n` are passed through unchanged.
+
+use std::iter::Peekable;
+use std::str::Chars;
+
+/// Translates a printf-style format string like `"%s: %.2f"`, which uses `nargs` arguments.
+pub(crate) fn printf_to_rust(fmt: &str, nargs: usize) -> Result<String, String> {
+    let mut out = String::with_capacity(fmt.len());
+    let mut chars = fmt.chars().peekable();
+    let mut count = 0;
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => copy_escape(&mut out, &mut chars),
+            '{' => out.push_str("{{"),
+            '}' => out.push_str("}}"),
+            '%' if chars.next_if_eq(&'%').is_some() => out.push('%'),
+            '%' => {
+                printf_spec(&mut out, &mut chars)?;
+                count += 1;
+            }
+            c => out.push(c),
+        }
+    }
+    if count != nargs {
+        return Err(format!(
+            "the format string has {count} placeholder(s), but {nargs} argument(s) were given",
+        ));
+    }
+    Ok(out)
+}
+
+/// Translates a `str.format()` format string like `"{}: {:.2f}"`, which uses `nargs`
+/// arguments.
+pub(crate) fn str_format_to_rust(fmt: &str, nargs: usize) -> Result<String, String> {
+    let mut out = String::with_capacity(fmt.len());
+    let mut chars = fmt.chars().peekable();
+    let mut used = vec![false; nargs];
+    // `Some(next_index)` for automatic field numbering, `None` for manual field numbering
+    let mut numbering: Option<Option<usize>> = None;
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => copy_escape(&mut out, &mut chars),
+            '{' if chars.next_if_eq(&'{').is_some() => out.push_str("{{"),
+            '}' if chars.next_if_eq(&'}').is_some() => out.push_str("}}"),
+            '}' => return Err("single `}` encountered in format string".to_owned()),
+            '{' => {
+                let mut name = String::new();
+                while let Some(c) = chars.next_if(|c| !matches!(c, '!' | ':' | '}' | '{')) {
+                    name.push(c);
+                }
+                let index = if name.is_empty() {
+                    let Some(index) = *numbering.get_or_insert(Some(0)) else {
+                        return Err("cannot switch from manual field numbering to automatic \
+                                    field numbering"
+                            .to_owned());
+                    };
+                    numbering = Some(Some(index + 1));
+                    out.push('{');
+                    index
+                } else if let Ok(index) = name.parse::<usize>() {
+                    if numbering.get_or_insert(None).is_some() {
+                        return Err("cannot switch from automatic field numbering to manual \
+                                    field numbering"
+                            .to_owned());
+                    }
+                    out.push_str(&format!("{{{index}"));
+                    index
+                } else {
+                    return Err(format!(
+                        "unsupported replacement field `{{{name}}}`: only positional fields \
+                         like `{{}}` or `{{0}}` are supported",
+                    ));
+                };
+                match used.get_mut(index) {
+                    Some(used) => *used = true,
+                    None => {
+                        return Err(format!(
+                            "replacement field {index} is out of range: only {nargs} \
+                             argument(s) were given",
+                        ));
+                    }
+                }
+
+                let debug = match chars.next_if_eq(&'!').and_then(|_| chars.next()) {
+                    None => false,
+                    Some('s') => false,
+                    Some('r') => true,
+                    Some(c) => return Err(format!("unsupported conversion `!{c}`")),
+                };
+                let mut spec = String::new();
+                if chars.next_if_eq(&':').is_some() {
+                    while let Some(c) = chars.next_if(|c| *c != '}') {
+                        if c == '{' {
+                            return Err("nested replacement fields are not supported".to_owned());
+                        }
+                        spec.push(c);
+                    }
+                }
+                if chars.next() != Some('}') {
+                    return Err("expected `}` at the end of the replacement field".to_owned());
+                }
+                format_spec(&mut out, &spec, debug)?;
+            }
+            c => out.push(c),
+        }
+    }
+    if let Some(index) = used.iter().position(|used| !used) {
+        return Err(format!(
+            "argument {index} is never used in the format string"
+        ));
+    }
+    Ok(out)
+}
+
+/// Copies an escape sequence like `\n` or `\u{1F600}` which follows a backslash.
+fn copy_escape(out: &mut String, chars: &mut Peekable<Chars<'_>>) {
+    out.push('\\');
+    if let Some(c) = chars.next() {
+        out.push(c);
+        if c == 'u' {
+            for c in chars.by_ref() {
+                out.push(c);
+                if c == '}' {
+                    break;
+                }
+            }
+        }
+    }
+}
+
+/// Translates the part after the `%` of a printf-style placeholder, e.g. `-5s` or `.2f`.
+fn printf_spec(out: &mut String, chars: &mut Peekable<Chars<'_>>) -> Result<(), String> {
+    let (mut left, mut zero, mut plus, mut alternate) = (false, false, false, false);
+    while let Some(c) = chars.next_if(|c| matches!(c, '-' | '0' | '+' | ' ' | '#' | '(')) {
+        match c {
+            '-' => left = true,
+            '0' => zero = true,
+            '+' => plus = true,
+            '#' => alternate = true,
+            ' ' => return Err("the ` ` flag is not supported in format strings".to_owned()),
+            _ => return Err("mapping keys like `%(name)s` are not supported".to_owned()),
+        }
+    }
+    let width = digits(chars);
+    if chars.next_if_eq(&'*').is_some() {
+        return Err("a width of `*` is not supported in format strings".to_owned());
+    }
+    let mut precision = chars.next_if_eq(&'.').map(|_| digits(chars));
+    if precision.as_deref() == Some("") {
+        precision = Some("0".to_owned());
+    }
+    // length modifiers are accepted and ignored, like in Python
+    while chars.next_if(|c| matches!(c, 'h' | 'l' | 'L')).is_some() {}
+
+    let (ty, is_str) = match chars.next() {
+        Some('s') => ("", true),
+        Some('r') => ("?", true),
+        Some('d' | 'i' | 'u') if precision.is_some() => {
+            return Err("a precision is only supported for `%s`, `%r`, `%f` and `%F`".to_owned());
+        }
+        Some('d' | 'i' | 'u') => ("", false),
+        Some('f' | 'F') => {
+            // Python prints six digits after the decimal point by default.
+            precision.get_or_insert_with(|| "6".to_owned());
+            ("", false)
+        }
+        Some('x') => ("x", false),
+        Some('X') => ("X", false),
+        Some('o') => ("o", false),
+        Some(c) => return Err(format!("unsupported conversion type `%{c}`")),
+        None => return Err("incomplete format: expected a conversion type after `%`".to_owned()),
+    };
+    if precision.is_some() && !is_str && !ty.is_empty() {
+        return Err("a precision is only supported for `%s`, `%r`, `%f` and `%F`".to_owned());
+    }
+    if alternate && !matches!(ty, "x" | "X" | "o") {
+        return Err("the `#` flag is only supported for `%x`, `%X` and `%o`".to_owned());
+    }
+
+    let mut spec = String::new();
+    if !width.is_empty() {
+        if left {
+            spec.push('<');
+        } else if !zero || is_str {
+            // Python right-aligns all values, Rust left-aligns strings by default.
+            spec.push('>');
+        }
+    }
+    if plus && !is_str {
+        spec.push('+');
+    }
+    if alternate {
+        spec.push('#');
+    }
+    if zero && !left && !is_str && !width.is_empty() {
+        spec.push('0');
+    }
+    spec.push_str(&width);
+    if let Some(precision) = precision {
+        spec.push('.');
+        spec.push_str(&precision);
+    }
+    spec.push_str(ty);
+    push_placeholder(out, &spec);
+    Ok(())
+}
+
+/// Translates the format specification after the `:` of a `str.format()` replacement field.
+fn format_spec(out: &mut String, spec: &str, debug: bool) -> Result<(), String> {
+    let mut chars = spec.chars().peekable();
+    let mut rust = String::new();
+
+    let mut lookahead = spec.chars();
+    match (lookahead.next(), lookahead.next()) {
+        (Some(fill), Some(align @ ('<' | '>' | '^' | '='))) => {
+            rust.push(fill);
+            rust.push(alignment(align)?);
+            chars.nth(1);
+        }
+        (Some(align @ ('<' | '>' | '^' | '=')), _) => {
+            rust.push(alignment(align)?);
+            chars.next();
+        }
+        _ => {}
+    }
+    match chars.next_if(|c| matches!(c, '+' | '-' | ' ')) {
+        Some('+') => rust.push('+'),
+        Some(' ') => return Err("the ` ` sign option is not supported".to_owned()),
+        _ => {}
+    }
+    if chars.next_if_eq(&'#').is_some() {
+        rust.push('#');
+    }
+    if chars.next_if_eq(&'0').is_some() {
+        rust.push('0');
+    }
+    rust.push_str(&digits(&mut chars));
+    if chars.next_if(|c| matches!(c, ',' | '_')).is_some() {
+        return Err("digit grouping is not supported".to_owned());
+    }
+    let precision = chars.next_if_eq(&'.').map(|_| digits(&mut chars));
+    let ty = match chars.next() {
+        None | Some('s' | 'd') => "",
+        Some('f' | 'F') => {
+            if precision.is_none() {
+                // Python prints six digits after the decimal point by default.
+                rust.push_str(".6");
+            }
+            ""
+        }
+        Some('x') => "x",
+        Some('X') => "X",
+        Some('o') => "o",
+        Some('b') => "b",
+        Some(c) => return Err(format!("unsupported format type `{c}`")),
+    };
+    if let Some(rest) = chars.next() {
+        return Err(format!("invalid format specifier `{spec}` near `{rest}`"));
+    }
+    if let Some(precision) = precision {
+        if precision.is_empty() {
+            return Err("format specifier missing precision".to_owned());
+        }
+        rust.push('.');
+        rust.push_str(&precision);
+    }
+    if debug {
+        if !ty.is_empty() {
+            return Err(format!("the format type `{ty}` cannot be used with `!r`"));
+        }
+        rust.push('?');
+    } else {
+        rust.push_str(ty);
+    }
+
+    if !rust.is_empty() {
+        out.push(':');
+        out.push_str(&rust);
+    }
+    out.push('}');
+    Ok(())
+}
+
+fn alignment(align: char) -> Result<char, String> {
+    match align {
+        '=' => Err("the `=` alignment is not supported".to_owned()),
+        align => Ok(align),
+    }
+}
+
+fn digits(chars: &mut Peekable<Chars<'_>>) -> String {
+    let mut digits = String::new();
+    while let Some(c) = chars.next_if(char::is_ascii_digit) {
+        digits.push(c);
+    }
+    digits
+}
+
+fn push_placeholder(out: &mut String, spec: &str) {
+    out.push('{');
+    if !spec.is_empty() {
+        out.push(':');
+        out.push_str(spec);
+    }
+    out.push('}');
+}
@@ -3,7 +3,7 @@ use std::collections::HashMap;
 use std::path::Path;
 use std::sync::Arc;
 
-use parser::node::{BlockDef, Macro};
+use parser::node::{BlockDef, LetValueOrBlock, Macro};
 use parser::{Node, Parsed, Span};
 use rustc_hash::FxBuildHasher;
 
@@ -119,11 +119,22 @@ impl<'a> Context<'a> {
                         nested.push(&l.body);
                         nested.push(&l.else_nodes);
                     }
+                    Node::While(w) => {
+                        nested.push(&w.nodes);
+                    }
+                    Node::With(w) => {
+                        nested.push(&w.nodes);
+                    }
                     Node::Match(m) => {
                         for arm in &m.arms {
                             nested.push(&arm.nodes);
                         }
                     }
+                    Node::Let(l) => {
+                        if let Some(LetValueOrBlock::Block { nodes, .. }) = &l.val {
+                            nested.push(nodes);
+                        }
+                    }
                     _ => {}
                 }
             }
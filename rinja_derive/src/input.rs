@@ -1,12 +1,11 @@
 use std::borrow::Cow;
 use std::collections::hash_map::{Entry, HashMap};
 use std::fs::read_to_string;
-use std::iter::FusedIterator;
 use std::path::{Path, PathBuf};
 use std::str::FromStr;
 use std::sync::{Arc, OnceLock};
 
-use parser::node::Whitespace;
+use parser::node::{LetValueOrBlock, Whitespace};
 use parser::{Node, Parsed};
 use proc_macro2::Span;
 use rustc_hash::FxBuildHasher;
@@ -14,7 +13,8 @@ use syn::punctuated::Punctuated;
 use syn::spanned::Spanned;
 use syn::{Attribute, Expr, ExprLit, ExprPath, Ident, Lit, LitBool, LitStr, Meta, Token};
 
-use crate::config::{Config, SyntaxAndCache};
+use crate::config::{Config, DEFAULT_MIME_TYPE, SyntaxAndCache};
+use crate::minify::Minify;
 use crate::{CompileError, FileInfo, MsgValidEscapers, OnceMap};
 
 #[derive(Clone)]
@@ -32,6 +32,9 @@ pub(crate) struct TemplateInput<'a> {
     pub(crate) escaper: &'a str,
     pub(crate) path: Arc<Path>,
     pub(crate) fields: Arc<[String]>,
+    pub(crate) extension: Option<&'a str>,
+    pub(crate) mime_type: &'a str,
+    pub(crate) minify: Minify,
 }
 
 impl TemplateInput<'_> {
@@ -54,6 +57,7 @@ impl TemplateInput<'_> {
             ext,
             ext_span,
             syntax,
+            minify,
             ..
         } = args;
 
@@ -86,6 +90,20 @@ impl TemplateInput<'_> {
             },
         )?;
 
+        // An explicit `escape` argument takes precedence over the extension of the template
+        let extension = args.extension();
+        let mime_type = escaping
+            .as_deref()
+            .and_then(|escaping| config.mime_type(escaping))
+            .or_else(|| config.mime_type(extension.unwrap_or_default()))
+            .unwrap_or(DEFAULT_MIME_TYPE);
+
+        // Only HTML can be minified
+        let minify = match minify.unwrap_or(config.minify) {
+            Minify::Html if !mime_type.starts_with("text/html") => Minify::None,
+            minify => minify,
+        };
+
         // Match extension against defined output formats
 
         let escaping = escaping
@@ -145,6 +163,9 @@ impl TemplateInput<'_> {
             escaper,
             path,
             fields: fields.into(),
+            extension,
+            mime_type,
+            minify,
         })
     }
 
@@ -165,17 +186,22 @@ impl TemplateInput<'_> {
         while let Some((path, source, source_path)) = check.pop() {
             let parsed = match self.syntax.parse(Arc::clone(&source), source_path) {
                 Ok(parsed) => parsed,
-                Err(err) => {
-                    let msg = err
-                        .message
-                        .unwrap_or_else(|| "failed to parse template source".into());
-                    let file_path = err
-                        .file_path
-                        .as_deref()
-                        .unwrap_or(Path::new("<source attribute>"));
-                    let file_info =
-                        FileInfo::new(file_path, Some(&source), Some(&source[err.offset..]));
-                    return Err(CompileError::new(msg, Some(file_info)));
+                Err(errors) => {
+                    let errors = errors.into_iter().map(|err| {
+                        let msg = err
+                            .message
+                            .unwrap_or_else(|| "failed to parse template source".into());
+                        let file_path = err
+                            .file_path
+                            .as_deref()
+                            .unwrap_or(Path::new("<source attribute>"));
+                        let file_info =
+                            FileInfo::new(file_path, Some(&source), Some(&source[err.offset..]));
+                        CompileError::new(msg, Some(file_info))
+                    });
+                    return Err(CompileError::from_many(errors).unwrap_or_else(|| {
+                        CompileError::no_file_info("failed to parse template source", None)
+                    }));
                 }
             };
 
@@ -235,6 +261,11 @@ impl TemplateInput<'_> {
                         Node::FilterBlock(f) => {
                             nested.push(&f.nodes);
                         }
+                        Node::Let(l) => {
+                            if let Some(LetValueOrBlock::Block { nodes, .. }) = &l.val {
+                                nested.push(nodes);
+                            }
+                        }
                         Node::Include(include) => {
                             let include = self.config.find_template(
                                 include.path,
@@ -255,6 +286,12 @@ impl TemplateInput<'_> {
                             nested.push(&l.body);
                             nested.push(&l.else_nodes);
                         }
+                        Node::While(w) => {
+                            nested.push(&w.nodes);
+                        }
+                        Node::With(w) => {
+                            nested.push(&w.nodes);
+                        }
                         Node::Match(m) => {
                             for arm in &m.arms {
                                 nested.push(&arm.nodes);
@@ -265,7 +302,6 @@ impl TemplateInput<'_> {
                         | Node::Expr(_, _)
                         | Node::Call(_)
                         | Node::Extends(_)
-                        | Node::Let(_)
                         | Node::Import(_)
                         | Node::Macro(_)
                         | Node::Raw(_)
@@ -370,6 +406,7 @@ pub(crate) struct TemplateArgs {
     config: Option<String>,
     crate_name: Option<ExprPath>,
     pub(crate) whitespace: Option<Whitespace>,
+    minify: Option<Minify>,
     pub(crate) template_span: Option<Span>,
     pub(crate) config_span: Option<Span>,
 }
@@ -429,6 +466,7 @@ impl TemplateArgs {
             config: args.config.as_ref().map(|value| value.value()),
             crate_name: args.crate_name,
             whitespace: args.whitespace,
+            minify: args.minify,
             template_span: Some(args.template.span()),
             config_span: args.config.as_ref().map(|value| value.span()),
         })
@@ -448,6 +486,7 @@ impl TemplateArgs {
             config: None,
             crate_name: None,
             whitespace: None,
+            minify: None,
             template_span: None,
             config_span: None,
         }
@@ -456,6 +495,27 @@ impl TemplateArgs {
     pub(crate) fn config_path(&self) -> Option<&str> {
         self.config.as_deref()
     }
+
+    /// The extension of the template, as given by its path or by the `ext` argument
+    ///
+    /// Jinja extensions are skipped, i.e. the extension of `template.html.j2` is `"html"`.
+    pub(crate) fn extension(&self) -> Option<&str> {
+        match &self.source.0 {
+            Source::Path(path) => {
+                let path = Path::new(&**path);
+                match path.extension().and_then(|s| s.to_str()) {
+                    Some(ext) if JINJA_EXTENSIONS.contains(&ext) => Some(
+                        Path::new(path.file_stem().unwrap_or_default())
+                            .extension()
+                            .and_then(|s| s.to_str())
+                            .unwrap_or(ext),
+                    ),
+                    ext => ext,
+                }
+            }
+            Source::Source(_) => self.ext.as_deref(),
+        }
+    }
 }
 
 /// Try to find the source in the comment, in a `rinja` code block.
@@ -585,13 +645,12 @@ fn collect_rinja_code_blocks(
     let mut had_rinja_code = false;
     for e in Parser::new(&source) {
         match (in_rinja_code, e) {
-            (false, Event::Start(Tag::CodeBlock(CodeBlockKind::Fenced(s)))) => {
+            (false, Event::Start(Tag::CodeBlock(CodeBlockKind::Fenced(s))))
                 if s.split(",")
-                    .any(|s| JINJA_EXTENSIONS.contains(&s.trim_ascii()))
-                {
-                    in_rinja_code = true;
-                    had_rinja_code = true;
-                }
+                    .any(|s| JINJA_EXTENSIONS.contains(&s.trim_ascii())) =>
+            {
+                in_rinja_code = true;
+                had_rinja_code = true;
             }
             (true, Event::End(TagEnd::CodeBlock)) => in_rinja_code = false,
             (true, Event::Text(text)) => tmpl_source.push_str(&text),
@@ -608,50 +667,21 @@ fn collect_rinja_code_blocks(
     Ok(Source::Source(tmpl_source.into()))
 }
 
-struct ResultIter<I, E>(Result<I, Option<E>>);
-
-impl<I: IntoIterator, E> From<Result<I, E>> for ResultIter<I::IntoIter, E> {
-    fn from(value: Result<I, E>) -> Self {
-        Self(match value {
-            Ok(i) => Ok(i.into_iter()),
-            Err(e) => Err(Some(e)),
-        })
-    }
-}
-
-impl<I: Iterator, E> Iterator for ResultIter<I, E> {
-    type Item = Result<I::Item, E>;
-
-    fn next(&mut self) -> Option<Self::Item> {
-        match &mut self.0 {
-            Ok(iter) => Some(Ok(iter.next()?)),
-            Err(err) => Some(Err(err.take()?)),
-        }
-    }
-}
-
-impl<I: FusedIterator, E> FusedIterator for ResultIter<I, E> {}
-
 #[derive(Debug, Clone, Hash, PartialEq)]
 pub(crate) enum Source {
     Path(Arc<str>),
     Source(Arc<str>),
 }
 
-#[derive(Clone, Copy, Debug, PartialEq, Hash)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Hash)]
 pub(crate) enum Print {
     All,
     Ast,
     Code,
+    #[default]
     None,
 }
 
-impl Default for Print {
-    fn default() -> Self {
-        Self::None
-    }
-}
-
 impl FromStr for Print {
     type Err = String;
 
@@ -718,6 +748,7 @@ pub(crate) struct PartialTemplateArgs {
     pub(crate) syntax: Option<LitStr>,
     pub(crate) config: Option<LitStr>,
     pub(crate) whitespace: Option<Whitespace>,
+    pub(crate) minify: Option<Minify>,
     pub(crate) crate_name: Option<ExprPath>,
     #[cfg(feature = "blocks")]
     pub(crate) blocks: Option<Vec<LitStr>>,
@@ -782,6 +813,7 @@ const _: () = {
             syntax: None,
             config: None,
             whitespace: None,
+            minify: None,
             crate_name: None,
             #[cfg(feature = "blocks")]
             blocks: None,
@@ -908,6 +940,8 @@ const _: () = {
                     set_strlit_pair(ident, value, &mut this.config)?;
                 } else if ident == "whitespace" {
                     set_parseable_string(ident, value, &mut this.whitespace)?;
+                } else if ident == "minify" {
+                    set_parseable_string(ident, value, &mut this.minify)?;
                 } else {
                     return Err(CompileError::no_file_info(
                         format_args!("unsupported template attribute `{ident}` found"),
@@ -1054,7 +1088,6 @@ const _: () = {
     }
 };
 
-#[cfg(feature = "code-in-doc")]
 const JINJA_EXTENSIONS: &[&str] = &["askama", "j2", "jinja", "jinja2", "rinja"];
 
 #[test]
@@ -1064,3 +1097,28 @@ fn get_source() {
         .unwrap();
     assert_eq!(get_template_source(&path, None).unwrap(), "bar".into());
 }
+
+#[test]
+fn test_extension() {
+    fn extension(attr: &str) -> Option<String> {
+        let ast: syn::DeriveInput =
+            syn::parse_str(&format!("#[template({attr})] struct S;")).unwrap();
+        TemplateArgs::new(&ast)
+            .unwrap()
+            .extension()
+            .map(str::to_owned)
+    }
+
+    assert_eq!(extension(r#"path = "a.html""#).as_deref(), Some("html"));
+    assert_eq!(extension(r#"path = "a.html.j2""#).as_deref(), Some("html"));
+    assert_eq!(
+        extension(r#"path = "a.json.jinja""#).as_deref(),
+        Some("json")
+    );
+    assert_eq!(extension(r#"path = "a.rinja""#).as_deref(), Some("rinja"));
+    assert_eq!(extension(r#"path = "a""#).as_deref(), None);
+    assert_eq!(
+        extension(r#"source = "", ext = "css""#).as_deref(),
+        Some("css")
+    );
+}
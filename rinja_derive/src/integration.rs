@@ -8,14 +8,35 @@ use syn::{
     Variant, parse_quote,
 };
 
+use crate::config::DEFAULT_MIME_TYPE;
 use crate::generator::TmplKind;
 use crate::input::{PartialTemplateArgs, TemplateArgs};
 use crate::{CompileError, build_template_item};
 
 /// Implement every integration for the given item
-pub(crate) fn impl_everything(ast: &DeriveInput, buf: &mut Buffer) {
+///
+/// `content_type` is an expression that evaluates to the `Content-Type` of `self`.
+pub(crate) fn impl_everything(ast: &DeriveInput, buf: &mut Buffer, content_type: &str) {
     impl_display(ast, buf);
     impl_fast_writable(ast, buf);
+    if cfg!(feature = "with-actix-web") {
+        impl_actix_web_responder(ast, buf, content_type);
+    }
+    if cfg!(feature = "with-axum") {
+        impl_axum_into_response(ast, buf, content_type);
+    }
+    if cfg!(feature = "with-poem") {
+        impl_poem_into_response(ast, buf, content_type);
+    }
+    if cfg!(feature = "with-rocket") {
+        impl_rocket_responder(ast, buf, content_type);
+    }
+    if cfg!(feature = "with-salvo") {
+        impl_salvo_scribe(ast, buf, content_type);
+    }
+    if cfg!(feature = "with-warp") {
+        impl_warp_reply(ast, buf, content_type);
+    }
 }
 
 /// Writes header for the `impl` for `TraitFromPathName` or `Template` for the given item
@@ -31,6 +52,113 @@ pub(crate) fn write_header(ast: &DeriveInput, buf: &mut Buffer, target: impl Dis
     ));
 }
 
+/// Like [`write_header`], but only implements `target` if the item is `Send`
+///
+/// The higher-ranked bound is not checked eagerly, so items that are not `Send` simply don't get
+/// the `impl` instead of failing to compile.
+fn write_send_header(ast: &DeriveInput, buf: &mut Buffer, target: impl Display) {
+    let mut generics = ast.generics.clone();
+    generics
+        .make_where_clause()
+        .predicates
+        .push(parse_quote!(for<'__rinja_send> Self: rinja::helpers::core::marker::Send));
+    let (impl_generics, _, where_clause) = generics.split_for_impl();
+    let (_, orig_ty_generics, _) = ast.generics.split_for_impl();
+
+    let ident = &ast.ident;
+    buf.write(format_args!(
+        "impl {} {} for {} {{",
+        quote!(#impl_generics),
+        target,
+        quote!(#ident #orig_ty_generics #where_clause),
+    ));
+}
+
+/// Writes the constants `Template::EXTENSION` and `Template::MIME_TYPE`
+pub(crate) fn write_content_type_consts(
+    buf: &mut Buffer,
+    extension: Option<&str>,
+    mime_type: &str,
+) {
+    write_extension_const(buf, extension);
+    write_mime_type_const(buf, mime_type);
+}
+
+/// Writes the constant `Template::EXTENSION`
+fn write_extension_const(buf: &mut Buffer, extension: Option<&str>) {
+    match extension {
+        Some(extension) => buf.write(format_args!(
+            "const EXTENSION: rinja::helpers::core::option::Option<&'static str> = \
+                rinja::helpers::core::option::Option::Some({extension:?});",
+        )),
+        None => buf.write(
+            "const EXTENSION: rinja::helpers::core::option::Option<&'static str> = \
+                rinja::helpers::core::option::Option::None;",
+        ),
+    }
+}
+
+/// Writes the constant `Template::MIME_TYPE`
+fn write_mime_type_const(buf: &mut Buffer, mime_type: &str) {
+    buf.write(format_args!(
+        "const MIME_TYPE: &'static str = {mime_type:?};"
+    ));
+}
+
+/// Writes the storage for `Template::adaptive_size_hint()`
+pub(crate) fn write_size_hint_cache(buf: &mut Buffer, size_hint: usize) {
+    buf.write(format_args!(
+        "#[inline] \
+        fn size_hint_cache() -> rinja::helpers::core::option::Option<\
+            &'static rinja::helpers::SizeHintCache\
+        > {{\
+            static CACHE: rinja::helpers::SizeHintCache =\
+                rinja::helpers::SizeHintCache::new({size_hint}usize);\
+            rinja::helpers::core::option::Option::Some(&CACHE)\
+        }}",
+    ));
+}
+
+/// Writes the constants `Template::RENDERED` and its compressed variants for a template that
+/// only consists of literal text
+pub(crate) fn write_rendered_consts(buf: &mut Buffer, text: &str) {
+    let lit = proc_macro2::Literal::string(text);
+    buf.write(format_args!(
+        "const RENDERED: rinja::helpers::core::option::Option<&'static str> = \
+            rinja::helpers::core::option::Option::Some({lit});",
+    ));
+
+    #[cfg(feature = "gzip")]
+    {
+        use std::io::Write as _;
+
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::best());
+        let compressed = encoder
+            .write_all(text.as_bytes())
+            .and_then(|()| encoder.finish());
+        if let Ok(compressed) = compressed {
+            let lit = proc_macro2::Literal::byte_string(&compressed);
+            buf.write(format_args!(
+                "const RENDERED_GZIP: rinja::helpers::core::option::Option<&'static [u8]> = \
+                    rinja::helpers::core::option::Option::Some({lit});",
+            ));
+        }
+    }
+
+    #[cfg(feature = "brotli")]
+    {
+        let mut compressed = Vec::new();
+        let params = brotli::enc::BrotliEncoderParams::default();
+        if brotli::BrotliCompress(&mut text.as_bytes(), &mut compressed, &params).is_ok() {
+            let lit = proc_macro2::Literal::byte_string(&compressed);
+            buf.write(format_args!(
+                "const RENDERED_BROTLI: rinja::helpers::core::option::Option<&'static [u8]> = \
+                    rinja::helpers::core::option::Option::Some({lit});",
+            ));
+        }
+    }
+}
+
 /// Implement `Display` for the given item.
 fn impl_display(ast: &DeriveInput, buf: &mut Buffer) {
     let ident = &ast.ident;
@@ -70,16 +198,123 @@ fn impl_fast_writable(ast: &DeriveInput, buf: &mut Buffer) {
             {\
                 rinja::Template::render_into(self, dest)\
             }\
+            #[inline]\
+            fn write_bytes_into<RinjaB>(&self, dest: &mut RinjaB) -> rinja::Result<()> \
+            where \
+                RinjaB: rinja::filters::ByteSink + ?rinja::helpers::core::marker::Sized,\
+            {\
+                rinja::Template::render_into_bytes(self, dest)\
+            }\
         }",
     );
 }
 
+/// Implement `actix_web::Responder` for the given item.
+fn impl_actix_web_responder(ast: &DeriveInput, buf: &mut Buffer, content_type: &str) {
+    write_header(ast, buf, "rinja::helpers::actix_web::Responder");
+    buf.write(format_args!(
+        "\
+            type Body = rinja::helpers::actix_web::BoxBody;\
+            #[inline]\
+            fn respond_to(\
+                self,\
+                _: &rinja::helpers::actix_web::HttpRequest,\
+            ) -> rinja::helpers::actix_web::HttpResponse<Self::Body> {{\
+                rinja::helpers::actix_web::respond(&self, {content_type})\
+            }}\
+        }}",
+    ));
+}
+
+/// Implement `axum_core::response::IntoResponse` for the given item.
+fn impl_axum_into_response(ast: &DeriveInput, buf: &mut Buffer, content_type: &str) {
+    write_header(ast, buf, "rinja::helpers::axum::IntoResponse");
+    buf.write(format_args!(
+        "\
+            #[inline]\
+            fn into_response(self) -> rinja::helpers::axum::Response {{\
+                rinja::helpers::axum::into_response(&self, {content_type})\
+            }}\
+        }}",
+    ));
+}
+
+/// Implement `poem::IntoResponse` for the given item.
+fn impl_poem_into_response(ast: &DeriveInput, buf: &mut Buffer, content_type: &str) {
+    write_send_header(ast, buf, "rinja::helpers::poem::IntoResponse");
+    buf.write(format_args!(
+        "\
+            #[inline]\
+            fn into_response(self) -> rinja::helpers::poem::Response {{\
+                rinja::helpers::poem::into_response(&self, {content_type})\
+            }}\
+        }}",
+    ));
+}
+
+/// Implement `rocket::response::Responder` for the given item.
+fn impl_rocket_responder(ast: &DeriveInput, buf: &mut Buffer, content_type: &str) {
+    let lifetime = Lifetime::new("'__rinja_r", ast.ident.span());
+    let mut generics = ast.generics.clone();
+    generics.params.insert(
+        0,
+        GenericParam::Lifetime(LifetimeParam::new(lifetime.clone())),
+    );
+    let (impl_generics, _, _) = generics.split_for_impl();
+    let (_, orig_ty_generics, where_clause) = ast.generics.split_for_impl();
+
+    let ident = &ast.ident;
+    buf.write(format_args!(
+        "\
+        impl {} rinja::helpers::rocket::Responder<{lifetime}, 'static> for {} {{\
+            #[inline]\
+            fn respond_to(\
+                self,\
+                _: &{lifetime} rinja::helpers::rocket::Request<'_>,\
+            ) -> rinja::helpers::rocket::Result<'static> {{\
+                rinja::helpers::rocket::respond(&self, {content_type})\
+            }}\
+        }}",
+        quote!(#impl_generics),
+        quote!(#ident #orig_ty_generics #where_clause),
+        lifetime = quote!(#lifetime),
+    ));
+}
+
+/// Implement `salvo_core::Scribe` for the given item.
+fn impl_salvo_scribe(ast: &DeriveInput, buf: &mut Buffer, content_type: &str) {
+    write_header(ast, buf, "rinja::helpers::salvo::Scribe");
+    buf.write(format_args!(
+        "\
+            #[inline]\
+            fn render(self, res: &mut rinja::helpers::salvo::Response) {{\
+                rinja::helpers::salvo::render(&self, {content_type}, res)\
+            }}\
+        }}",
+    ));
+}
+
+/// Implement `warp::Reply` for the given item.
+fn impl_warp_reply(ast: &DeriveInput, buf: &mut Buffer, content_type: &str) {
+    write_send_header(ast, buf, "rinja::helpers::warp::Reply");
+    buf.write(format_args!(
+        "\
+            #[inline]\
+            fn into_response(self) -> rinja::helpers::warp::Response {{\
+                rinja::helpers::warp::into_response(&self, {content_type})\
+            }}\
+        }}",
+    ));
+}
+
 #[derive(Debug)]
 pub(crate) struct Buffer {
     // The buffer to generate the code into
     buf: String,
     discard: bool,
     last_was_write_str: bool,
+    // Warnings that are emitted next to the generated code
+    warnings: Vec<String>,
 }
 
 impl Display for Buffer {
@@ -94,6 +329,7 @@ impl Buffer {
             buf: String::new(),
             discard: false,
             last_was_write_str: false,
+            warnings: Vec::new(),
         }
     }
 
@@ -101,6 +337,14 @@ impl Buffer {
         self.buf
     }
 
+    pub(crate) fn warn(&mut self, msg: String) {
+        self.warnings.push(msg);
+    }
+
+    pub(crate) fn take_warnings(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.warnings)
+    }
+
     pub(crate) fn is_discard(&self) -> bool {
         self.discard
     }
@@ -172,7 +416,13 @@ impl Buffer {
     }
 
     pub(crate) fn marked_text(&self, mark: usize) -> &str {
-        &self.buf[..mark]
+        &self.buf[mark..]
+    }
+
+    /// Removes everything that was written after the `mark`
+    pub(crate) fn truncate(&mut self, mark: usize) {
+        self.buf.truncate(mark);
+        self.last_was_write_str = false;
     }
 }
 
@@ -250,8 +500,6 @@ pub(crate) fn build_template_enum(
         unreachable!();
     };
 
-    impl_everything(enum_ast, buf);
-
     let enum_id = &enum_ast.ident;
     let enum_span = enum_id.span();
     let lifetime = Lifetime::new(&format!("'__Rinja_{enum_id}"), enum_span);
@@ -268,6 +516,8 @@ pub(crate) fn build_template_enum(
         .insert(0, GenericParam::Lifetime(LifetimeParam::new(lifetime)));
 
     let mut biggest_size_hint = 0;
+    let mut content_types: Vec<(Option<String>, String)> = Vec::new();
+    let mut mime_type_arms = TokenStream::new();
     let mut render_into_arms = TokenStream::new();
     let mut size_hint_arms = TokenStream::new();
     for (var, var_args) in enum_data.variants.iter().zip(vars_args) {
@@ -286,14 +536,20 @@ pub(crate) fn build_template_enum(
             set_default(&mut var_args, enum_args, |v| &mut v.syntax);
             set_default(&mut var_args, enum_args, |v| &mut v.config);
             set_default(&mut var_args, enum_args, |v| &mut v.whitespace);
+            set_default(&mut var_args, enum_args, |v| &mut v.minify);
         }
-        let size_hint = biggest_size_hint.max(build_template_item(
+        let item = build_template_item(
             buf,
             &var_ast,
             Some(enum_ast),
             &TemplateArgs::from_partial(&var_ast, Some(var_args))?,
             TmplKind::Variant,
-        )?);
+        )?;
+        let size_hint = biggest_size_hint.max(item.size_hint);
+        let ident = &var.ident;
+        let mime_type = &item.mime_type;
+        mime_type_arms.extend(quote!(Self :: #ident { .. } => #mime_type,));
+        content_types.push((item.extension, item.mime_type));
         biggest_size_hint = biggest_size_hint.max(size_hint);
 
         variant_as_arm(
@@ -305,13 +561,17 @@ pub(crate) fn build_template_enum(
         );
     }
     if has_default_impl {
-        let size_hint = build_template_item(
+        let item = build_template_item(
             buf,
             enum_ast,
             None,
             &TemplateArgs::from_partial(enum_ast, enum_args)?,
             TmplKind::Variant,
         )?;
+        let size_hint = item.size_hint;
+        let mime_type = &item.mime_type;
+        mime_type_arms.extend(quote!(_ => #mime_type,));
+        content_types.push((item.extension, item.mime_type));
         biggest_size_hint = biggest_size_hint.max(size_hint);
 
         render_into_arms.extend(quote! {
@@ -330,6 +590,28 @@ pub(crate) fn build_template_enum(
         });
     }
 
+    // The constants `EXTENSION` and `MIME_TYPE` are only written if all templates agree on them,
+    // which is the common case. Otherwise the enum has no single value, so the defaults of the
+    // trait are used, and only the web-framework integrations select the `Content-Type` of the
+    // actual variant.
+    let extension = match &content_types[..] {
+        [(extension, _), rest @ ..] if rest.iter().all(|(e, _)| e == extension) => {
+            Some(extension.as_deref())
+        }
+        _ => None,
+    };
+    let mime_type = match &content_types[..] {
+        [(_, mime_type), rest @ ..] if rest.iter().all(|(_, m)| m == mime_type) => {
+            Some(mime_type.as_str())
+        }
+        _ => None,
+    };
+    let content_type = match mime_type {
+        Some(mime_type) => format!("{mime_type:?}"),
+        None if content_types.is_empty() => format!("{DEFAULT_MIME_TYPE:?}"),
+        None => quote!(match self { #mime_type_arms }).to_string(),
+    };
+    impl_everything(enum_ast, buf, &content_type);
     write_header(enum_ast, buf, "rinja::Template");
     buf.write(format_args!(
         "\
@@ -366,9 +648,16 @@ pub(crate) fn build_template_enum(
 
     buf.write(format_args!(
         "\
-        const SIZE_HINT: rinja::helpers::core::primitive::usize = {biggest_size_hint}usize;\
-        }}",
+        const SIZE_HINT: rinja::helpers::core::primitive::usize = {biggest_size_hint}usize;",
     ));
+    write_size_hint_cache(buf, biggest_size_hint);
+    if let Some(extension) = extension {
+        write_extension_const(buf, extension);
+    }
+    if let Some(mime_type) = mime_type {
+        write_mime_type_const(buf, mime_type);
+    }
+    buf.write('}');
     Ok(biggest_size_hint)
 }
 
@@ -8,22 +8,24 @@ mod heritage;
 mod html;
 mod input;
 mod integration;
+mod minify;
 #[cfg(test)]
 mod tests;
+mod validate_html;
 
 use std::borrow::{Borrow, Cow};
 use std::collections::hash_map::{Entry, HashMap};
-use std::fmt;
 use std::hash::{BuildHasher, Hash};
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::sync::Mutex;
+use std::{env, fmt, fs};
 
 use config::{Config, read_config_file};
 use generator::{TmplKind, template_to_string};
 use heritage::{Context, Heritage};
 use input::{AnyTemplateArgs, Print, TemplateArgs, TemplateInput};
 use integration::{Buffer, build_template_enum};
-use parser::{Parsed, strip_common};
+use parser::{Node, Parsed, strip_common};
 #[cfg(not(feature = "__standalone"))]
 use proc_macro::TokenStream as TokenStream12;
 #[cfg(feature = "__standalone")]
@@ -31,6 +33,7 @@ use proc_macro2::TokenStream as TokenStream12;
 use proc_macro2::{Delimiter, Group, Span, TokenStream, TokenTree};
 use quote::{quote, quote_spanned};
 use rustc_hash::FxBuildHasher;
+use validate_html::validate_html;
 
 /// The `Template` derive macro and its `template()` attribute.
 ///
@@ -98,7 +101,9 @@ use rustc_hash::FxBuildHasher;
 ///
 /// Enable debugging by printing nothing (`none`), the parsed syntax tree (`ast`),
 /// the generated code (`code`) or `all` for both.
-/// The requested data will be printed to stdout at compile time.
+/// The requested data will be written into the file `{StructName}-{hash}.rs` in the `dump_dir`
+/// configured in `rinja.toml`, or in `$OUT_DIR/rinja` if your crate has a build script.
+/// Otherwise it will be printed to stderr at compile time.
 ///
 /// ### block
 ///
@@ -223,11 +228,16 @@ pub fn derive_template(input: TokenStream12) -> TokenStream12 {
         .unwrap_or_default();
 
     let result = args.and_then(|args| build_template(&mut buf, &ast, args));
-    let ts = if let Err(CompileError { msg, span }) = result {
-        let mut ts = quote_spanned! {
-            span.unwrap_or(ast.ident.span()) =>
-            rinja::helpers::core::compile_error!(#msg);
-        };
+    let ts = if let Err(err) = result {
+        // Errors without a span of their own are reported at the same location as the first one.
+        let first_span = err.span.unwrap_or(ast.ident.span());
+        let mut ts = TokenStream::new();
+        for CompileError { msg, span, .. } in err.into_all() {
+            ts.extend(quote_spanned! {
+                span.unwrap_or(first_span) =>
+                rinja::helpers::core::compile_error!(#msg);
+            });
+        }
         buf.clear();
         if build_skeleton(&mut buf, &ast).is_ok() {
             let source: TokenStream = buf.into_string().parse().unwrap();
@@ -235,7 +245,23 @@ pub fn derive_template(input: TokenStream12) -> TokenStream12 {
         }
         ts
     } else {
-        buf.into_string().parse().unwrap()
+        // Proc-macros cannot emit warnings on stable Rust, so we use a deprecated item instead.
+        // It must have the span of the input, or else the lint would not be reported.
+        let span = ast.ident.span();
+        let mut ts = TokenStream::new();
+        for msg in buf.take_warnings() {
+            ts.extend(quote_spanned! {
+                span =>
+                const _: () = {
+                    #[deprecated(note = #msg)]
+                    #[allow(non_upper_case_globals)]
+                    const rinja_unused_definition: () = ();
+                    rinja_unused_definition
+                };
+            });
+        }
+        ts.extend(buf.into_string().parse::<TokenStream>().unwrap());
+        ts
     };
 
     let ts = TokenTree::Group(Group::new(Delimiter::None, ts));
@@ -283,7 +309,7 @@ pub(crate) fn build_template(
     let mut result = match args {
         AnyTemplateArgs::Struct(item) => {
             err_span = item.source.1.or(item.template_span);
-            build_template_item(buf, ast, None, &item, TmplKind::Struct)
+            build_template_item(buf, ast, None, &item, TmplKind::Struct).map(|item| item.size_hint)
         }
         AnyTemplateArgs::Enum {
             enum_args,
@@ -306,13 +332,20 @@ pub(crate) fn build_template(
     result
 }
 
+/// Properties of a generated template item, that are needed to implement `Template` for an enum
+struct TemplateItem {
+    size_hint: usize,
+    extension: Option<String>,
+    mime_type: String,
+}
+
 fn build_template_item(
     buf: &mut Buffer,
     ast: &syn::DeriveInput,
     enum_ast: Option<&syn::DeriveInput>,
     template_args: &TemplateArgs,
     tmpl_kind: TmplKind<'_>,
-) -> Result<usize, CompileError> {
+) -> Result<TemplateItem, CompileError> {
     let config_path = template_args.config_path();
     let s = read_config_file(config_path, template_args.config_span)?;
     let config = Config::new(
@@ -332,6 +365,9 @@ fn build_template_item(
     }
 
     let ctx = &contexts[&input.path];
+    if input.config.validate_html && input.mime_type.starts_with("text/html") {
+        validate_html(ctx)?;
+    }
     let heritage = if !ctx.blocks.is_empty() || ctx.extends.is_some() {
         Some(Heritage::new(ctx, &contexts))
     } else {
@@ -351,22 +387,88 @@ fn build_template_item(
         }
     }
 
-    if input.print == Print::Ast || input.print == Print::All {
-        eprintln!("{:?}", templates[&input.path].nodes());
+    // The parse tree is dumped before the code is generated, so it is available if that fails.
+    let nodes =
+        matches!(input.print, Print::Ast | Print::All).then(|| templates[&input.path].nodes());
+    if nodes.is_some() {
+        dump_template(&input, nodes, None)?;
     }
-
     let mark = buf.get_mark();
     let size_hint = template_to_string(buf, &input, &contexts, heritage.as_ref(), tmpl_kind)?;
-    if input.print == Print::Code || input.print == Print::All {
-        eprintln!("{}", buf.marked_text(mark));
+    if matches!(input.print, Print::Code | Print::All) {
+        dump_template(&input, nodes, Some(buf.marked_text(mark)))?;
+    }
+    Ok(TemplateItem {
+        size_hint,
+        extension: input.extension.map(str::to_owned),
+        mime_type: input.mime_type.to_owned(),
+    })
+}
+
+/// Writes the parse tree and/or the generated code of a template into the file
+/// `{ident}-{hash}.rs`.
+///
+/// The file is written into the `dump_dir` of the config, or into `$OUT_DIR/rinja` if the crate
+/// has a build script. Otherwise the data is printed to stderr, where the parse tree is only
+/// printed if there is no `code`, because it was already printed before the code was generated.
+///
+/// The hash of the derive input tells apart types with the same name in different modules.
+fn dump_template(
+    input: &TemplateInput<'_>,
+    nodes: Option<&[Node<'_>]>,
+    code: Option<&str>,
+) -> Result<(), CompileError> {
+    let dir = match (&input.config.dump_dir, env::var_os("OUT_DIR")) {
+        (Some(dir), _) => dir.clone(),
+        (None, Some(out_dir)) => PathBuf::from(out_dir).join("rinja"),
+        (None, None) => {
+            match (nodes, code) {
+                (_, Some(code)) => eprintln!("{}", pretty_print(code)),
+                (Some(nodes), None) => eprintln!("{nodes:?}"),
+                (None, None) => {}
+            }
+            return Ok(());
+        }
+    };
+
+    let mut content = String::new();
+    if let Some(nodes) = nodes {
+        for line in format!("{nodes:#?}").lines() {
+            content.push_str("// ");
+            content.push_str(line);
+            content.push('\n');
+        }
+    }
+    if let Some(code) = code {
+        content.push_str(&pretty_print(code));
+    }
+
+    let ast = input.ast;
+    let hash = FxBuildHasher.hash_one(quote!(#ast).to_string());
+    let path = dir.join(format!("{}-{hash:016x}.rs", ast.ident));
+    if let Err(err) = fs::create_dir_all(&dir).and_then(|()| fs::write(&path, content)) {
+        return Err(CompileError::no_file_info(
+            format_args!("could not write `{}`: {err}", path.display()),
+            input.source_span,
+        ));
+    }
+    Ok(())
+}
+
+/// Formats the generated `code`, or returns it as is if it cannot be parsed
+fn pretty_print(code: &str) -> String {
+    match syn::parse_file(code) {
+        Ok(file) => prettyplease::unparse(&file),
+        Err(_) => format!("{code}\n"),
     }
-    Ok(size_hint)
 }
 
 #[derive(Debug, Clone)]
 struct CompileError {
     msg: String,
     span: Option<Span>,
+    /// Further errors that were found in the same pass, e.g. all syntax errors of a template
+    others: Vec<CompileError>,
 }
 
 impl CompileError {
@@ -383,15 +485,34 @@ impl CompileError {
             Some(file_info) => format!("{msg}{file_info}"),
             None => msg.to_string(),
         };
-        Self { msg, span }
+        Self {
+            msg,
+            span,
+            others: Vec::new(),
+        }
     }
 
     fn no_file_info<S: ToString>(msg: S, span: Option<Span>) -> Self {
         Self {
             msg: msg.to_string(),
             span,
+            others: Vec::new(),
         }
     }
+
+    /// Combines a non-empty list of errors into one error, so they can be reported together.
+    fn from_many(errors: impl IntoIterator<Item = CompileError>) -> Option<Self> {
+        let mut errors = errors.into_iter();
+        let mut first = errors.next()?;
+        first.others.extend(errors);
+        Some(first)
+    }
+
+    /// Returns this error, followed by all errors that were found in the same pass.
+    fn into_all(mut self) -> impl Iterator<Item = CompileError> {
+        let others = std::mem::take(&mut self.others);
+        std::iter::once(self).chain(others)
+    }
 }
 
 impl std::error::Error for CompileError {}
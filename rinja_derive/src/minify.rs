@@ -0,0 +1,486 @@
+use std::borrow::Cow;
+use std::str::FromStr;
+
+#[cfg(feature = "config")]
+use serde_derive::Deserialize;
+
+use crate::validate_html::RAW_TEXT_ELEMENTS;
+
+/// How the literal text of a template is minified at compile time
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug, Hash)]
+#[cfg_attr(feature = "config", derive(Deserialize))]
+#[cfg_attr(feature = "config", serde(rename_all = "lowercase"))]
+pub(crate) enum Minify {
+    #[default]
+    None,
+    Html,
+}
+
+impl FromStr for Minify {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "none" => Ok(Minify::None),
+            "html" => Ok(Minify::Html),
+            s => Err(format!("invalid value for `minify`: {s:?}")),
+        }
+    }
+}
+
+/// Minifies the literal text of an HTML template
+///
+/// The literals of a template are interrupted by expressions and blocks, so the minifier keeps
+/// track of its position in the document between calls of [`HtmlMinifier::minify()`].
+/// Anything that cannot be minified safely without knowing the surrounding text, e.g. a comment
+/// that contains an expression, is left as is.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub(crate) struct HtmlMinifier {
+    state: State,
+    /// Set if the last output was the `>` of a tag of a block-level element
+    after_block_tag: bool,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+enum State {
+    /// Text between tags
+    #[default]
+    Text,
+    /// Inside a tag; `raw` is the name of the element if its content must not be changed
+    Tag {
+        quote: Option<u8>,
+        raw: Option<&'static str>,
+        block: bool,
+    },
+    /// Inside a comment that could not be removed because it contains expressions or blocks
+    Comment,
+    /// Inside the content of e.g. `<pre>` until its end tag
+    Raw(&'static str),
+    /// After branches that ended in different states, so nothing can be minified anymore
+    Unknown,
+}
+
+/// Returns the name of the element if its content must be kept as is: the content of e.g.
+/// `<script>` is not HTML, and whitespace is significant in `<pre>`
+fn raw_element(name: &str) -> Option<&'static str> {
+    ["pre"]
+        .iter()
+        .chain(RAW_TEXT_ELEMENTS)
+        .find(|raw| raw.eq_ignore_ascii_case(name))
+        .copied()
+}
+
+/// Elements that are not rendered inline, so whitespace between their tags can be dropped
+const BLOCK_ELEMENTS: &[&str] = &[
+    "address",
+    "article",
+    "aside",
+    "blockquote",
+    "body",
+    "dd",
+    "details",
+    "dialog",
+    "div",
+    "dl",
+    "dt",
+    "fieldset",
+    "figcaption",
+    "figure",
+    "footer",
+    "form",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "head",
+    "header",
+    "hgroup",
+    "hr",
+    "html",
+    "li",
+    "link",
+    "main",
+    "meta",
+    "nav",
+    "ol",
+    "p",
+    "pre",
+    "script",
+    "section",
+    "style",
+    "summary",
+    "table",
+    "tbody",
+    "td",
+    "tfoot",
+    "th",
+    "thead",
+    "title",
+    "tr",
+    "ul",
+];
+
+impl HtmlMinifier {
+    /// Returns `true` if whitespace at the current position may be collapsed
+    pub(crate) fn may_collapse_whitespace(&self) -> bool {
+        matches!(self.state, State::Text | State::Tag { quote: None, .. })
+    }
+
+    /// Returns the state after either `self` or `other`, which are the states at the end of
+    /// e.g. two branches of an `{% if %}` block
+    pub(crate) fn join(self, other: Self) -> Self {
+        match self.state == other.state {
+            true => Self {
+                state: self.state,
+                after_block_tag: self.after_block_tag && other.after_block_tag,
+            },
+            false => Self {
+                state: State::Unknown,
+                after_block_tag: false,
+            },
+        }
+    }
+
+    pub(crate) fn minify<'a>(&mut self, text: &'a str) -> Cow<'a, str> {
+        let mut out = String::with_capacity(text.len());
+        let mut rest = text;
+        while !rest.is_empty() {
+            rest = match self.state {
+                State::Text => self.text(&mut out, rest),
+                State::Tag {
+                    quote: None,
+                    raw,
+                    block,
+                } => self.tag(&mut out, rest, raw, block),
+                State::Tag {
+                    quote: Some(quote),
+                    raw,
+                    block,
+                } => match rest.bytes().position(|c| c == quote) {
+                    Some(end) => {
+                        out.push_str(&rest[..=end]);
+                        self.state = State::Tag {
+                            quote: None,
+                            raw,
+                            block,
+                        };
+                        &rest[end + 1..]
+                    }
+                    None => {
+                        out.push_str(rest);
+                        ""
+                    }
+                },
+                State::Comment => match rest.find("-->") {
+                    Some(end) => {
+                        out.push_str(&rest[..end + 3]);
+                        self.state = State::Text;
+                        &rest[end + 3..]
+                    }
+                    None => {
+                        out.push_str(rest);
+                        ""
+                    }
+                },
+                State::Raw(name) => match find_end_tag(rest, name) {
+                    Some(end) => {
+                        out.push_str(&rest[..end]);
+                        self.state = State::Text;
+                        &rest[end..]
+                    }
+                    None => {
+                        out.push_str(rest);
+                        ""
+                    }
+                },
+                State::Unknown => {
+                    out.push_str(rest);
+                    ""
+                }
+            };
+        }
+        match out == text {
+            true => Cow::Borrowed(text),
+            false => Cow::Owned(out),
+        }
+    }
+
+    /// Handles the text up to the next tag, comment or whitespace
+    fn text<'a>(&mut self, out: &mut String, rest: &'a str) -> &'a str {
+        if let Some(comment) = rest.strip_prefix("<!--") {
+            return match comment.find("-->") {
+                // keep conditional comments like `<!--[if IE]>`
+                Some(end) if comment.starts_with('[') => {
+                    out.push_str(&rest[..end + 7]);
+                    &comment[end + 3..]
+                }
+                Some(end) => &comment[end + 3..],
+                None => {
+                    out.push_str(rest);
+                    self.state = State::Comment;
+                    ""
+                }
+            };
+        }
+
+        let bytes = rest.as_bytes();
+        match bytes[0] {
+            b'<' if bytes
+                .get(1)
+                .is_some_and(|&c| c.is_ascii_alphabetic() || c == b'!') =>
+            {
+                let name = tag_name(&rest[1..]);
+                out.push('<');
+                self.state = State::Tag {
+                    quote: None,
+                    raw: raw_element(name),
+                    // `<!DOCTYPE html>`
+                    block: bytes[1] == b'!' || is_block_element(name),
+                };
+                &rest[1..]
+            }
+            b'<' if bytes.get(1) == Some(&b'/') => {
+                out.push_str("</");
+                self.state = State::Tag {
+                    quote: None,
+                    raw: None,
+                    block: is_block_element(tag_name(&rest[2..])),
+                };
+                &rest[2..]
+            }
+            c if c.is_ascii_whitespace() => {
+                let (ws, rest) = split_whitespace(rest);
+                // whitespace that only separates two block-level tags on different lines is
+                // dropped, otherwise it would be rendered as a space; a comment is removed, so
+                // the whitespace after it is checked against the tag before it
+                let between_block_tags = self.after_block_tag
+                    && out.ends_with('>')
+                    && (rest.starts_with("<!--")
+                        || rest.starts_with('<')
+                            && is_block_element(tag_name(rest[1..].trim_start_matches('/'))));
+                if !(between_block_tags && ws.contains('\n')) {
+                    let ws = if ws.contains('\n') { '\n' } else { ' ' };
+                    // the whitespace before and after a removed comment is collapsed, too
+                    match out.chars().next_back() {
+                        Some(' ') => {
+                            out.pop();
+                            out.push(ws);
+                        }
+                        Some('\n') => {}
+                        _ => out.push(ws),
+                    }
+                    self.after_block_tag = false;
+                }
+                rest
+            }
+            _ => {
+                let end = rest
+                    .bytes()
+                    .position(|c| c == b'<' || c.is_ascii_whitespace())
+                    .unwrap_or(rest.len())
+                    .max(1);
+                let end = ceil_char_boundary(rest, end);
+                out.push_str(&rest[..end]);
+                self.after_block_tag = false;
+                &rest[end..]
+            }
+        }
+    }
+
+    /// Handles the content of a tag outside of quoted attribute values
+    fn tag<'a>(
+        &mut self,
+        out: &mut String,
+        rest: &'a str,
+        raw: Option<&'static str>,
+        block: bool,
+    ) -> &'a str {
+        let bytes = rest.as_bytes();
+        match bytes[0] {
+            b'>' => {
+                out.push('>');
+                self.state = match raw {
+                    Some(raw) => State::Raw(raw),
+                    None => State::Text,
+                };
+                self.after_block_tag = block;
+                &rest[1..]
+            }
+            c if c.is_ascii_whitespace() => {
+                let (_, rest) = split_whitespace(rest);
+                if !rest.starts_with('>') {
+                    out.push(' ');
+                }
+                rest
+            }
+            b'=' if matches!(bytes.get(1), Some(b'"' | b'\'')) => {
+                let quote = bytes[1];
+                let value = &rest[2..];
+                if let Some(end) = value.bytes().position(|c| c == quote) {
+                    let after = value.as_bytes().get(end + 1);
+                    let value = &value[..end];
+                    let can_unquote = !value.is_empty()
+                        && !value.bytes().any(|c| {
+                            c.is_ascii_whitespace()
+                                || matches!(c, b'"' | b'\'' | b'=' | b'<' | b'>' | b'`')
+                        })
+                        && after.is_some_and(|&c| c == b'>' || c.is_ascii_whitespace());
+                    if can_unquote {
+                        out.push('=');
+                        out.push_str(value);
+                        return &rest[end + 3..];
+                    }
+                }
+                out.push('=');
+                out.push(quote as char);
+                self.state = State::Tag {
+                    quote: Some(quote),
+                    raw,
+                    block,
+                };
+                &rest[2..]
+            }
+            quote @ (b'"' | b'\'') => {
+                out.push(quote as char);
+                self.state = State::Tag {
+                    quote: Some(quote),
+                    raw,
+                    block,
+                };
+                &rest[1..]
+            }
+            _ => {
+                let end = rest
+                    .bytes()
+                    .position(|c| {
+                        matches!(c, b'>' | b'=' | b'"' | b'\'') || c.is_ascii_whitespace()
+                    })
+                    .unwrap_or(rest.len())
+                    .max(1);
+                let end = ceil_char_boundary(rest, end);
+                out.push_str(&rest[..end]);
+                &rest[end..]
+            }
+        }
+    }
+}
+
+fn tag_name(s: &str) -> &str {
+    let end = s
+        .bytes()
+        .position(|c| !c.is_ascii_alphanumeric())
+        .unwrap_or(s.len());
+    &s[..end]
+}
+
+fn is_block_element(name: &str) -> bool {
+    BLOCK_ELEMENTS
+        .iter()
+        .any(|block| block.eq_ignore_ascii_case(name))
+}
+
+fn split_whitespace(s: &str) -> (&str, &str) {
+    let end = s
+        .bytes()
+        .position(|c| !c.is_ascii_whitespace())
+        .unwrap_or(s.len());
+    s.split_at(end)
+}
+
+/// Returns the index of the end tag `</{name}`, ignoring the case of the name
+fn find_end_tag(s: &str, name: &str) -> Option<usize> {
+    s.match_indices("</").map(|(idx, _)| idx).find(|&idx| {
+        s.get(idx + 2..idx + 2 + name.len())
+            .is_some_and(|tag| tag.eq_ignore_ascii_case(name))
+    })
+}
+
+fn ceil_char_boundary(s: &str, mut idx: usize) -> usize {
+    while !s.is_char_boundary(idx) {
+        idx += 1;
+    }
+    idx
+}
+
+#[test]
+fn test_minify_html() {
+    fn minify(text: &str) -> String {
+        HtmlMinifier::default().minify(text).into_owned()
+    }
+
+    assert_eq!(
+        minify("<ul>\n    <li>a</li>\n    <li>b</li>\n</ul>"),
+        "<ul><li>a</li><li>b</li></ul>",
+    );
+    assert_eq!(minify("<b>a</b> <i>b</i>"), "<b>a</b> <i>b</i>");
+    assert_eq!(minify("some   text\n\n  here"), "some text\nhere");
+    assert_eq!(minify("<p><!-- a comment -->text</p>"), "<p>text</p>");
+    assert_eq!(minify("a <!-- c --> b"), "a b");
+    assert_eq!(minify("a <!-- c -->\n  b"), "a\nb");
+    assert_eq!(
+        minify("<!--[if IE]><p>IE</p><![endif]-->"),
+        "<!--[if IE]><p>IE</p><![endif]-->"
+    );
+    assert_eq!(
+        minify(r#"<a  href="/index.html"  class="a b"  >x</a>"#),
+        r#"<a href=/index.html class="a b">x</a>"#,
+    );
+    assert_eq!(minify(r#"<input value="" />"#), r#"<input value="" />"#);
+    assert_eq!(minify(r#"<a href="x"/>"#), r#"<a href="x"/>"#);
+    assert_eq!(
+        minify("<pre>\n  a   b\n</pre>\n<p>  x  </p>"),
+        "<pre>\n  a   b\n</pre><p> x </p>",
+    );
+    assert_eq!(
+        minify("<SCRIPT>if (a  <  b) {}</script >"),
+        "<SCRIPT>if (a  <  b) {}</script>",
+    );
+    assert_eq!(minify("<p>grüße   <b>ü</b></p>"), "<p>grüße <b>ü</b></p>");
+    // whitespace between inline elements is rendered
+    assert_eq!(minify("<a>x</a>\n  <a>y</a>"), "<a>x</a>\n<a>y</a>");
+    assert_eq!(
+        minify("<div>\n  <span>x</span>\n</div>"),
+        "<div>\n<span>x</span>\n</div>"
+    );
+    assert_eq!(
+        minify("<!DOCTYPE html>\n<html>\n<p>x</p>\n</html>"),
+        "<!DOCTYPE html><html><p>x</p></html>",
+    );
+}
+
+#[test]
+fn test_minify_html_across_literals() {
+    let mut minifier = HtmlMinifier::default();
+    assert_eq!(minifier.minify(r#"<a title="  a  "#), r#"<a title="  a  "#);
+    assert!(!minifier.may_collapse_whitespace());
+    assert_eq!(minifier.minify(r#"  b  ">"#), r#"  b  ">"#);
+    assert!(minifier.may_collapse_whitespace());
+
+    let mut minifier = HtmlMinifier::default();
+    assert_eq!(minifier.minify("<!-- "), "<!-- ");
+    assert_eq!(minifier.minify(" -->  <p>"), " --> <p>");
+
+    let mut minifier = HtmlMinifier::default();
+    assert_eq!(minifier.minify("<textarea>  "), "<textarea>  ");
+    assert!(!minifier.may_collapse_whitespace());
+    assert_eq!(minifier.minify("  </textarea>  <p>"), "  </textarea> <p>");
+
+    let mut a = HtmlMinifier::default();
+    let mut b = HtmlMinifier::default();
+    assert_eq!(a.minify("<div>"), "<div>");
+    assert_eq!(b.minify("<pre>"), "<pre>");
+    let mut joined = a.join(b);
+    assert!(!joined.may_collapse_whitespace());
+    assert_eq!(joined.minify("  a  </pre>"), "  a  </pre>");
+
+    // only the tag before differs, so the text after can still be minified
+    let mut a = HtmlMinifier::default();
+    let mut b = HtmlMinifier::default();
+    assert_eq!(a.minify("<div>"), "<div>");
+    assert_eq!(b.minify("<span>"), "<span>");
+    let mut joined = a.join(b);
+    assert!(joined.may_collapse_whitespace());
+    assert_eq!(joined.minify("\n  a   b\n  <p>"), "\na b\n<p>");
+}
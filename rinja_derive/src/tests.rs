@@ -22,9 +22,37 @@ fn build_template(ast: &syn::DeriveInput) -> Result<String, crate::CompileError>
 // the code we want to check.
 #[track_caller]
 fn compare(jinja: &str, expected: &str, fields: &[(&str, &str)], size_hint: usize) {
-    let generated = jinja_to_rust(jinja, fields).unwrap();
+    compare_ex(jinja, expected, fields, size_hint, "");
+}
+
+// Like `compare()`, but the template implements the additional associated constants `consts`.
+#[track_caller]
+fn compare_ex(
+    jinja: &str,
+    expected: &str,
+    fields: &[(&str, &str)],
+    size_hint: usize,
+    consts: &str,
+) {
+    let mut generated = jinja_to_rust(jinja, fields).unwrap();
+    // The web-framework integrations are tested in `testing/tests/web_frameworks.rs`.
+    generated.items.retain(|item| !is_web_framework_impl(item));
+    // The compressed output only exists if the feature `"gzip"` or `"brotli"` is enabled, and it
+    // is tested in `testing/tests/rendered.rs`.
+    for item in &mut generated.items {
+        if let syn::Item::Impl(item) = item {
+            item.items.retain(|item| {
+                !matches!(
+                    item,
+                    syn::ImplItem::Const(item)
+                        if item.ident == "RENDERED_GZIP" || item.ident == "RENDERED_BROTLI"
+                )
+            });
+        }
+    }
 
     let expected: proc_macro2::TokenStream = expected.parse().unwrap();
+    let consts: proc_macro2::TokenStream = consts.parse().unwrap();
     let expected: syn::File = syn::parse_quote! {
         impl rinja::Template for Foo {
             fn render_into_with_values<RinjaW>(
@@ -34,16 +62,42 @@ fn compare(jinja: &str, expected: &str, fields: &[(&str, &str)], size_hint: usiz
             ) -> rinja::Result<()>
             where
                 RinjaW: rinja::helpers::core::fmt::Write + ?rinja::helpers::core::marker::Sized,
+            {
+                rinja::Template::render_into_sink(
+                    self,
+                    &mut rinja::helpers::FmtSink(__rinja_writer),
+                    __rinja_values,
+                )
+            }
+            fn render_into_sink<RinjaS>(
+                &self,
+                __rinja_writer: &mut RinjaS,
+                __rinja_values: &dyn rinja::Values,
+            ) -> rinja::Result<()>
+            where
+                RinjaS: rinja::helpers::Sink + ?rinja::helpers::core::marker::Sized,
             {
                 #[allow(unused_imports)]
                 use rinja::{
                     filters::{AutoEscape as _, WriteWritable as _},
-                    helpers::{ResultConverter as _, core::fmt::Write as _},
+                    helpers::{InContains as _, ResultConverter as _, core::fmt::Write as _},
                 };
                 #expected
                 rinja::Result::Ok(())
             }
             const SIZE_HINT: rinja::helpers::core::primitive::usize = #size_hint;
+            #[inline]
+            fn size_hint_cache() -> rinja::helpers::core::option::Option<
+                &'static rinja::helpers::SizeHintCache
+            > {
+                static CACHE: rinja::helpers::SizeHintCache =
+                    rinja::helpers::SizeHintCache::new(#size_hint);
+                rinja::helpers::core::option::Option::Some(&CACHE)
+            }
+            const EXTENSION: rinja::helpers::core::option::Option<&'static str> =
+                rinja::helpers::core::option::Option::Some("txt");
+            const MIME_TYPE: &'static str = "text/plain; charset=utf-8";
+            #consts
         }
 
         /// Implement the [`format!()`][rinja::helpers::std::format] trait for [`Foo`]
@@ -64,6 +118,13 @@ fn compare(jinja: &str, expected: &str, fields: &[(&str, &str)], size_hint: usiz
             {
                 rinja::Template::render_into(self, dest)
             }
+            #[inline]
+            fn write_bytes_into<RinjaB>(&self, dest: &mut RinjaB) -> rinja::Result<()>
+            where
+                RinjaB: rinja::filters::ByteSink + ?rinja::helpers::core::marker::Sized,
+            {
+                rinja::Template::render_into_bytes(self, dest)
+            }
         }
     };
 
@@ -120,6 +181,27 @@ fn compare(jinja: &str, expected: &str, fields: &[(&str, &str)], size_hint: usiz
     }
 }
 
+/// Returns `true` if `item` implements a trait of a web-framework integration, which only exists
+/// if the matching `"with-*"` feature is enabled
+fn is_web_framework_impl(item: &syn::Item) -> bool {
+    let syn::Item::Impl(item) = item else {
+        return false;
+    };
+    let Some((_, path, _)) = &item.trait_ else {
+        return false;
+    };
+    let mut segments = path
+        .segments
+        .iter()
+        .map(|segment| segment.ident.to_string());
+    segments.next().as_deref() == Some("rinja")
+        && segments.next().as_deref() == Some("helpers")
+        && matches!(
+            segments.next().as_deref(),
+            Some("actix_web" | "axum" | "poem" | "rocket" | "salvo" | "warp"),
+        )
+}
+
 fn jinja_to_rust(jinja: &str, fields: &[(&str, &str)]) -> syn::Result<syn::File> {
     let jinja = format!(
         r##"#[template(source = {jinja:?}, ext = "txt")]
@@ -768,7 +850,7 @@ fn check_escaping_at_compile_time() {
         16,
     );
 
-    compare(
+    compare_ex(
         r#"{{ '\x41' }}{{ '\n' }}{{ '\r' }}{{ '\t' }}{{ '\\' }}{{ '\u{2665}' }}{{ '\'' }}{{ '\"' }}{{ '"' }}
 {{ "\x41\n\r\t\\\u{2665}\'\"'" }}"#,
         r#"__rinja_writer.write_str("A
@@ -777,13 +859,152 @@ A
 \r	\\♥'\"'")?;"#,
         &[],
         23,
+        r#"const RENDERED: rinja::helpers::core::option::Option<&'static str> =
+            rinja::helpers::core::option::Option::Some("A\n\r\t\\♥'\"\"\nA\n\r\t\\♥'\"'");"#,
     );
 
-    compare(
+    compare_ex(
         r"{{ 1_2_3_4 }} {{ 4e3 }} {{ false }}",
         r#"__rinja_writer.write_str("1234 4000 false")?;"#,
         &[],
         15,
+        r#"const RENDERED: rinja::helpers::core::option::Option<&'static str> =
+            rinja::helpers::core::option::Option::Some("1234 4000 false");"#,
+    );
+}
+
+#[test]
+fn check_constant_folding() {
+    compare(
+        r#"{{ "hello"|upper }} {{ 1 + 2 * 3 }} {{ -(7 % 4) }} {{ 1.5 / 2.0 }} {{ 250u8 + 5 }}
+{{ "a" ~ 'b' ~ 3 ~ true }} {{ "  <b>  "|trim|safe }} {{ "<b>"|e }} {{ "<b>"|lower }}
+{{ "rINJA"|capitalize }} {% if 1 + 1 == 2 && "a" < "b" %}yes{% else %}no{% endif %}
+{{ "in" in "rinja" }} {{ 'x' not in "rinja" }}"#,
+        r#"__rinja_writer.write_str("HELLO 7 -3 0.75 255
+ab3true <b> <b> <b>
+Rinja yes
+true true")?;"#,
+        &[],
+        59,
+    );
+
+    // expressions that cannot be evaluated are left for `rustc`
+    compare(
+        r#"{{ 255u8 + 1 }}{{ 1 / 0 }}{{ 1u8 + 1i8 }}{% if x == 1 + 1 %}{{ x ~ "!"|upper }}{% endif %}"#,
+        r#"match (
+            &((&&rinja::filters::AutoEscaper::new(&(255u8 + 1), rinja::filters::Text)).rinja_auto_escape()?),
+            &((&&rinja::filters::AutoEscaper::new(&(1 / 0), rinja::filters::Text)).rinja_auto_escape()?),
+            &((&&rinja::filters::AutoEscaper::new(&(1u8 + 1i8), rinja::filters::Text)).rinja_auto_escape()?),
+        ) {
+            (expr0, expr1, expr2) => {
+                (&&&rinja::filters::Writable(expr0)).rinja_write(__rinja_writer, __rinja_values)?;
+                (&&&rinja::filters::Writable(expr1)).rinja_write(__rinja_writer, __rinja_values)?;
+                (&&&rinja::filters::Writable(expr2)).rinja_write(__rinja_writer, __rinja_values)?;
+            }
+        }
+        if rinja::helpers::as_bool(&(self.x == 1 + 1)) {
+            match (
+                &((&&rinja::filters::AutoEscaper::new(&(self.x), rinja::filters::Text)).rinja_auto_escape()?),
+            ) {
+                (expr0,) => {
+                    (&&&rinja::filters::Writable(expr0)).rinja_write(__rinja_writer, __rinja_values)?;
+                }
+            }
+            __rinja_writer.write_str("!")?;
+        }"#,
+        &[("x", "u32")],
+        13,
+    );
+}
+
+#[test]
+fn check_inline_if() {
+    compare(
+        r#"{{ x if y else 0 }}{{ "a" if y }}{{ "b" if true else "c" }}{{ z if z is defined else x }}"#,
+        r#"match (
+            &((&&rinja::filters::AutoEscaper::new(
+                &(if rinja::helpers::as_bool(&(self.y)) {
+                    rinja::helpers::Either::Left(self.x)
+                } else {
+                    rinja::helpers::Either::Right(0)
+                }),
+                rinja::filters::Text,
+            )).rinja_auto_escape()?),
+            &((&&rinja::filters::AutoEscaper::new(
+                &(rinja::helpers::MaybeEmpty(
+                    if rinja::helpers::as_bool(&(self.y)) {
+                        rinja::helpers::core::option::Option::Some("a")
+                    } else {
+                        rinja::helpers::core::option::Option::None
+                    },
+                )),
+                rinja::filters::Text,
+            )).rinja_auto_escape()?),
+            &((&&rinja::filters::AutoEscaper::new(&(self.x), rinja::filters::Text)).rinja_auto_escape()?),
+        ) {
+            (expr0, expr1, expr3) => {
+                (&&&rinja::filters::Writable(expr0)).rinja_write(__rinja_writer, __rinja_values)?;
+                (&&&rinja::filters::Writable(expr1)).rinja_write(__rinja_writer, __rinja_values)?;
+                __rinja_writer.write_str("b")?;
+                (&&&rinja::filters::Writable(expr3)).rinja_write(__rinja_writer, __rinja_values)?;
+            }
+        }"#,
+        &[("x", "u32"), ("y", "bool")],
+        10,
+    );
+}
+
+#[test]
+fn check_map_literal() {
+    compare(
+        r#"{% let m = {"a": x, "b": {"c": 1}} %}{{ m.b.c }}{{ m.len() }}"#,
+        r#"let m = &(rinja::helpers::Map(rinja::helpers::MapEntry(
+            "a",
+            &(self.x),
+            rinja::helpers::MapEntry(
+                "b",
+                rinja::helpers::Map(rinja::helpers::MapEntry("c", 1, ())),
+                (),
+            ),
+        )));
+        match (
+            &((&&rinja::filters::AutoEscaper::new(&(m.0.2.1.0.1), rinja::filters::Text)).rinja_auto_escape()?),
+            &((&&rinja::filters::AutoEscaper::new(&(m.len()), rinja::filters::Text)).rinja_auto_escape()?),
+        ) {
+            (expr0, expr1) => {
+                (&&&rinja::filters::Writable(expr0)).rinja_write(__rinja_writer, __rinja_values)?;
+                (&&&rinja::filters::Writable(expr1)).rinja_write(__rinja_writer, __rinja_values)?;
+            }
+        }"#,
+        &[("x", "u32")],
+        6,
+    );
+
+    // Only places are borrowed, computed values are stored in the map.
+    compare(
+        r#"{% let m = {"a": x + 1, "b": [x], "c": y, "d": y.len()} %}{{ m.c }}"#,
+        r#"let m = &(rinja::helpers::Map(rinja::helpers::MapEntry(
+            "a",
+            self.x + 1,
+            rinja::helpers::MapEntry(
+                "b",
+                [self.x],
+                rinja::helpers::MapEntry(
+                    "c",
+                    &(self.y),
+                    rinja::helpers::MapEntry("d", self.y.len(), ()),
+                ),
+            ),
+        )));
+        match (
+            &((&&rinja::filters::AutoEscaper::new(&(m.0.2.2.1), rinja::filters::Text)).rinja_auto_escape()?),
+        ) {
+            (expr0,) => {
+                (&&&rinja::filters::Writable(expr0)).rinja_write(__rinja_writer, __rinja_values)?;
+            }
+        }"#,
+        &[("x", "u32"), ("y", "String")],
+        3,
     );
 }
 
@@ -0,0 +1,433 @@
+use parser::node::{LetValueOrBlock, Lit, Raw};
+use parser::{Node, Span};
+
+use crate::CompileError;
+use crate::heritage::Context;
+
+/// Checks that the tags in the literal text of an HTML template are balanced and properly nested
+///
+/// Expressions are treated as opaque text or attribute values. If the branches of an `{% if %}`
+/// or `{% match %}` leave different elements open, or if the body of a loop is unbalanced, then
+/// the rest of the template cannot be checked and is skipped. The same is true after an
+/// `{% include %}` or a macro `{% call %}`. Templates that extend another
+/// template only consist of fragments, so they are not checked either.
+pub(crate) fn validate_html(ctx: &Context<'_>) -> Result<(), CompileError> {
+    if ctx.extends.is_some() {
+        return Ok(());
+    }
+    let mut validator = Validator::default();
+    validator.nodes(ctx, ctx.nodes)?;
+    validator.finish(ctx)
+}
+
+#[derive(Debug, Default, Clone)]
+struct Validator<'a> {
+    /// The currently open elements
+    open: Vec<Element<'a>>,
+    state: State<'a>,
+    /// Set if the structure of the template could not be followed
+    unknown: bool,
+}
+
+#[derive(Debug, Clone)]
+struct Element<'a> {
+    /// The lowercase name of the element
+    name: String,
+    /// The location of its start tag
+    span: Span<'a>,
+}
+
+#[derive(Debug, Default, Clone)]
+enum State<'a> {
+    #[default]
+    Text,
+    Tag(Tag<'a>),
+    Comment,
+    /// Inside e.g. a `<script>` element, until its end tag
+    Raw(&'static str),
+}
+
+#[derive(Debug, Clone)]
+struct Tag<'a> {
+    /// The lowercase name of the tag, empty if it is not known at compile time
+    name: String,
+    span: Span<'a>,
+    is_end: bool,
+    quote: Option<u8>,
+    value: AttrValue,
+    /// Set if the last byte was a `/` outside of an attribute value, so the tag is self-closing
+    /// if it is followed by `>`
+    self_closing: bool,
+}
+
+/// Whether the parser is in an attribute value that is not quoted
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum AttrValue {
+    Outside,
+    /// After the `=`, so the next byte that is not whitespace starts the value
+    Expected,
+    /// Inside an unquoted value, which ends at whitespace or `>`, so e.g. `<a href=/x/>`
+    /// is not self-closing
+    Unquoted,
+}
+
+impl Validator<'_> {
+    /// Returns `true` if both validators are in the same position of the document structure
+    fn same_as(&self, other: &Self) -> bool {
+        let same_state = match (&self.state, &other.state) {
+            (State::Text, State::Text) | (State::Comment, State::Comment) => true,
+            (State::Raw(a), State::Raw(b)) => a == b,
+            (State::Tag(a), State::Tag(b)) => {
+                a.name == b.name && a.is_end == b.is_end && a.quote == b.quote && a.value == b.value
+            }
+            _ => false,
+        };
+        same_state
+            && self.open.len() == other.open.len()
+            && self
+                .open
+                .iter()
+                .zip(&other.open)
+                .all(|(a, b)| a.name == b.name)
+    }
+}
+
+impl<'a> Validator<'a> {
+    fn nodes(&mut self, ctx: &Context<'a>, nodes: &'a [Node<'a>]) -> Result<(), CompileError> {
+        for node in nodes {
+            if self.unknown {
+                break;
+            }
+            match node {
+                Node::Lit(lit) => self.text(ctx, lit.val)?,
+                Node::Raw(raw) => {
+                    let Raw {
+                        lit: Lit { lws, val, rws },
+                        ..
+                    } = **raw;
+                    for text in [lws, val, rws] {
+                        self.text(ctx, text)?;
+                    }
+                }
+                Node::If(if_) => {
+                    let has_else = if_.branches.last().is_some_and(|b| b.cond.is_none());
+                    let branches = if_.branches.iter().map(|b| &*b.nodes);
+                    let empty: &[Node<'_>] = &[];
+                    match has_else {
+                        true => self.branches(ctx, branches)?,
+                        false => self.branches(ctx, branches.chain([empty]))?,
+                    }
+                }
+                Node::Match(match_) => {
+                    let arms = match_.arms.iter().map(|a| &*a.nodes);
+                    self.branches(ctx, arms)?;
+                }
+                Node::Loop(loop_) => {
+                    // the body can be repeated, and the `else` block replaces it
+                    for nodes in [&loop_.body, &loop_.else_nodes] {
+                        let mut child = self.clone();
+                        child.nodes(ctx, nodes)?;
+                        if child.unknown || !child.same_as(self) {
+                            self.unknown = true;
+                        }
+                    }
+                }
+                Node::While(while_) => {
+                    // the body can be repeated any number of times
+                    let mut child = self.clone();
+                    child.nodes(ctx, &while_.nodes)?;
+                    if child.unknown || !child.same_as(self) {
+                        self.unknown = true;
+                    }
+                }
+                Node::BlockDef(block) => self.nodes(ctx, &block.nodes)?,
+                Node::With(with) => self.nodes(ctx, &with.nodes)?,
+                Node::FilterBlock(filter) => self.nodes(ctx, &filter.nodes)?,
+                Node::Let(l) => {
+                    // the captured body is written wherever the variable is used
+                    if let Some(LetValueOrBlock::Block { nodes, .. }) = &l.val {
+                        let mut child = self.clone();
+                        child.nodes(ctx, nodes)?;
+                        if child.unknown || !child.same_as(self) {
+                            self.unknown = true;
+                        }
+                    }
+                }
+                // the included template or the called macro can open or close any element
+                Node::Include(_) | Node::Call(_) => self.unknown = true,
+                Node::Comment(_)
+                | Node::Expr(..)
+                | Node::Extends(_)
+                | Node::Import(_)
+                | Node::Macro(_)
+                | Node::Break(_)
+                | Node::Continue(_) => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// Validates each branch individually, and continues if they all end in the same state
+    fn branches(
+        &mut self,
+        ctx: &Context<'a>,
+        branches: impl Iterator<Item = &'a [Node<'a>]>,
+    ) -> Result<(), CompileError> {
+        let mut result: Option<Self> = None;
+        for nodes in branches {
+            let mut child = self.clone();
+            child.nodes(ctx, nodes)?;
+            match &result {
+                _ if child.unknown => self.unknown = true,
+                Some(result) if !result.same_as(&child) => self.unknown = true,
+                Some(_) => {}
+                None => result = Some(child),
+            }
+        }
+        if let Some(result) = result {
+            if !self.unknown {
+                *self = result;
+            }
+        }
+        Ok(())
+    }
+
+    fn text(&mut self, ctx: &Context<'a>, text: &'a str) -> Result<(), CompileError> {
+        let bytes = text.as_bytes();
+        let mut idx = 0;
+        while idx < bytes.len() {
+            match &mut self.state {
+                State::Text => {
+                    let Some(start) = text[idx..].find('<') else {
+                        break;
+                    };
+                    idx += start;
+                    let rest = &text[idx..];
+                    if rest.starts_with("<!--") {
+                        self.state = State::Comment;
+                        idx += 4;
+                    } else if let Some(tag) = rest.strip_prefix("</") {
+                        self.state = State::Tag(Tag::new(tag, rest, true));
+                        idx += 2;
+                    } else if rest[1..].starts_with(|c: char| c.is_ascii_alphabetic()) {
+                        self.state = State::Tag(Tag::new(&rest[1..], rest, false));
+                        idx += 1;
+                    } else if rest[1..].starts_with(['!', '?']) {
+                        // `<!DOCTYPE html>` or `<?xml … ?>`
+                        self.state = State::Tag(Tag::new("", rest, false));
+                        idx += 1;
+                    } else {
+                        idx += 1;
+                    }
+                }
+                State::Comment => match text[idx..].find("-->") {
+                    Some(end) => {
+                        self.state = State::Text;
+                        idx += end + 3;
+                    }
+                    None => break,
+                },
+                State::Raw(name) => match find_end_tag(&text[idx..], name) {
+                    Some(end) => {
+                        self.state = State::Text;
+                        idx += end;
+                    }
+                    None => break,
+                },
+                State::Tag(tag) => {
+                    let c = bytes[idx];
+                    idx += 1;
+                    match tag.quote {
+                        Some(quote) if quote == c => tag.quote = None,
+                        Some(_) => {}
+                        None if c == b'>' => {
+                            let State::Tag(tag) = std::mem::take(&mut self.state) else {
+                                unreachable!();
+                            };
+                            self.end_of_tag(ctx, tag)?;
+                        }
+                        None if c.is_ascii_whitespace() => {
+                            tag.self_closing = false;
+                            if tag.value == AttrValue::Unquoted {
+                                tag.value = AttrValue::Outside;
+                            }
+                        }
+                        None if tag.value == AttrValue::Unquoted => {}
+                        None if c == b'"' || c == b'\'' => {
+                            tag.quote = Some(c);
+                            tag.value = AttrValue::Outside;
+                            tag.self_closing = false;
+                        }
+                        None if tag.value == AttrValue::Expected => tag.value = AttrValue::Unquoted,
+                        None => {
+                            tag.self_closing = c == b'/';
+                            if c == b'=' {
+                                tag.value = AttrValue::Expected;
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn end_of_tag(&mut self, ctx: &Context<'a>, tag: Tag<'a>) -> Result<(), CompileError> {
+        let Tag {
+            name,
+            span,
+            is_end,
+            self_closing,
+            ..
+        } = tag;
+        if name.is_empty() {
+            // the name is an expression, or it is a doctype declaration
+            return Ok(());
+        }
+
+        if is_end {
+            while let Some(top) = self.open.last() {
+                if top.name == name {
+                    self.open.pop();
+                    return Ok(());
+                } else if OPTIONAL_END_TAG.contains(&top.name.as_str()) {
+                    self.open.pop();
+                } else {
+                    return Err(ctx.generate_error(
+                        format_args!(
+                            "closing tag `</{name}>` does not match the open element `<{}>`",
+                            top.name,
+                        ),
+                        span,
+                    ));
+                }
+            }
+            return Err(ctx.generate_error(
+                format_args!("closing tag `</{name}>` has no matching opening tag"),
+                span,
+            ));
+        }
+
+        while let Some(top) = self.open.last() {
+            match implicitly_closed_by(&top.name) {
+                Some(closers) if closers.contains(&name.as_str()) => self.open.pop(),
+                _ => break,
+            };
+        }
+        if self_closing || VOID_ELEMENTS.contains(&name.as_str()) {
+            return Ok(());
+        }
+        if let Some(&raw) = RAW_TEXT_ELEMENTS.iter().find(|&&raw| raw == name) {
+            self.state = State::Raw(raw);
+        }
+        self.open.push(Element { name, span });
+        Ok(())
+    }
+
+    fn finish(self, ctx: &Context<'a>) -> Result<(), CompileError> {
+        if self.unknown {
+            return Ok(());
+        }
+        if let State::Tag(tag) = &self.state {
+            return Err(ctx.generate_error("tag is not closed with `>`", tag.span));
+        }
+        match self
+            .open
+            .iter()
+            .find(|elem| !OPTIONAL_END_TAG.contains(&elem.name.as_str()))
+        {
+            Some(elem) => Err(ctx.generate_error(
+                format_args!("element `<{}>` is never closed", elem.name),
+                elem.span,
+            )),
+            None => Ok(()),
+        }
+    }
+}
+
+impl<'a> Tag<'a> {
+    fn new(name: &str, start: &'a str, is_end: bool) -> Self {
+        let len = name
+            .bytes()
+            .position(|c| !(c.is_ascii_alphanumeric() || c == b'-' || c == b':'))
+            .unwrap_or(name.len());
+        Self {
+            name: name[..len].to_ascii_lowercase(),
+            span: start.into(),
+            is_end,
+            quote: None,
+            value: AttrValue::Outside,
+            self_closing: false,
+        }
+    }
+}
+
+/// Returns the index of the end tag `</{name}`, ignoring the case of the name
+fn find_end_tag(s: &str, name: &str) -> Option<usize> {
+    s.match_indices("</").map(|(idx, _)| idx).find(|&idx| {
+        s.get(idx + 2..idx + 2 + name.len())
+            .is_some_and(|tag| tag.eq_ignore_ascii_case(name))
+    })
+}
+
+/// Returns the start tags that implicitly close an open element of the given name
+fn implicitly_closed_by(name: &str) -> Option<&'static [&'static str]> {
+    Some(match name {
+        "li" => &["li"],
+        "dt" | "dd" => &["dt", "dd"],
+        "option" => &["option", "optgroup"],
+        "optgroup" => &["optgroup"],
+        "tr" => &["tr", "tbody", "tfoot"],
+        "td" | "th" => &["td", "th", "tr", "tbody", "tfoot"],
+        "thead" | "tbody" => &["tbody", "tfoot"],
+        "rt" | "rp" => &["rt", "rp"],
+        "p" => &[
+            "address",
+            "article",
+            "aside",
+            "blockquote",
+            "details",
+            "div",
+            "dl",
+            "fieldset",
+            "figcaption",
+            "figure",
+            "footer",
+            "form",
+            "h1",
+            "h2",
+            "h3",
+            "h4",
+            "h5",
+            "h6",
+            "header",
+            "hgroup",
+            "hr",
+            "main",
+            "menu",
+            "nav",
+            "ol",
+            "p",
+            "pre",
+            "section",
+            "table",
+            "ul",
+        ],
+        _ => return None,
+    })
+}
+
+/// Elements that never have content nor an end tag
+const VOID_ELEMENTS: &[&str] = &[
+    "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "param", "source",
+    "track", "wbr",
+];
+
+/// Elements whose end tag may be omitted
+const OPTIONAL_END_TAG: &[&str] = &[
+    "body", "caption", "colgroup", "dd", "dt", "head", "html", "li", "optgroup", "option", "p",
+    "rp", "rt", "tbody", "td", "tfoot", "th", "thead", "tr",
+];
+
+/// Elements whose content is not parsed as HTML
+pub(crate) const RAW_TEXT_ELEMENTS: &[&str] = &["script", "style", "textarea", "title"];
@@ -0,0 +1,481 @@
+use std::borrow::Cow;
+use std::ops::Range as StdRange;
+
+use lsp_types::{
+    CompletionItem, CompletionItemKind, Diagnostic, DiagnosticSeverity, Documentation, Position,
+    Range,
+};
+use parser::node::{LetValueOrBlock, Node};
+use parser::{Ast, Span, Syntax};
+
+use crate::filters::BUILTIN_FILTERS;
+
+/// Converts between byte offsets and LSP positions, which count UTF-16 code units
+pub(crate) struct LineIndex<'a> {
+    source: &'a str,
+    line_starts: Vec<usize>,
+}
+
+impl<'a> LineIndex<'a> {
+    pub(crate) fn new(source: &'a str) -> Self {
+        let line_starts = [0]
+            .into_iter()
+            .chain(source.match_indices('\n').map(|(i, _)| i + 1))
+            .collect();
+        Self {
+            source,
+            line_starts,
+        }
+    }
+
+    pub(crate) fn position(&self, offset: usize) -> Position {
+        let mut offset = offset.min(self.source.len());
+        while !self.source.is_char_boundary(offset) {
+            offset -= 1;
+        }
+        let line = self.line_starts.partition_point(|&start| start <= offset) - 1;
+        let start = self.line_starts[line];
+        let character = self.source[start..offset].encode_utf16().count();
+        Position::new(line as u32, character as u32)
+    }
+
+    pub(crate) fn offset(&self, position: Position) -> usize {
+        let Some(&start) = self.line_starts.get(position.line as usize) else {
+            return self.source.len();
+        };
+        let mut remaining = position.character as usize;
+        for (index, c) in self.source[start..].char_indices() {
+            if remaining == 0 || c == '\n' {
+                return start + index;
+            }
+            remaining = remaining.saturating_sub(c.len_utf16());
+        }
+        self.source.len()
+    }
+
+    pub(crate) fn range(&self, range: StdRange<usize>) -> Range {
+        Range::new(self.position(range.start), self.position(range.end))
+    }
+}
+
+/// Returns the byte range of `part` in `source`, if `part` is a sub-slice of `source`.
+pub(crate) fn span_of(source: &str, part: &str) -> Option<StdRange<usize>> {
+    let start = Span::from(part).offset_from(source)?;
+    Some(start..start + part.len())
+}
+
+/// Parses `source` and reports a diagnostic for every parser error.
+pub(crate) fn diagnostics(source: &str, syntax: &Syntax<'_>) -> Vec<Diagnostic> {
+    let Err(errors) = Ast::from_str_with_recovery(source, None, syntax) else {
+        return Vec::new();
+    };
+
+    let index = LineIndex::new(source);
+    errors
+        .into_iter()
+        .map(|err| {
+            // Underline everything from the error location up to the end of its line.
+            let start = err.offset.min(source.len());
+            let end = source[start..]
+                .find('\n')
+                .map_or(source.len(), |len| start + len);
+            let message = err
+                .message
+                .as_deref()
+                .unwrap_or("failed to parse template source");
+            Diagnostic {
+                range: index.range(start..end),
+                severity: Some(DiagnosticSeverity::ERROR),
+                source: Some("rinja".into()),
+                message: message.into(),
+                ..Diagnostic::default()
+            }
+        })
+        .collect()
+}
+
+/// Blanks out the tags that fail to parse, so the rest of a template that is being edited can
+/// still be analyzed.
+///
+/// The tags are overwritten with spaces, keeping their line breaks, so byte offsets and line
+/// numbers in the returned source are the same as in `source`.
+pub(crate) fn parsable_source<'a>(source: &'a str, syntax: &Syntax<'_>) -> Option<Cow<'a, str>> {
+    let mut source = Cow::Borrowed(source);
+    loop {
+        let Err(errors) = Ast::from_str_with_recovery(&source, None, syntax) else {
+            return Some(source);
+        };
+        let mut blanked = source.to_string();
+        for err in errors {
+            let range = broken_tag(&blanked, err.offset, syntax)?;
+            let spaces: String = blanked[range.clone()]
+                .chars()
+                .map(|c| match c {
+                    '\n' => "\n".to_owned(),
+                    c => " ".repeat(c.len_utf8()),
+                })
+                .collect();
+            blanked.replace_range(range, &spaces);
+        }
+        if blanked == *source {
+            // Nothing could be removed, so the next attempt would fail the same way.
+            return None;
+        }
+        source = Cow::Owned(blanked);
+    }
+}
+
+/// Returns the byte range of the tag or comment that contains `offset`.
+fn broken_tag(source: &str, offset: usize, syntax: &Syntax<'_>) -> Option<StdRange<usize>> {
+    let delimiters = [
+        (syntax.block_start, syntax.block_end),
+        (syntax.expr_start, syntax.expr_end),
+        (syntax.comment_start, syntax.comment_end),
+    ];
+    let (start, end) = delimiters
+        .into_iter()
+        .filter_map(|(start, end)| {
+            // The offset may point at the opening delimiter itself.
+            let mut len = (offset + start.len()).min(source.len());
+            while !source.is_char_boundary(len) {
+                len -= 1;
+            }
+            Some((source[..len].rfind(start)?, end))
+        })
+        .max_by_key(|&(start, _)| start)?;
+    let end = source[start..]
+        .find(end)
+        .map_or(source.len(), |len| start + len + end.len());
+    Some(start..end)
+}
+
+/// Something in a template that can be resolved to a definition elsewhere
+#[derive(Debug, PartialEq)]
+pub(crate) enum Reference<'a> {
+    /// A template path in `{% extends %}`, `{% include %}` or `{% import %}`
+    Template(&'a str),
+    /// The name of a `{% block %}`, which may override a block of a parent template
+    Block(&'a str),
+    /// The name of the macro invoked by `{% call %}`, optionally in an imported scope
+    Macro {
+        scope: Option<&'a str>,
+        name: &'a str,
+    },
+}
+
+/// Finds the reference under the cursor at byte `offset`.
+pub(crate) fn reference_at<'a>(
+    source: &'a str,
+    nodes: &[Node<'a>],
+    offset: usize,
+) -> Option<Reference<'a>> {
+    // The cursor may also be placed directly after the last character of a name.
+    let hit = |part: &str| {
+        span_of(source, part).is_some_and(|span| span.start <= offset && offset <= span.end)
+    };
+    find_node(nodes, &mut |node| match node {
+        Node::Extends(extends) if hit(extends.path) => Some(Reference::Template(extends.path)),
+        Node::Include(include) if hit(include.path) => Some(Reference::Template(include.path)),
+        Node::Import(import) if hit(import.path) || hit(import.scope) => {
+            Some(Reference::Template(import.path))
+        }
+        Node::BlockDef(block) if hit(block.name) => Some(Reference::Block(block.name)),
+        Node::Call(call) => match call.scope {
+            Some(scope) if hit(scope) => find_import(nodes, scope).map(Reference::Template),
+            scope if hit(call.name) => Some(Reference::Macro {
+                scope,
+                name: call.name,
+            }),
+            _ => None,
+        },
+        _ => None,
+    })
+}
+
+/// Returns the path of the template a template extends.
+pub(crate) fn find_extends<'a>(nodes: &[Node<'a>]) -> Option<&'a str> {
+    nodes.iter().find_map(|node| match node {
+        Node::Extends(extends) => Some(extends.path),
+        _ => None,
+    })
+}
+
+/// Returns the path of the template imported as `scope`.
+pub(crate) fn find_import<'a>(nodes: &[Node<'a>], scope: &str) -> Option<&'a str> {
+    find_node(nodes, &mut |node| match node {
+        Node::Import(import) if import.scope == scope => Some(import.path),
+        _ => None,
+    })
+}
+
+/// Returns the name of the block definition `name`, as a sub-slice of the source.
+pub(crate) fn find_block<'a>(nodes: &[Node<'a>], name: &str) -> Option<&'a str> {
+    find_node(nodes, &mut |node| match node {
+        Node::BlockDef(block) if block.name == name => Some(block.name),
+        _ => None,
+    })
+}
+
+/// Returns the name of the macro definition `name`, as a sub-slice of the source.
+pub(crate) fn find_macro<'a>(nodes: &[Node<'a>], name: &str) -> Option<&'a str> {
+    find_node(nodes, &mut |node| match node {
+        Node::Macro(mac) if mac.name == name => Some(mac.name),
+        _ => None,
+    })
+}
+
+/// Visits `nodes` and all their descendants depth-first until `f` returns a value.
+fn find_node<'n, 'a, R>(
+    nodes: &'n [Node<'a>],
+    f: &mut impl FnMut(&'n Node<'a>) -> Option<R>,
+) -> Option<R> {
+    for node in nodes {
+        if let Some(result) = f(node) {
+            return Some(result);
+        }
+        let children: Vec<&'n [Node<'a>]> = match node {
+            Node::If(i) => i.branches.iter().map(|cond| &*cond.nodes).collect(),
+            Node::Match(m) => m.arms.iter().map(|arm| &*arm.nodes).collect(),
+            Node::Loop(l) => vec![&l.body, &l.else_nodes],
+            Node::While(w) => vec![&w.nodes],
+            Node::With(w) => vec![&w.nodes],
+            Node::BlockDef(b) => vec![&b.nodes],
+            Node::Macro(m) => vec![&m.nodes],
+            Node::FilterBlock(f) => vec![&f.nodes],
+            Node::Let(l) => match &l.val {
+                Some(LetValueOrBlock::Block { nodes, .. }) => vec![nodes],
+                Some(LetValueOrBlock::Value(_)) | None => vec![],
+            },
+            Node::Lit(_)
+            | Node::Comment(_)
+            | Node::Expr(_, _)
+            | Node::Call(_)
+            | Node::Extends(_)
+            | Node::Include(_)
+            | Node::Import(_)
+            | Node::Raw(_)
+            | Node::Break(_)
+            | Node::Continue(_) => vec![],
+        };
+        for nodes in children {
+            if let Some(result) = find_node(nodes, f) {
+                return Some(result);
+            }
+        }
+    }
+    None
+}
+
+/// Offers the builtin filters after a `|` or in `{% filter %}`.
+pub(crate) fn completions(source: &str, offset: usize, syntax: &Syntax<'_>) -> Vec<CompletionItem> {
+    let Some(before) = source.get(..offset) else {
+        return Vec::new();
+    };
+    if !is_inside_tag(before, syntax) {
+        return Vec::new();
+    }
+
+    let rest = before.trim_end_matches(|c: char| c.is_ascii_alphanumeric() || c == '_');
+    let prefix = &before[rest.len()..];
+    let rest = rest.trim_end();
+    let is_filter = match rest.strip_suffix('|') {
+        Some(rest) => !rest.ends_with('|'),
+        None => rest.strip_suffix("filter").is_some_and(|rest| {
+            rest.trim_end()
+                .trim_end_matches(['-', '+', '~'])
+                .ends_with(syntax.block_start)
+        }),
+    };
+    if !is_filter {
+        return Vec::new();
+    }
+
+    BUILTIN_FILTERS
+        .iter()
+        .filter(|filter| filter.name.starts_with(prefix))
+        .map(|filter| CompletionItem {
+            label: filter.name.into(),
+            kind: Some(CompletionItemKind::FUNCTION),
+            detail: Some("builtin filter".into()),
+            documentation: Some(Documentation::String(filter.doc.into())),
+            ..CompletionItem::default()
+        })
+        .collect()
+}
+
+/// Checks whether the end of `before` is inside an expression or block tag.
+fn is_inside_tag(before: &str, syntax: &Syntax<'_>) -> bool {
+    let open = before
+        .rfind(syntax.expr_start)
+        .max(before.rfind(syntax.block_start));
+    let close = before
+        .rfind(syntax.expr_end)
+        .max(before.rfind(syntax.block_end));
+    open.is_some() && open > close
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_line_index() {
+        let source = "a\nbé𝄞c\n\nd";
+        let index = LineIndex::new(source);
+        for (offset, position) in [
+            (0, Position::new(0, 0)),
+            (2, Position::new(1, 0)),
+            (3, Position::new(1, 1)),
+            (5, Position::new(1, 2)),
+            (9, Position::new(1, 4)),
+            (11, Position::new(2, 0)),
+            (12, Position::new(3, 0)),
+            (13, Position::new(3, 1)),
+        ] {
+            assert_eq!(index.position(offset), position, "offset {offset}");
+            assert_eq!(index.offset(position), offset, "{position:?}");
+        }
+        // positions past the end of a line are clamped to the end of the line
+        assert_eq!(index.offset(Position::new(0, 10)), 1);
+        assert_eq!(index.offset(Position::new(10, 0)), source.len());
+    }
+
+    #[test]
+    fn test_diagnostics() {
+        let syntax = Syntax::default();
+        assert_eq!(diagnostics("{{ a }}", &syntax), []);
+
+        let source = "{{ a }}\n{% if x %}\n{% endfor %}\n{% let b = %}";
+        let diagnostics = diagnostics(source, &syntax);
+        assert_eq!(diagnostics.len(), 2);
+        assert_eq!(
+            diagnostics[0].message,
+            "expected `endif` to terminate `if` node, found `endfor`"
+        );
+        assert_eq!(
+            diagnostics[0].range,
+            Range::new(Position::new(2, 2), Position::new(2, 12)),
+        );
+        assert_eq!(
+            diagnostics[1].range,
+            Range::new(Position::new(3, 9), Position::new(3, 13)),
+        );
+    }
+
+    #[test]
+    fn test_reference_at() {
+        let source = r#"{% extends "base.html" %}
+{% import "macros.html" as m %}
+{% block content %}{% call m::hello("x") %}{% call bye() %}{% endblock %}
+{% set s %}{% call later() %}{% endset %}"#;
+        let ast = Ast::from_str(source, None, &Syntax::default()).unwrap();
+        let at = |needle: &str| {
+            let offset = source.find(needle).unwrap() + 1;
+            reference_at(source, ast.nodes(), offset)
+        };
+
+        assert_eq!(at("base.html"), Some(Reference::Template("base.html")));
+        assert_eq!(at("macros.html"), Some(Reference::Template("macros.html")));
+        assert_eq!(at("m %}"), Some(Reference::Template("macros.html")));
+        assert_eq!(at("content"), Some(Reference::Block("content")));
+        assert_eq!(at("m::"), Some(Reference::Template("macros.html")));
+        assert_eq!(
+            at("hello"),
+            Some(Reference::Macro {
+                scope: Some("m"),
+                name: "hello",
+            }),
+        );
+        assert_eq!(
+            at("bye"),
+            Some(Reference::Macro {
+                scope: None,
+                name: "bye",
+            }),
+        );
+        assert_eq!(
+            at("later"),
+            Some(Reference::Macro {
+                scope: None,
+                name: "later",
+            }),
+        );
+        assert_eq!(at("extends"), None);
+        assert_eq!(at("\"x\""), None);
+    }
+
+    #[test]
+    fn test_find_definitions() {
+        let source = r#"{% extends "base.html" %}
+{% macro hello(name) %}Hello {{ name }}{% endmacro %}
+{% block outer %}{% if x %}{% block inner %}{% endblock %}{% endif %}{% endblock %}
+{% set s %}{% block captured %}{% endblock %}{% endset %}"#;
+        let ast = Ast::from_str(source, None, &Syntax::default()).unwrap();
+        let nodes = ast.nodes();
+
+        assert_eq!(find_extends(nodes), Some("base.html"));
+        assert_eq!(find_import(nodes, "m"), None);
+
+        let inner = find_block(nodes, "inner").unwrap();
+        assert_eq!(
+            span_of(source, inner),
+            source.find("inner").map(|i| i..i + 5)
+        );
+        let hello = find_macro(nodes, "hello").unwrap();
+        assert_eq!(
+            span_of(source, hello),
+            source.find("hello").map(|i| i..i + 5)
+        );
+        assert_eq!(find_macro(nodes, "bye"), None);
+        assert!(find_block(nodes, "captured").is_some());
+    }
+
+    #[test]
+    fn test_parsable_source() {
+        let syntax = Syntax::default();
+        assert!(matches!(
+            parsable_source("{{ a }}", &syntax),
+            Some(Cow::Borrowed("{{ a }}")),
+        ));
+
+        let source = "{% import \"m.html\" as m %}\n{% if a + %}é\n{{ b | }}{% endif %}\n\
+                      {% block x %}{% call m::hello() %}{% endblock %}\n{# é";
+        let parsable = parsable_source(source, &syntax).unwrap();
+        assert_eq!(
+            parsable,
+            "{% import \"m.html\" as m %}\n            é\n                    \n\
+             {% block x %}{% call m::hello() %}{% endblock %}\n     ",
+        );
+        let ast = Ast::from_str(&parsable, None, &syntax).unwrap();
+        let offset = source.find("hello").unwrap();
+        assert_eq!(
+            reference_at(&parsable, ast.nodes(), offset),
+            Some(Reference::Macro {
+                scope: Some("m"),
+                name: "hello",
+            }),
+        );
+    }
+
+    #[test]
+    fn test_completions() {
+        let syntax = Syntax::default();
+        let labels = |source: &str| {
+            completions(source, source.len(), &syntax)
+                .into_iter()
+                .map(|item| item.label)
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(labels("{{ a|upp"), ["upper", "uppercase"]);
+        assert_eq!(
+            labels("{{ a | lower | t"),
+            ["title", "tojson", "trim", "truncate"]
+        );
+        assert_eq!(labels("{%- filter url"), ["urlencode", "urlencode_strict"]);
+        assert_eq!(labels("{{ a|").len(), BUILTIN_FILTERS.len());
+        assert!(labels("{{ a || t").is_empty());
+        assert!(labels("{{ a }} | t").is_empty());
+        assert!(labels("{% if t").is_empty());
+    }
+}
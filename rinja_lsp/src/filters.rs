@@ -0,0 +1,83 @@
+/// A filter that is implemented by rinja itself, so it's available in every template.
+pub(crate) struct BuiltinFilter {
+    pub(crate) name: &'static str,
+    pub(crate) doc: &'static str,
+}
+
+macro_rules! builtin_filters {
+    ($($name:literal => $doc:literal,)*) => {
+        /// Keep in sync with the filters dispatched in `rinja_derive/src/generator/expr.rs` and
+        /// `BUILTIN_FILTERS` in `rinja_derive/src/lib.rs`.
+        pub(crate) const BUILTIN_FILTERS: &[BuiltinFilter] = &[
+            $(BuiltinFilter { name: $name, doc: $doc },)*
+        ];
+    };
+}
+
+builtin_filters! {
+    "capitalize" => "Capitalize a value. The first character will be uppercase, all others lowercase.",
+    "center" => "Centers the value in a field of a given width.",
+    "deref" => "Dereferences the given argument.",
+    "e" => "Alias for `escape`.",
+    "escape" => "Escapes HTML characters in strings.",
+    "filesizeformat" => "Returns adequate string representation (in KB, ..) of number of bytes.",
+    "fmt" => "Formats arguments according to the specified format.",
+    "format" => "Formats arguments according to the specified format.",
+    "indent" => "Indent newlines with width spaces.",
+    "join" => "Joins iterable into a string separated by provided argument.",
+    "json" => "Serializes the value as JSON. Needs the `serde_json` feature.",
+    "linebreaks" => "Replaces line breaks in plain text with appropriate HTML.",
+    "linebreaksbr" => "Converts all newlines in a piece of plain text to HTML line breaks.",
+    "lower" => "Converts to lowercase.",
+    "lowercase" => "Converts to lowercase.",
+    "paragraphbreaks" => "A new line followed by a blank line becomes `<p>`.",
+    "pluralize" => "Select a singular or plural version of a word, depending on the input value.",
+    "pyformat" => "Formats arguments according to a Python `str.format()` format string.",
+    "ref" => "Creates a reference to the given argument.",
+    "safe" => "Marks a string (or other Display type) as safe.",
+    "title" => "Return a title cased version of the value.",
+    "tojson" => "Alias for `json`.",
+    "trim" => "Strip leading and trailing whitespace.",
+    "truncate" => "Limit string length, appends '...' if truncated.",
+    "upper" => "Converts to uppercase.",
+    "uppercase" => "Converts to uppercase.",
+    "urlencode" => "Percent encodes the string.",
+    "urlencode_strict" => "Percent encodes the string, including `/`.",
+    "value" => "Retrieves a runtime value by its key.",
+    "wordcount" => "Count the words in that string.",
+}
+
+#[cfg(test)]
+mod tests {
+    use super::BUILTIN_FILTERS;
+
+    /// Collects all identifier-like string literals between `start` and the next `end`.
+    fn quoted_names<'a>(source: &'a str, start: &str, end: &str) -> Vec<&'a str> {
+        let source = &source[source.find(start).unwrap() + start.len()..];
+        let source = &source[..source.find(end).unwrap()];
+        source
+            .split('"')
+            .skip(1)
+            .step_by(2)
+            .filter(|s| s.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'_'))
+            .collect()
+    }
+
+    #[test]
+    fn test_filter_list_is_complete() {
+        let generator = include_str!("../../rinja_derive/src/generator/expr.rs");
+        let lib = include_str!("../../rinja_derive/src/lib.rs");
+
+        let mut expected = quoted_names(generator, "let filter = match name {", "};");
+        expected.extend(quoted_names(
+            lib,
+            "const BUILTIN_FILTERS: &[&str] = &[",
+            "];",
+        ));
+        expected.sort_unstable();
+        expected.dedup();
+
+        let actual = BUILTIN_FILTERS.iter().map(|f| f.name).collect::<Vec<_>>();
+        assert_eq!(actual, expected);
+    }
+}
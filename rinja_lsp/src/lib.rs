@@ -0,0 +1,15 @@
+//! A [Language Server Protocol](https://microsoft.github.io/language-server-protocol/) server
+//! for [Rinja](https://github.com/rinja-rs/rinja) templates.
+//!
+//! It reports all parser errors as diagnostics, resolves `{% extends %}`, `{% include %}`,
+//! `{% import %}`, blocks and macro calls to their definition, and completes builtin filters.
+
+#![deny(elided_lifetimes_in_paths)]
+#![deny(unreachable_pub)]
+
+mod analysis;
+mod filters;
+mod project;
+mod server;
+
+pub use server::run;
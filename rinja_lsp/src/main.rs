@@ -0,0 +1,11 @@
+use std::process::ExitCode;
+
+fn main() -> ExitCode {
+    match rinja_lsp::run() {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("rinja-lsp: {err}");
+            ExitCode::FAILURE
+        }
+    }
+}
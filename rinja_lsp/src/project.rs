@@ -0,0 +1,192 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use parser::{Dialect, Syntax, SyntaxBuilder};
+use serde_derive::Deserialize;
+
+const CONFIG_FILE_NAME: &str = "rinja.toml";
+const DEFAULT_SYNTAX_NAME: &str = "default";
+
+/// The parts of a crate's `rinja.toml` that are needed to analyze its templates
+#[derive(Debug)]
+pub(crate) struct Project {
+    pub(crate) dirs: Vec<PathBuf>,
+    pub(crate) syntax: Syntax<'static>,
+}
+
+impl Project {
+    /// Returns the directory of the closest `Cargo.toml` above `file`.
+    pub(crate) fn root_of(file: &Path) -> PathBuf {
+        file.ancestors()
+            .skip(1)
+            .find(|dir| dir.join("Cargo.toml").is_file())
+            .or_else(|| file.parent())
+            .unwrap_or(file)
+            .to_owned()
+    }
+
+    /// Reads the `rinja.toml` in `root`, if any.
+    ///
+    /// Problems in the configuration file are logged, and the defaults are used instead.
+    pub(crate) fn load(root: PathBuf) -> Self {
+        let config = root.join(CONFIG_FILE_NAME);
+        let source = match config.is_file() {
+            true => match fs::read_to_string(&config) {
+                Ok(source) => Some(source),
+                Err(err) => {
+                    eprintln!("unable to read {}: {err}", config.display());
+                    None
+                }
+            },
+            false => None,
+        };
+        match source.map(|source| Self::from_toml_str(&root, source)) {
+            Some(Ok(project)) => project,
+            Some(Err(err)) => {
+                eprintln!("invalid {}: {err}", config.display());
+                Self::new(&root, None, Syntax::default())
+            }
+            None => Self::new(&root, None, Syntax::default()),
+        }
+    }
+
+    fn new(root: &Path, dirs: Option<Vec<&str>>, syntax: Syntax<'static>) -> Self {
+        let dirs = match dirs {
+            Some(dirs) => dirs.into_iter().map(|dir| root.join(dir)).collect(),
+            None => vec![root.join("templates")],
+        };
+        Self { dirs, syntax }
+    }
+
+    pub(crate) fn from_toml_str(root: &Path, source: String) -> Result<Self, String> {
+        // Like in `rinja_derive`, configurations are leaked, so syntaxes can borrow from them.
+        let source: &'static str = Box::leak(source.into_boxed_str());
+        let raw: RawConfig<'static> = basic_toml::from_str(source).map_err(|e| e.to_string())?;
+
+        let General {
+            dirs,
+            default_syntax,
+            dialect,
+        } = raw.general.unwrap_or_default();
+        // Like in `rinja_derive`, the `dialect` of the `[general]` section applies to all syntaxes
+        // that don't override it.
+        let mut syntax = match default_syntax.unwrap_or(DEFAULT_SYNTAX_NAME) {
+            DEFAULT_SYNTAX_NAME => SyntaxBuilder {
+                name: DEFAULT_SYNTAX_NAME,
+                ..SyntaxBuilder::default()
+            },
+            name => raw
+                .syntax
+                .unwrap_or_default()
+                .into_iter()
+                .find(|syntax| syntax.name == name)
+                .ok_or_else(|| format!("default syntax \"{name}\" not found"))?,
+        };
+        syntax.dialect = syntax.dialect.or(Some(dialect));
+        let syntax = syntax.to_syntax()?;
+        Ok(Self::new(root, dirs, syntax))
+    }
+
+    /// Resolves a template path the same way `rinja_derive` does: first relative to the current
+    /// template, then in each of the configured template directories.
+    pub(crate) fn find_template(&self, path: &str, start_at: Option<&Path>) -> Option<PathBuf> {
+        let relative = start_at.map(|root| root.with_file_name(path));
+        let path = relative
+            .into_iter()
+            .chain(self.dirs.iter().map(|dir| dir.join(path)))
+            .find(|path| path.exists())?;
+        path.canonicalize().ok()
+    }
+}
+
+#[derive(Deserialize)]
+struct RawConfig<'a> {
+    #[serde(borrow)]
+    general: Option<General<'a>>,
+    syntax: Option<Vec<SyntaxBuilder<'a>>>,
+}
+
+#[derive(Default, Deserialize)]
+struct General<'a> {
+    #[serde(borrow)]
+    dirs: Option<Vec<&'a str>>,
+    default_syntax: Option<&'a str>,
+    #[serde(default)]
+    dialect: Dialect,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_config() {
+        let project = Project::from_toml_str(Path::new("/crate"), String::new()).unwrap();
+        assert_eq!(project.dirs, [Path::new("/crate/templates")]);
+        assert_eq!(project.syntax, Syntax::default());
+    }
+
+    #[test]
+    fn test_custom_config() {
+        let config = r#"
+            [general]
+            dirs = ["tpl", "more/tpl"]
+            default_syntax = "foo"
+
+            [[syntax]]
+            name = "foo"
+            block_start = "<%"
+            block_end = "%>"
+        "#;
+        let project = Project::from_toml_str(Path::new("/crate"), config.into()).unwrap();
+        assert_eq!(
+            project.dirs,
+            [Path::new("/crate/tpl"), Path::new("/crate/more/tpl")],
+        );
+        assert_eq!(project.syntax.block_start, "<%");
+        assert_eq!(project.syntax.block_end, "%>");
+        assert_eq!(project.syntax.expr_start, "{{");
+    }
+
+    #[test]
+    fn test_dialect() {
+        let config = r#"
+            [general]
+            dialect = "jinja2"
+        "#;
+        let project = Project::from_toml_str(Path::new("/crate"), config.into()).unwrap();
+        assert_eq!(project.syntax.dialect, Dialect::Jinja2);
+
+        let config = r#"
+            [general]
+            default_syntax = "foo"
+            dialect = "jinja2"
+
+            [[syntax]]
+            name = "foo"
+            dialect = "rinja"
+        "#;
+        let project = Project::from_toml_str(Path::new("/crate"), config.into()).unwrap();
+        assert_eq!(project.syntax.dialect, Dialect::Rinja);
+
+        let config = r#"
+            [general]
+            default_syntax = "foo"
+            dialect = "jinja2"
+
+            [[syntax]]
+            name = "foo"
+        "#;
+        let project = Project::from_toml_str(Path::new("/crate"), config.into()).unwrap();
+        assert_eq!(project.syntax.dialect, Dialect::Jinja2);
+    }
+
+    #[test]
+    fn test_unknown_syntax() {
+        let config = "[general]\ndefault_syntax = \"foo\"";
+        assert_eq!(
+            Project::from_toml_str(Path::new("/crate"), config.into()).unwrap_err(),
+            "default syntax \"foo\" not found",
+        );
+    }
+}
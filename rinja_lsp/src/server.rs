@@ -0,0 +1,214 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use lsp_server::{Connection, ErrorCode, Message, Notification, Request, Response};
+use lsp_types::notification::{
+    DidChangeTextDocument, DidCloseTextDocument, DidOpenTextDocument, Notification as _,
+    PublishDiagnostics,
+};
+use lsp_types::request::{Completion, GotoDefinition, Request as _};
+use lsp_types::{
+    CompletionOptions, CompletionParams, CompletionResponse, DidChangeTextDocumentParams,
+    DidCloseTextDocumentParams, DidOpenTextDocumentParams, GotoDefinitionParams,
+    GotoDefinitionResponse, Location, OneOf, PublishDiagnosticsParams, Range, ServerCapabilities,
+    TextDocumentSyncCapability, TextDocumentSyncKind, Url,
+};
+use parser::Ast;
+
+use crate::analysis::{
+    LineIndex, Reference, completions, diagnostics, find_block, find_extends, find_import,
+    find_macro, parsable_source, reference_at, span_of,
+};
+use crate::project::Project;
+
+/// How many `{% extends %}` are followed at most when looking for a block definition
+const MAX_EXTENDS_DEPTH: usize = 32;
+
+/// Runs the language server on stdin / stdout until the client asks it to exit.
+pub fn run() -> Result<(), Box<dyn Error + Send + Sync>> {
+    let (connection, io_threads) = Connection::stdio();
+    let capabilities = ServerCapabilities {
+        text_document_sync: Some(TextDocumentSyncCapability::Kind(TextDocumentSyncKind::FULL)),
+        definition_provider: Some(OneOf::Left(true)),
+        completion_provider: Some(CompletionOptions {
+            trigger_characters: Some(vec!["|".into()]),
+            ..CompletionOptions::default()
+        }),
+        ..ServerCapabilities::default()
+    };
+    connection.initialize(serde_json::to_value(capabilities)?)?;
+    Server::default().main_loop(&connection)?;
+    io_threads.join()?;
+    Ok(())
+}
+
+#[derive(Default)]
+struct Server {
+    /// Content of the documents that are currently opened in the client
+    documents: HashMap<Url, String>,
+    /// Configuration per crate root
+    projects: HashMap<PathBuf, Arc<Project>>,
+}
+
+impl Server {
+    fn main_loop(&mut self, connection: &Connection) -> Result<(), Box<dyn Error + Send + Sync>> {
+        for msg in &connection.receiver {
+            match msg {
+                Message::Request(req) => {
+                    if connection.handle_shutdown(&req)? {
+                        break;
+                    }
+                    let response = self.handle_request(req);
+                    connection.sender.send(Message::Response(response))?;
+                }
+                Message::Notification(not) => {
+                    if let Some(not) = self.handle_notification(not) {
+                        connection.sender.send(Message::Notification(not))?;
+                    }
+                }
+                Message::Response(_) => {}
+            }
+        }
+        Ok(())
+    }
+
+    fn handle_request(&mut self, req: Request) -> Response {
+        let Request { id, method, params } = req;
+        let result = match method.as_str() {
+            GotoDefinition::METHOD => serde_json::from_value(params)
+                .map(|params| serde_json::to_value(self.definition(params))),
+            Completion::METHOD => serde_json::from_value(params)
+                .map(|params| serde_json::to_value(self.completion(params))),
+            _ => {
+                let msg = format!("unhandled method {method:?}");
+                return Response::new_err(id, ErrorCode::MethodNotFound as i32, msg);
+            }
+        };
+        match result {
+            Ok(Ok(result)) => Response::new_ok(id, result),
+            Ok(Err(err)) | Err(err) => {
+                Response::new_err(id, ErrorCode::InvalidParams as i32, err.to_string())
+            }
+        }
+    }
+
+    /// Updates the opened documents, and returns the diagnostics for the affected document.
+    fn handle_notification(&mut self, not: Notification) -> Option<Notification> {
+        let uri = match not.method.as_str() {
+            DidOpenTextDocument::METHOD => {
+                let params: DidOpenTextDocumentParams = serde_json::from_value(not.params).ok()?;
+                let uri = params.text_document.uri;
+                self.documents
+                    .insert(uri.clone(), params.text_document.text);
+                uri
+            }
+            DidChangeTextDocument::METHOD => {
+                let params: DidChangeTextDocumentParams =
+                    serde_json::from_value(not.params).ok()?;
+                let uri = params.text_document.uri;
+                // We requested `TextDocumentSyncKind::FULL`, so the last change has the full text.
+                let text = params.content_changes.into_iter().last()?.text;
+                self.documents.insert(uri.clone(), text);
+                uri
+            }
+            DidCloseTextDocument::METHOD => {
+                let params: DidCloseTextDocumentParams = serde_json::from_value(not.params).ok()?;
+                let uri = params.text_document.uri;
+                self.documents.remove(&uri);
+                uri
+            }
+            _ => return None,
+        };
+
+        let project = self.project(&uri.to_file_path().ok()?);
+        let diagnostics = match self.documents.get(&uri) {
+            Some(source) => diagnostics(source, &project.syntax),
+            None => Vec::new(),
+        };
+        let params = PublishDiagnosticsParams::new(uri, diagnostics, None);
+        Some(Notification::new(PublishDiagnostics::METHOD.into(), params))
+    }
+
+    fn definition(&mut self, params: GotoDefinitionParams) -> Option<GotoDefinitionResponse> {
+        let params = params.text_document_position_params;
+        let path = params.text_document.uri.to_file_path().ok()?;
+        let project = self.project(&path);
+        let source = self.documents.get(&params.text_document.uri)?;
+        let offset = LineIndex::new(source).offset(params.position);
+        let parsable = parsable_source(source, &project.syntax)?;
+        let ast = Ast::from_str(&parsable, None, &project.syntax).ok()?;
+
+        let (path, range) = match reference_at(&parsable, ast.nodes(), offset)? {
+            Reference::Template(template) => (
+                project.find_template(template, Some(&path))?,
+                Range::default(),
+            ),
+            Reference::Macro { scope, name } => {
+                let (path, source) = match scope {
+                    Some(scope) => {
+                        let template = find_import(ast.nodes(), scope)?;
+                        let path = project.find_template(template, Some(&path))?;
+                        let source = self.read(&path)?;
+                        (path, source)
+                    }
+                    None => (path, source.clone()),
+                };
+                let parsable = parsable_source(&source, &project.syntax)?;
+                let ast = Ast::from_str(&parsable, None, &project.syntax).ok()?;
+                let name = find_macro(ast.nodes(), name)?;
+                let range = LineIndex::new(&source).range(span_of(&parsable, name)?);
+                (path, range)
+            }
+            Reference::Block(name) => {
+                let mut parent = find_extends(ast.nodes()).map(str::to_owned);
+                let mut path = path;
+                let mut found = None;
+                for _ in 0..MAX_EXTENDS_DEPTH {
+                    path = project.find_template(&parent?, Some(&path))?;
+                    let source = self.read(&path)?;
+                    let parsable = parsable_source(&source, &project.syntax)?;
+                    let ast = Ast::from_str(&parsable, None, &project.syntax).ok()?;
+                    if let Some(name) = find_block(ast.nodes(), name) {
+                        found = Some(LineIndex::new(&source).range(span_of(&parsable, name)?));
+                        break;
+                    }
+                    parent = find_extends(ast.nodes()).map(str::to_owned);
+                }
+                (path, found?)
+            }
+        };
+        let uri = Url::from_file_path(path).ok()?;
+        Some(GotoDefinitionResponse::Scalar(Location::new(uri, range)))
+    }
+
+    fn completion(&mut self, params: CompletionParams) -> Option<CompletionResponse> {
+        let params = params.text_document_position;
+        let path = params.text_document.uri.to_file_path().ok()?;
+        let project = self.project(&path);
+        let source = self.documents.get(&params.text_document.uri)?;
+        let offset = LineIndex::new(source).offset(params.position);
+        let items = completions(source, offset, &project.syntax);
+        Some(CompletionResponse::Array(items))
+    }
+
+    fn project(&mut self, path: &Path) -> Arc<Project> {
+        let root = Project::root_of(path);
+        let project = self
+            .projects
+            .entry(root)
+            .or_insert_with_key(|root| Arc::new(Project::load(root.clone())));
+        Arc::clone(project)
+    }
+
+    /// Reads the content of a template, preferring unsaved changes in the client.
+    fn read(&self, path: &Path) -> Option<String> {
+        let uri = Url::from_file_path(path).ok()?;
+        match self.documents.get(&uri) {
+            Some(source) => Some(source.clone()),
+            None => fs::read_to_string(path).ok(),
+        }
+    }
+}
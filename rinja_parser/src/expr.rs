@@ -12,13 +12,14 @@ use winnow::stream::Stream as _;
 use crate::node::CondTest;
 use crate::{
     CharLit, ErrorContext, Level, Num, ParseErr, ParseResult, PathOrIdentifier, Span, StrLit,
-    WithSpan, char_lit, filter, identifier, keyword, num_lit, path_or_identifier, skip_ws0,
-    skip_ws1, str_lit, ws,
+    WithSpan, char_lit, filter, identifier, keyword, num_lit, path_or_identifier,
+    single_quoted_str_lit, skip_ws0, skip_ws1, str_lit, ws,
 };
 
 macro_rules! expr_prec_layer {
-    ( $name:ident, $inner:ident, $op:expr ) => {
-        fn $name(i: &mut &'a str, level: Level<'_>) -> ParseResult<'a, WithSpan<'a, Self>> {
+    ( $name:ident, $inner:ident, |$level:ident| $op:expr ) => {
+        fn $name(i: &mut &'a str, $level: Level<'_>) -> ParseResult<'a, WithSpan<'a, Self>> {
+            let level = $level;
             let mut level_guard = level.guard();
             let start = *i;
             let mut expr = Self::$inner(i, level)?;
@@ -32,6 +33,9 @@ macro_rules! expr_prec_layer {
             Ok(expr)
         }
     };
+    ( $name:ident, $inner:ident, $op:expr ) => {
+        expr_prec_layer!($name, $inner, |_level| $op);
+    };
 }
 
 fn check_expr<'a>(
@@ -72,6 +76,13 @@ fn check_expr<'a>(
             }
             Ok(())
         }
+        Expr::Map(entries) => {
+            for (_, value) in entries {
+                check_expr(value, false)?;
+            }
+            Ok(())
+        }
+        Expr::Closure { body, .. } => check_expr(body, false),
         Expr::Index(elem1, elem2) | Expr::BinOp(_, elem1, elem2) => {
             check_expr(elem1, false)?;
             check_expr(elem2, false)
@@ -93,6 +104,15 @@ fn check_expr<'a>(
             }
             Ok(())
         }
+        Expr::IfElse {
+            then, otherwise, ..
+        } => {
+            check_expr(then, false)?;
+            if let Some(otherwise) = otherwise {
+                check_expr(otherwise, false)?;
+            }
+            Ok(())
+        }
     }
 }
 
@@ -105,6 +125,8 @@ pub enum Expr<'a> {
     Var(&'a str),
     Path(Vec<&'a str>),
     Array(Vec<WithSpan<'a, Expr<'a>>>),
+    /// `{"key": value, ...}`, the keys are the unprefixed contents of their string literals.
+    Map(Vec<(&'a str, WithSpan<'a, Expr<'a>>)>),
     Attr(Box<WithSpan<'a, Expr<'a>>>, Attr<'a>),
     Index(Box<WithSpan<'a, Expr<'a>>>, Box<WithSpan<'a, Expr<'a>>>),
     Filter(Filter<'a>),
@@ -137,6 +159,17 @@ pub enum Expr<'a> {
     Concat(Vec<WithSpan<'a, Expr<'a>>>),
     /// If you have `&& let Some(y)`, this variant handles it.
     LetCond(Box<WithSpan<'a, CondTest<'a>>>),
+    /// `then if cond else otherwise`, the `else` branch is optional.
+    IfElse {
+        then: Box<WithSpan<'a, Expr<'a>>>,
+        cond: Box<WithSpan<'a, CondTest<'a>>>,
+        otherwise: Option<Box<WithSpan<'a, Expr<'a>>>>,
+    },
+    /// `|param, ...| body`, a closure whose parameters are plain identifiers.
+    Closure {
+        params: Vec<&'a str>,
+        body: Box<WithSpan<'a, Expr<'a>>>,
+    },
 }
 
 impl<'a> Expr<'a> {
@@ -226,6 +259,63 @@ impl<'a> Expr<'a> {
         allow_underscore: bool,
     ) -> ParseResult<'a, WithSpan<'a, Self>> {
         let _level_guard = level.nest(i)?;
+        let start = *i;
+        let expr = Self::range(i, level)?;
+        let expr = Self::if_else(i, level, expr, start)?;
+        check_expr(&expr, allow_underscore)?;
+        Ok(expr)
+    }
+
+    /// Like [`Expr::parse()`], but an `if` after the expression is not parsed as an inline
+    /// conditional, e.g. for the loop condition in `{% for x in xs if x > 0 %}`.
+    pub(super) fn parse_without_if(
+        i: &mut &'a str,
+        level: Level<'_>,
+        allow_underscore: bool,
+    ) -> ParseResult<'a, WithSpan<'a, Self>> {
+        let _level_guard = level.nest(i)?;
+        let expr = Self::range(i, level)?;
+        check_expr(&expr, allow_underscore)?;
+        Ok(expr)
+    }
+
+    /// Parses `then if cond else otherwise`, where `else otherwise` is optional.
+    fn if_else(
+        i: &mut &'a str,
+        level: Level<'_>,
+        then: WithSpan<'a, Self>,
+        start: &'a str,
+    ) -> ParseResult<'a, WithSpan<'a, Self>> {
+        let cond = opt(preceded(
+            ws(keyword("if")),
+            cut_err(|i: &mut &'a str| {
+                let start = *i;
+                let cond = CondTest::parse_cond(i, level)?;
+                Ok(WithSpan::new(cond, start))
+            }),
+        ))
+        .parse_next(i)?;
+        let Some(cond) = cond else {
+            return Ok(then);
+        };
+
+        let _level_guard = level.nest(i)?;
+        let otherwise = opt(preceded(
+            ws(keyword("else")),
+            cut_err(|i: &mut _| Self::parse(i, level, false)),
+        ))
+        .parse_next(i)?;
+        Ok(WithSpan::new(
+            Self::IfElse {
+                then: Box::new(then),
+                cond: Box::new(cond),
+                otherwise: otherwise.map(Box::new),
+            },
+            start,
+        ))
+    }
+
+    fn range(i: &mut &'a str, level: Level<'_>) -> ParseResult<'a, WithSpan<'a, Self>> {
         let start = Span::from(*i);
         let range_right = move |i: &mut _| {
             (
@@ -234,7 +324,7 @@ impl<'a> Expr<'a> {
             )
                 .parse_next(i)
         };
-        let expr = alt((
+        alt((
             range_right.map(move |(op, right)| {
                 WithSpan::new(Self::Range(op, None, right.map(Box::new)), start)
             }),
@@ -248,14 +338,35 @@ impl<'a> Expr<'a> {
                 }
             }),
         ))
-        .parse_next(i)?;
-        check_expr(&expr, allow_underscore)?;
-        Ok(expr)
+        .parse_next(i)
     }
 
-    expr_prec_layer!(or, and, "||");
-    expr_prec_layer!(and, compare, "&&");
-    expr_prec_layer!(compare, bor, alt(("==", "!=", ">=", ">", "<=", "<",)));
+    expr_prec_layer!(or, and, |level| alt((
+        "||",
+        jinja2_alias(level, "or", "||")
+    )));
+    expr_prec_layer!(and, not, |level| alt((
+        "&&",
+        jinja2_alias(level, "and", "&&")
+    )));
+
+    /// Jinja2's `not x` has a lower precedence than the comparison operators, unlike `!x`.
+    fn not(i: &mut &'a str, level: Level<'_>) -> ParseResult<'a, WithSpan<'a, Self>> {
+        let start = *i;
+        if !level.is_jinja2() || opt(ws(keyword("not"))).parse_next(i)?.is_none() {
+            return Self::compare(i, level);
+        }
+        let _level_guard = level.nest(start)?;
+        let expr = Self::not(i, level)?;
+        let expr = WithSpan::new(Self::Group(Box::new(expr)), start);
+        Ok(WithSpan::new(Self::Unary("!", Box::new(expr)), start))
+    }
+
+    expr_prec_layer!(
+        compare,
+        bor,
+        alt(("==", "!=", ">=", ">", "<=", "<", token_in))
+    );
     expr_prec_layer!(bor, bxor, "bitor".value("|"));
     expr_prec_layer!(bxor, band, token_xor);
     expr_prec_layer!(band, shifts, token_bitand);
@@ -306,6 +417,9 @@ impl<'a> Expr<'a> {
         let before_keyword = *i;
         let rhs = opt(ws(identifier)).parse_next(i)?;
         match rhs {
+            Some("is") if level.is_jinja2() && !Self::is_defined_test(i) => {
+                return Self::jinja2_test(i, level, lhs, start);
+            }
             Some("is") => {}
             Some("as") => {
                 let target = opt(identifier).parse_next(i)?;
@@ -363,6 +477,100 @@ impl<'a> Expr<'a> {
         Ok(WithSpan::new(ctor(var_name), start))
     }
 
+    fn is_defined_test(i: &mut &'a str) -> bool {
+        peek((opt(ws(keyword("not"))), ws(keyword("defined"))))
+            .parse_next(i)
+            .is_ok()
+    }
+
+    /// Maps a Jinja2 test like `x is even` or `x is not divisibleby(3)` to an expression.
+    fn jinja2_test(
+        i: &mut &'a str,
+        level: Level<'_>,
+        lhs: WithSpan<'a, Self>,
+        start: &'a str,
+    ) -> ParseResult<'a, WithSpan<'a, Self>> {
+        let _level_guard = level.nest(start)?;
+        let negated = opt(ws(keyword("not"))).parse_next(i)?.is_some();
+        let name_start = *i;
+        let name = ws(alt((identifier, alt(("==", "!=", ">=", ">", "<=", "<")))))
+            .parse_next(i)
+            .map_err(|_: ParseErr<'_>| {
+                winnow::error::ErrMode::Cut(ErrorContext::new("expected the name of a test", start))
+            })?;
+        let mut args = opt(|i: &mut _| Self::arguments(i, level, false))
+            .parse_next(i)?
+            .unwrap_or_default();
+
+        let expr = |expr| WithSpan::new(expr, start);
+        let int = |value| expr(Self::NumLit(value, Num::Int(value, None)));
+        let binop = |op, lhs, rhs| expr(Self::BinOp(op, Box::new(lhs), Box::new(rhs)));
+        let test = match (name, args.len()) {
+            ("none", 0) => {
+                let method = if negated { "is_some" } else { "is_none" };
+                let path = Self::Attr(
+                    Box::new(lhs),
+                    Attr {
+                        name: method,
+                        generics: vec![],
+                    },
+                );
+                let call = Self::Call {
+                    path: Box::new(expr(path)),
+                    args: vec![],
+                    generics: vec![],
+                };
+                return Ok(expr(call));
+            }
+            ("true" | "false", 0) => binop("==", lhs, expr(Self::BoolLit(name == "true"))),
+            ("even" | "odd", 0) => {
+                let rem = expr(Self::Group(Box::new(binop("%", lhs, int("2")))));
+                binop(if name == "even" { "==" } else { "!=" }, rem, int("0"))
+            }
+            ("divisibleby", 1) => {
+                let rhs = expr(Self::Group(Box::new(args.pop().unwrap())));
+                let rem = expr(Self::Group(Box::new(binop("%", lhs, rhs))));
+                binop("==", rem, int("0"))
+            }
+            ("in", 1) => binop(
+                if negated { "not in" } else { "in" },
+                lhs,
+                args.pop().unwrap(),
+            ),
+            (name, 1) => {
+                let op = match name {
+                    "eq" | "equalto" | "==" => "==",
+                    "ne" | "!=" => "!=",
+                    "lt" | "lessthan" | "<" => "<",
+                    "le" | "<=" => "<=",
+                    "gt" | "greaterthan" | ">" => ">",
+                    "ge" | ">=" => ">=",
+                    _ => return Err(Self::unknown_test(name, name_start)),
+                };
+                binop(op, lhs, args.pop().unwrap())
+            }
+            (name, _) => return Err(Self::unknown_test(name, name_start)),
+        };
+        let test = expr(Self::Group(Box::new(test)));
+        if negated && name != "in" {
+            Ok(expr(Self::Unary("!", Box::new(test))))
+        } else {
+            Ok(test)
+        }
+    }
+
+    #[cold]
+    fn unknown_test(name: &str, start: &'a str) -> ParseErr<'a> {
+        winnow::error::ErrMode::Cut(ErrorContext::new(
+            format!(
+                "unknown test `{name}` or wrong number of arguments, supported are `defined`, \
+                 `none`, `true`, `false`, `even`, `odd`, `divisibleby(n)`, `in(seq)` and the \
+                 comparisons `eq(x)`, `ne(x)`, `lt(x)`, `le(x)`, `gt(x)`, `ge(x)`",
+            ),
+            start,
+        ))
+    }
+
     fn filtered(i: &mut &'a str, level: Level<'_>) -> ParseResult<'a, WithSpan<'a, Self>> {
         let mut level_guard = level.guard();
         let start = *i;
@@ -412,10 +620,16 @@ impl<'a> Expr<'a> {
         alt((
             Self::num,
             Self::str,
+            move |i: &mut _| match level.is_jinja2() {
+                true => Self::single_quoted_str(i),
+                false => fail.parse_next(i),
+            },
             Self::char,
-            Self::path_var_bool,
+            move |i: &mut _| Self::path_var_bool(i, level),
             move |i: &mut _| Self::array(i, level),
+            move |i: &mut _| Self::map(i, level),
             move |i: &mut _| Self::group(i, level),
+            move |i: &mut _| Self::closure(i, level),
         ))
         .parse_next(i)
     }
@@ -466,13 +680,87 @@ impl<'a> Expr<'a> {
         Ok(WithSpan::new(Self::Array(array.unwrap_or_default()), start))
     }
 
-    fn path_var_bool(i: &mut &'a str) -> ParseResult<'a, WithSpan<'a, Self>> {
+    fn map(i: &mut &'a str, level: Level<'_>) -> ParseResult<'a, WithSpan<'a, Self>> {
+        let start = *i;
+        let _level_guard = level.nest(i)?;
+        let mut keys = HashSet::new();
+        let entry = move |i: &mut &'a str| {
+            let key_start = *i;
+            let Some(key) = opt(str_lit).parse_next(i)? else {
+                if i.starts_with('}') {
+                    return fail.parse_next(i);
+                }
+                return Err(winnow::error::ErrMode::Cut(ErrorContext::new(
+                    "map keys must be string literals",
+                    key_start,
+                )));
+            };
+            let (_, value) = (ws(':'), |i: &mut _| Self::parse(i, level, false)).parse_next(i)?;
+            if key.prefix.is_some() {
+                return Err(winnow::error::ErrMode::Cut(ErrorContext::new(
+                    "map keys must be unprefixed string literals",
+                    key_start,
+                )));
+            }
+            if !keys.insert(key.content) {
+                return Err(winnow::error::ErrMode::Cut(ErrorContext::new(
+                    format!("key `{}` was used more than once in the map", key.content),
+                    key_start,
+                )));
+            }
+            Ok((key.content, value))
+        };
+        let entries = preceded(
+            ws('{'),
+            cut_err(terminated(
+                opt(terminated(separated(1.., ws(entry), ','), ws(opt(',')))),
+                '}',
+            )),
+        )
+        .parse_next(i)?;
+        Ok(WithSpan::new(Self::Map(entries.unwrap_or_default()), start))
+    }
+
+    fn closure(i: &mut &'a str, level: Level<'_>) -> ParseResult<'a, WithSpan<'a, Self>> {
+        let start = *i;
+        let _level_guard = level.nest(i)?;
+        let params = alt((
+            "||".value(vec![]),
+            preceded(
+                '|',
+                cut_err(terminated(
+                    separated(1.., ws(identifier), ','),
+                    (opt(ws(',')), '|'),
+                )),
+            ),
+        ))
+        .parse_next(i)?;
+        let body = cut_err(ws(|i: &mut _| Self::parse(i, level, false))).parse_next(i)?;
+        Ok(WithSpan::new(
+            Self::Closure {
+                params,
+                body: Box::new(body),
+            },
+            start,
+        ))
+    }
+
+    fn path_var_bool(i: &mut &'a str, level: Level<'_>) -> ParseResult<'a, WithSpan<'a, Self>> {
         let start = *i;
         path_or_identifier
             .map(|v| match v {
+                PathOrIdentifier::Path(v) if level.is_jinja2() && v == ["True"] => {
+                    Self::BoolLit(true)
+                }
+                PathOrIdentifier::Path(v) if level.is_jinja2() && v == ["False"] => {
+                    Self::BoolLit(false)
+                }
                 PathOrIdentifier::Path(v) => Self::Path(v),
                 PathOrIdentifier::Identifier("true") => Self::BoolLit(true),
                 PathOrIdentifier::Identifier("false") => Self::BoolLit(false),
+                PathOrIdentifier::Identifier("none") if level.is_jinja2() => {
+                    Self::Path(vec!["None"])
+                }
                 PathOrIdentifier::Identifier(v) => Self::Var(v),
             })
             .parse_next(i)
@@ -486,6 +774,13 @@ impl<'a> Expr<'a> {
             .parse_next(i)
     }
 
+    fn single_quoted_str(i: &mut &'a str) -> ParseResult<'a, WithSpan<'a, Self>> {
+        let start = *i;
+        single_quoted_str_lit
+            .map(|i| WithSpan::new(Self::StrLit(i), start))
+            .parse_next(i)
+    }
+
     fn num(i: &mut &'a str) -> ParseResult<'a, WithSpan<'a, Self>> {
         let start = *i;
         let (num, full) = num_lit.with_taken().parse_next(i)?;
@@ -523,14 +818,37 @@ impl<'a> Expr<'a> {
             | Self::Index(_, _)
             | Self::Tuple(_)
             | Self::Array(_)
+            | Self::Map(_)
+            | Self::Closure { .. }
             | Self::BinOp(_, _, _)
             | Self::Path(_)
             | Self::Concat(_)
-            | Self::LetCond(_) => false,
+            | Self::LetCond(_)
+            | Self::IfElse { .. } => false,
         }
     }
 }
 
+/// In the Jinja2 dialect, `keyword` is accepted in place of the operator `op`.
+fn jinja2_alias<'a, 'l>(
+    level: Level<'l>,
+    keyword: &'static str,
+    op: &'static str,
+) -> impl FnMut(&mut &'a str) -> ParseResult<'a> + 'l {
+    move |i: &mut &'a str| match level.is_jinja2() {
+        true => crate::keyword(keyword).value(op).parse_next(i),
+        false => fail.parse_next(i),
+    }
+}
+
+fn token_in<'a>(i: &mut &'a str) -> ParseResult<'a> {
+    alt((
+        keyword("in"),
+        (keyword("not"), skip_ws1, keyword("in")).value("not in"),
+    ))
+    .parse_next(i)
+}
+
 fn token_xor<'a>(i: &mut &'a str) -> ParseResult<'a> {
     let good = alt((keyword("xor").value(true), '^'.value(false))).parse_next(i)?;
     if good {
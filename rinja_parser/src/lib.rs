@@ -3,7 +3,8 @@
 #![deny(unreachable_pub)]
 
 use std::borrow::Cow;
-use std::cell::Cell;
+use std::cell::{Cell, RefCell};
+use std::collections::HashSet;
 use std::env::current_dir;
 use std::ops::{Deref, DerefMut};
 use std::path::Path;
@@ -46,16 +47,18 @@ mod _parsed {
     impl Parsed {
         /// If `file_path` is `None`, it means the `source` is an inline template. Therefore, if
         /// a parsing error occurs, we won't display the path as it wouldn't be useful.
+        ///
+        /// All errors in `source` are returned, see [`Ast::from_str_with_recovery()`].
         pub fn new(
             source: Arc<str>,
             file_path: Option<Arc<Path>>,
             syntax: &Syntax<'_>,
-        ) -> Result<Self, ParseError> {
+        ) -> Result<Self, Vec<ParseError>> {
             // Self-referential borrowing: `self` will keep the source alive as `String`,
             // internally we will transmute it to `&'static str` to satisfy the compiler.
             // However, we only expose the nodes with a lifetime limited to `self`.
             let src = unsafe { mem::transmute::<&str, &'static str>(source.as_ref()) };
-            let ast = Ast::from_str(src, file_path, syntax)?;
+            let ast = Ast::from_str_with_recovery(src, file_path, syntax)?;
             Ok(Self { ast, source })
         }
 
@@ -106,16 +109,66 @@ impl<'a> Ast<'a> {
     /// If `file_path` is `None`, it means the `source` is an inline template. Therefore, if
     /// a parsing error occurs, we won't display the path as it wouldn't be useful.
     pub fn from_str(
+        src: &'a str,
+        file_path: Option<Arc<Path>>,
+        syntax: &Syntax<'_>,
+    ) -> Result<Self, ParseError> {
+        Self::parse_fragment(src, src, file_path, syntax, 0)
+    }
+
+    /// Like [`Ast::from_str()`], but the parser does not stop at the first error.
+    ///
+    /// After an error, the parser resynchronizes at the next block delimiter (e.g. `{%`), so all
+    /// errors of the template can be reported in one pass. Closing and intermediate tags like
+    /// `{% endif %}` or `{% else %}`, that belong to a block which could not be parsed, are
+    /// skipped. The returned list of errors is never empty.
+    pub fn from_str_with_recovery(
+        src: &'a str,
+        file_path: Option<Arc<Path>>,
+        syntax: &Syntax<'_>,
+    ) -> Result<Self, Vec<ParseError>> {
+        let first = match Self::from_str(src, file_path.clone(), syntax) {
+            Ok(ast) => return Ok(ast),
+            Err(err) => err,
+        };
+
+        let mut offset = first.offset;
+        let mut errors = vec![first];
+        // Resuming inside of an excessively nested template would only report the same error
+        // over and over again.
+        while errors.last().unwrap().message.as_deref() != Some(Level::TOO_DEEP) {
+            let Some(start) = next_block_start(src, offset, syntax) else {
+                break;
+            };
+            // The fragment could be inside of a loop, so `{% break %}` and `{% continue %}`
+            // are always accepted.
+            match Self::parse_fragment(&src[start..], src, file_path.clone(), syntax, 1) {
+                Ok(_) => break,
+                Err(err) => {
+                    offset = err.offset.max(start);
+                    if !is_stray_tag(src, offset, syntax) {
+                        errors.push(err);
+                    }
+                }
+            }
+        }
+        Err(errors)
+    }
+
+    /// Parses `src`, which is a suffix of `start`. Error offsets are relative to `start`.
+    fn parse_fragment(
         mut src: &'a str,
+        start: &'a str,
         file_path: Option<Arc<Path>>,
         syntax: &Syntax<'_>,
+        loop_depth: usize,
     ) -> Result<Self, ParseError> {
-        let start = src;
         let level = Cell::new(Level::MAX_DEPTH);
         let state = State {
             syntax,
-            loop_depth: Cell::new(0),
-            level: Level(&level),
+            loop_depth: Cell::new(loop_depth),
+            level: Level(&level, syntax.dialect),
+            forward_declarations: RefCell::default(),
         };
         match Node::parse_template(&mut src, &state) {
             Ok(nodes) if src.is_empty() => Ok(Self { nodes }),
@@ -137,6 +190,104 @@ impl<'a> Ast<'a> {
     }
 }
 
+/// Returns the offset of the next block delimiter after `offset`, that does not start a stray tag.
+///
+/// Block delimiters inside of comments are skipped. The error at `offset` is usually inside of a
+/// tag, so block delimiters inside of string literals are skipped, too, until the end of the tag.
+fn next_block_start(src: &str, offset: usize, syntax: &Syntax<'_>) -> Option<usize> {
+    let bytes = src.as_bytes();
+    let mut in_tag = true;
+    let mut idx = offset + 1;
+    while idx < bytes.len() {
+        let rest = &bytes[idx..];
+        if rest.starts_with(syntax.comment_start.as_bytes()) {
+            idx = end_of_comment(src, idx, syntax)?;
+            in_tag = false;
+        } else if rest.starts_with(syntax.block_start.as_bytes()) {
+            if !is_stray_tag(src, idx, syntax) {
+                return Some(idx);
+            }
+            idx += syntax.block_start.len();
+            in_tag = true;
+        } else if rest.starts_with(syntax.expr_start.as_bytes()) {
+            idx += syntax.expr_start.len();
+            in_tag = true;
+        } else if !in_tag {
+            idx += 1;
+        } else if rest.starts_with(syntax.block_end.as_bytes()) {
+            idx += syntax.block_end.len();
+            in_tag = false;
+        } else if rest.starts_with(syntax.expr_end.as_bytes()) {
+            idx += syntax.expr_end.len();
+            in_tag = false;
+        } else if rest[0] == b'"' || (rest[0] == b'\'' && syntax.dialect == Dialect::Jinja2) {
+            // an unterminated quote is not skipped
+            idx += 1 + end_of_string(&rest[1..], rest[0]).unwrap_or(0);
+        } else {
+            idx += 1;
+        }
+    }
+    None
+}
+
+/// Returns the offset after the (possibly nested) comment that starts at `offset`
+fn end_of_comment(src: &str, mut offset: usize, syntax: &Syntax<'_>) -> Option<usize> {
+    let mut depth = 0usize;
+    loop {
+        let rest = src.as_bytes().get(offset..)?;
+        if rest.starts_with(syntax.comment_start.as_bytes()) {
+            depth += 1;
+            offset += syntax.comment_start.len();
+        } else if rest.starts_with(syntax.comment_end.as_bytes()) {
+            depth -= 1;
+            offset += syntax.comment_end.len();
+            if depth == 0 {
+                return Some(offset);
+            }
+        } else if rest.is_empty() {
+            return None;
+        } else {
+            offset += 1;
+        }
+    }
+}
+
+/// Returns the length of the string literal `rest` including its closing `quote`
+fn end_of_string(rest: &[u8], quote: u8) -> Option<usize> {
+    let mut idx = 0;
+    loop {
+        match *rest.get(idx)? {
+            b'\\' => idx += 2,
+            c if c == quote => return Some(idx + 1),
+            _ => idx += 1,
+        }
+    }
+}
+
+/// Tests if `offset` is at the start of, or right after the opening delimiter of a closing or an
+/// intermediate tag, like `{% endif %}` or `{%- else %}`.
+fn is_stray_tag(src: &str, offset: usize, syntax: &Syntax<'_>) -> bool {
+    let Some((before, after)) = src.split_at_checked(offset) else {
+        return false;
+    };
+    let rest = if let Some(rest) = after.strip_prefix(syntax.block_start) {
+        rest
+    } else if before
+        .trim_end_matches(['-', '+', '~'])
+        .ends_with(syntax.block_start)
+    {
+        after
+    } else {
+        return false;
+    };
+    let rest = rest.trim_start_matches(['-', '+', '~']).trim_start();
+    let tag = match rest.find(|c: char| !c.is_ascii_alphanumeric() && c != '_') {
+        Some(end) => &rest[..end],
+        None => rest,
+    };
+    tag.starts_with("end") || matches!(tag, "elif" | "else" | "when")
+}
+
 /// Struct used to wrap types with their associated "span" which is used when generating errors
 /// in the code generation.
 pub struct WithSpan<'a, T> {
@@ -570,6 +721,38 @@ fn str_lit<'a>(i: &mut &'a str) -> ParseResult<'a, StrLit<'a>> {
     Ok(StrLit { prefix, content })
 }
 
+/// A Jinja2 string literal in single quotes, e.g. `'text'`.
+fn single_quoted_str_lit<'a>(i: &mut &'a str) -> ParseResult<'a, StrLit<'a>> {
+    let start = *i;
+    let content = delimited(
+        '\'',
+        opt(take_escaped(take_till(1.., ['\\', '\'']), '\\', any)),
+        '\'',
+    )
+    .parse_next(i)?
+    .unwrap_or_default();
+    // The content is used as is in a double-quoted rust string literal.
+    let mut chars = content.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => {
+                chars.next();
+            }
+            '"' => {
+                return Err(winnow::error::ErrMode::Cut(ErrorContext::new(
+                    "a single-quoted string cannot contain an unescaped `\"`",
+                    start,
+                )));
+            }
+            _ => {}
+        }
+    }
+    Ok(StrLit {
+        prefix: None,
+        content,
+    })
+}
+
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub enum CharPrefix {
     Binary,
@@ -738,9 +921,16 @@ struct State<'a, 'l> {
     syntax: &'l Syntax<'a>,
     loop_depth: Cell<usize>,
     level: Level<'l>,
+    /// Positions of bare `{% let x %}` tags that are known to be forward declarations, and not
+    /// block assignments.
+    forward_declarations: RefCell<HashSet<*const u8>>,
 }
 
 impl State<'_, '_> {
+    fn is_forward_declaration(&self, i: &str) -> bool {
+        self.forward_declarations.borrow().contains(&i.as_ptr())
+    }
+
     fn tag_block_start<'i>(&self, i: &mut &'i str) -> ParseResult<'i, ()> {
         self.syntax.block_start.value(()).parse_next(i)
     }
@@ -805,6 +995,19 @@ pub struct InnerSyntax<'a> {
     pub expr_end: &'a str,
     pub comment_start: &'a str,
     pub comment_end: &'a str,
+    pub dialect: Dialect,
+}
+
+/// The template language that is accepted in addition to rinja's own syntax.
+#[derive(Debug, Default, Clone, Copy, Hash, PartialEq, Eq)]
+#[cfg_attr(feature = "config", derive(serde_derive::Deserialize))]
+#[cfg_attr(feature = "config", serde(rename_all = "lowercase"))]
+pub enum Dialect {
+    #[default]
+    Rinja,
+    /// Also accept Jinja2 spellings like `a and not b`, `'single-quoted strings'`, `none`,
+    /// `True`, `x is even` or `{{ super() }}`, and map them to rinja's AST.
+    Jinja2,
 }
 
 impl<'a> Deref for Syntax<'a> {
@@ -825,6 +1028,7 @@ impl Default for InnerSyntax<'static> {
             expr_end: "}}",
             comment_start: "{#",
             comment_end: "#}",
+            dialect: Dialect::Rinja,
         }
     }
 }
@@ -851,6 +1055,7 @@ fn fmt_syntax(name: &str, inner: &InnerSyntax<'_>, f: &mut fmt::Formatter<'_>) -
         .field("expr_end", &inner.expr_end)
         .field("comment_start", &inner.comment_start)
         .field("comment_end", &inner.comment_end)
+        .field("dialect", &inner.dialect)
         .finish()
 }
 
@@ -864,6 +1069,7 @@ pub struct SyntaxBuilder<'a> {
     pub expr_end: Option<&'a str>,
     pub comment_start: Option<&'a str>,
     pub comment_end: Option<&'a str>,
+    pub dialect: Option<Dialect>,
 }
 
 impl<'a> SyntaxBuilder<'a> {
@@ -876,6 +1082,7 @@ impl<'a> SyntaxBuilder<'a> {
             expr_end: self.expr_end.unwrap_or(default.expr_end),
             comment_start: self.comment_start.unwrap_or(default.comment_start),
             comment_end: self.comment_end.unwrap_or(default.comment_end),
+            dialect: self.dialect.unwrap_or(default.dialect),
         });
 
         for (s, k, is_closing) in [
@@ -952,12 +1159,19 @@ impl<'a> SyntaxBuilder<'a> {
 /// [`Level::nest()`] / [`LevelGuard::nest()`] will return an error. The same [`Level`] instance is
 /// shared across all usages in a [`Parsed::new()`] / [`Ast::from_str()`] call, using a reference
 /// to an interior mutable counter.
+///
+/// Since it is passed to every expression parser, the [`Level`] also carries the [`Dialect`] of
+/// the template.
 #[derive(Debug, Clone, Copy)]
-struct Level<'l>(&'l Cell<usize>);
+struct Level<'l>(&'l Cell<usize>, Dialect);
 
 impl Level<'_> {
     const MAX_DEPTH: usize = 128;
 
+    fn is_jinja2(&self) -> bool {
+        self.1 == Dialect::Jinja2
+    }
+
     /// Acquire a [`LevelGuard`] without decrementing the counter, to be used with loops.
     fn guard(&self) -> LevelGuard<'_> {
         LevelGuard {
@@ -980,13 +1194,13 @@ impl Level<'_> {
         }
     }
 
+    const TOO_DEEP: &'static str =
+        "your template code is too deeply nested, or the last expression is too complex";
+
     #[cold]
     #[inline(never)]
     fn _fail(i: &str) -> ParseErr<'_> {
-        winnow::error::ErrMode::Cut(ErrorContext::new(
-            "your template code is too deeply nested, or the last expression is too complex",
-            i,
-        ))
+        winnow::error::ErrMode::Cut(ErrorContext::new(Self::TOO_DEEP, i))
     }
 }
 
@@ -11,8 +11,8 @@ use winnow::{ModalParser, Parser};
 
 use crate::memchr_splitter::{Splitter1, Splitter2, Splitter3};
 use crate::{
-    ErrorContext, Expr, Filter, ParseResult, Span, State, Target, WithSpan, filter, identifier,
-    is_rust_keyword, keyword, skip_till, skip_ws0, str_lit_without_prefix, ws,
+    ErrorContext, Expr, Filter, Level, ParseResult, Span, State, Target, WithSpan, filter,
+    identifier, is_rust_keyword, keyword, skip_till, skip_ws0, str_lit_without_prefix, ws,
 };
 
 #[derive(Debug, PartialEq)]
@@ -25,6 +25,8 @@ pub enum Node<'a> {
     If(WithSpan<'a, If<'a>>),
     Match(WithSpan<'a, Match<'a>>),
     Loop(Box<WithSpan<'a, Loop<'a>>>),
+    While(WithSpan<'a, While<'a>>),
+    With(WithSpan<'a, With<'a>>),
     Extends(WithSpan<'a, Extends<'a>>),
     BlockDef(WithSpan<'a, BlockDef<'a>>),
     Include(WithSpan<'a, Include<'a>>),
@@ -95,6 +97,8 @@ impl<'a> Node<'a> {
             "let" | "set" => |i: &mut _, s| Let::parse(i, s).map(Self::Let),
             "if" => |i: &mut _, s| If::parse(i, s).map(Self::If),
             "for" => |i: &mut _, s| Loop::parse(i, s).map(|n| Self::Loop(Box::new(n))),
+            "while" => |i: &mut _, s| While::parse(i, s).map(Self::While),
+            "with" => |i: &mut _, s| With::parse(i, s).map(Self::With),
             "match" => |i: &mut _, s| Match::parse(i, s).map(Self::Match),
             "extends" => |i: &mut _, _s| Extends::parse(i).map(Self::Extends),
             "include" => |i: &mut _, _s| Include::parse(i).map(Self::Include),
@@ -135,7 +139,7 @@ impl<'a> Node<'a> {
         let (pws, _, nws) = p.parse_next(i)?;
         if !s.is_in_loop() {
             return Err(winnow::error::ErrMode::Cut(ErrorContext::new(
-                "you can only `break` inside a `for` loop",
+                "you can only `break` inside a `for` or `while` loop",
                 start,
             )));
         }
@@ -153,7 +157,7 @@ impl<'a> Node<'a> {
         let (pws, _, nws) = p.parse_next(i)?;
         if !s.is_in_loop() {
             return Err(winnow::error::ErrMode::Cut(ErrorContext::new(
-                "you can only `continue` inside a `for` loop",
+                "you can only `continue` inside a `for` or `while` loop",
                 start,
             )));
         }
@@ -186,9 +190,29 @@ impl<'a> Node<'a> {
             ),
         )
         .parse_next(i)?;
-        match closed {
-            true => Ok(Self::Expr(Ws(pws, nws), expr)),
-            false => Err(ErrorContext::unclosed("expression", s.syntax.expr_end, start).cut()),
+        if !closed {
+            return Err(ErrorContext::unclosed("expression", s.syntax.expr_end, start).cut());
+        }
+        match *expr {
+            // Jinja2's `{{ super() }}` is rinja's `{% call super() %}`
+            Expr::Call {
+                ref path,
+                ref args,
+                ref generics,
+            } if s.level.is_jinja2()
+                && matches!(***path, Expr::Var("super"))
+                && args.is_empty()
+                && generics.is_empty() =>
+            {
+                let call = Call {
+                    ws: Ws(pws, nws),
+                    scope: None,
+                    name: "super",
+                    args: vec![],
+                };
+                Ok(Self::Call(WithSpan::new(call, start)))
+            }
+            _ => Ok(Self::Expr(Ws(pws, nws), expr)),
         }
     }
 
@@ -203,6 +227,8 @@ impl<'a> Node<'a> {
             Self::If(span) => span.span,
             Self::Match(span) => span.span,
             Self::Loop(span) => span.span,
+            Self::While(span) => span.span,
+            Self::With(span) => span.span,
             Self::Extends(span) => span.span,
             Self::BlockDef(span) => span.span,
             Self::Include(span) => span.span,
@@ -337,7 +363,7 @@ impl<'a> When<'a> {
             cut_node(
                 Some("match-when"),
                 (
-                    separated(1.., ws(|i: &mut _| Target::parse(i, s)), '|'),
+                    separated(1.., ws(|i: &mut _| Target::parse(i, s.level)), '|'),
                     opt(Whitespace::parse),
                     |i: &mut _| s.tag_block_end(i),
                     cut_node(Some("match-when"), |i: &mut _| Node::many(i, s)),
@@ -378,7 +404,7 @@ impl<'a> Cond<'a> {
                 preceded(
                     ws(keyword("elif")),
                     cut_node(Some("if-elif"), |i: &mut _| {
-                        CondTest::parse_cond(i, s).map(Some)
+                        CondTest::parse_cond(i, s.level).map(Some)
                     }),
                 ),
             )),
@@ -409,27 +435,27 @@ impl<'a> CondTest<'a> {
     fn parse(i: &mut &'a str, s: &State<'_, '_>) -> ParseResult<'a, Self> {
         preceded(
             ws(keyword("if")),
-            cut_node(Some("if"), |i: &mut _| Self::parse_cond(i, s)),
+            cut_node(Some("if"), |i: &mut _| Self::parse_cond(i, s.level)),
         )
         .parse_next(i)
     }
 
-    fn parse_cond(i: &mut &'a str, s: &State<'_, '_>) -> ParseResult<'a, Self> {
+    pub(crate) fn parse_cond(i: &mut &'a str, level: Level<'_>) -> ParseResult<'a, Self> {
         let (target, expr) = (
             opt(delimited(
                 ws(alt((keyword("let"), keyword("set")))),
-                ws(|i: &mut _| Target::parse(i, s)),
+                ws(|i: &mut _| Target::parse(i, level)),
                 ws('='),
             )),
             ws(|i: &mut _| {
                 let start = *i;
-                let mut expr = Expr::parse(i, s.level, false)?;
+                let mut expr = Expr::parse(i, level, false)?;
                 if let Expr::BinOp(_, _, ref mut right) = expr.inner {
                     if matches!(right.inner, Expr::Var("set" | "let")) {
-                        let _level_guard = s.level.nest(i)?;
+                        let _level_guard = level.nest(i)?;
                         *i = right.span.as_suffix_of(start).unwrap();
                         let start_span = Span::from(*i);
-                        let new_right = Self::parse_cond(i, s)?;
+                        let new_right = Self::parse_cond(i, level)?;
                         right.inner = Expr::LetCond(Box::new(WithSpan::new(new_right, start_span)));
                     }
                 }
@@ -577,12 +603,12 @@ impl<'a> Loop<'a> {
             cut_node(
                 Some("for"),
                 (
-                    ws(|i: &mut _| Target::parse(i, s)),
+                    ws(|i: &mut _| Target::parse(i, s.level)),
                     ws(keyword("in")),
                     cut_node(
                         Some("for"),
                         (
-                            ws(|i: &mut _| Expr::parse(i, s.level, true)),
+                            ws(|i: &mut _| Expr::parse_without_if(i, s.level, true)),
                             opt(if_cond),
                             opt(Whitespace::parse),
                             |i: &mut _| s.tag_block_end(i),
@@ -611,6 +637,128 @@ impl<'a> Loop<'a> {
     }
 }
 
+#[derive(Debug, PartialEq)]
+pub struct While<'a> {
+    pub ws1: Ws,
+    pub cond: WithSpan<'a, CondTest<'a>>,
+    pub nodes: Vec<Node<'a>>,
+    pub ws2: Ws,
+}
+
+impl<'a> While<'a> {
+    fn parse(i: &mut &'a str, s: &State<'_, '_>) -> ParseResult<'a, WithSpan<'a, Self>> {
+        let start = *i;
+        let mut p = (
+            opt(Whitespace::parse),
+            ws(keyword("while")),
+            cut_node(
+                Some("while"),
+                (
+                    ws(|i: &mut _| {
+                        let cond_start = *i;
+                        let cond = CondTest::parse_cond(i, s.level)?;
+                        Ok(WithSpan::new(cond, cond_start))
+                    }),
+                    opt(Whitespace::parse),
+                    |i: &mut _| s.tag_block_end(i),
+                    cut_node(
+                        Some("while"),
+                        (
+                            |i: &mut _| {
+                                s.enter_loop();
+                                let result = Node::many(i, s);
+                                s.leave_loop();
+                                result
+                            },
+                            cut_node(
+                                Some("while"),
+                                (
+                                    |i: &mut _| check_block_start(i, start, s, "while", "endwhile"),
+                                    opt(Whitespace::parse),
+                                    end_node("while", "endwhile"),
+                                    opt(Whitespace::parse),
+                                ),
+                            ),
+                        ),
+                    ),
+                ),
+            ),
+        );
+        let (pws1, _, (cond, nws1, _, (nodes, (_, pws2, _, nws2)))) = p.parse_next(i)?;
+        Ok(WithSpan::new(
+            Self {
+                ws1: Ws(pws1, nws1),
+                cond,
+                nodes,
+                ws2: Ws(pws2, nws2),
+            },
+            start,
+        ))
+    }
+}
+
+/// `{% with a = x, b = y %}...{% endwith %}`, the variables are only visible inside the block.
+#[derive(Debug, PartialEq)]
+pub struct With<'a> {
+    pub ws1: Ws,
+    pub vars: Vec<(Target<'a>, WithSpan<'a, Expr<'a>>)>,
+    pub nodes: Vec<Node<'a>>,
+    pub ws2: Ws,
+}
+
+impl<'a> With<'a> {
+    fn parse(i: &mut &'a str, s: &State<'_, '_>) -> ParseResult<'a, WithSpan<'a, Self>> {
+        let start = *i;
+        let var = |i: &mut _| {
+            let (var, _, val) = (
+                ws(|i: &mut _| Target::parse(i, s.level)),
+                ws('='),
+                ws(|i: &mut _| Expr::parse(i, s.level, false)),
+            )
+                .parse_next(i)?;
+            Ok((var, val))
+        };
+        let mut p = (
+            opt(Whitespace::parse),
+            ws(keyword("with")),
+            cut_node(
+                Some("with"),
+                (
+                    separated(1.., var, ','),
+                    opt(ws(',')),
+                    opt(Whitespace::parse),
+                    |i: &mut _| s.tag_block_end(i),
+                    cut_node(
+                        Some("with"),
+                        (
+                            |i: &mut _| Node::many(i, s),
+                            cut_node(
+                                Some("with"),
+                                (
+                                    |i: &mut _| check_block_start(i, start, s, "with", "endwith"),
+                                    opt(Whitespace::parse),
+                                    end_node("with", "endwith"),
+                                    opt(Whitespace::parse),
+                                ),
+                            ),
+                        ),
+                    ),
+                ),
+            ),
+        );
+        let (pws1, _, (vars, _, nws1, _, (nodes, (_, pws2, _, nws2)))) = p.parse_next(i)?;
+        Ok(WithSpan::new(
+            Self {
+                ws1: Ws(pws1, nws1),
+                vars,
+                nodes,
+                ws2: Ws(pws2, nws2),
+            },
+            start,
+        ))
+    }
+}
+
 #[derive(Debug, PartialEq)]
 pub struct Macro<'a> {
     pub ws1: Ws,
@@ -1158,7 +1306,25 @@ impl<'a> Raw<'a> {
 pub struct Let<'a> {
     pub ws: Ws,
     pub var: Target<'a>,
-    pub val: Option<WithSpan<'a, Expr<'a>>>,
+    pub val: Option<LetValueOrBlock<'a>>,
+    /// `{% let mut x = value %}`, later assignments to `x` update the variable instead of
+    /// shadowing it
+    pub is_mutable: bool,
+}
+
+/// The value of a `{% let %}` / `{% set %}` statement
+#[derive(Debug, PartialEq)]
+pub enum LetValueOrBlock<'a> {
+    /// `{% let x = value %}`
+    Value(WithSpan<'a, Expr<'a>>),
+    /// `{% set x | filters %}...{% endset %}`, the variable captures the rendered body
+    Block {
+        nodes: Vec<Node<'a>>,
+        /// The filters are applied to the rendered body, like in a `{% filter %}` block
+        filters: Option<Filter<'a>>,
+        /// The whitespace handling of the `{% endset %}` tag
+        ws: Ws,
+    },
 }
 
 impl<'a> Let<'a> {
@@ -1170,51 +1336,186 @@ impl<'a> Let<'a> {
             cut_node(
                 Some("let"),
                 (
-                    ws(|i: &mut _| Target::parse(i, s)),
+                    // `{% let mut %}` tries to declare a variable named `mut`
+                    opt(terminated(
+                        ws(keyword("mut")),
+                        not(|i: &mut _| s.tag_block_end(i)),
+                    ))
+                    .map(|m| m.is_some()),
+                    ws(|i: &mut _| Target::parse(i, s.level)),
                     opt(preceded(
                         ws('='),
                         ws(|i: &mut _| Expr::parse(i, s.level, false)),
                     )),
+                    repeat(0.., |i: &mut _| {
+                        let start = *i;
+                        filter(i, s.level)
+                            .map(|(name, generics, params)| (name, generics, params, start))
+                    })
+                    .map(|v: Vec<_>| v),
+                    ws(empty),
                     opt(Whitespace::parse),
                 ),
             ),
         );
-        let (pws, _, (var, val, nws)) = p.parse_next(i)?;
-        if val.is_none() {
-            let kind = match &var {
-                Target::Name(_) => None,
-                Target::Tuple(..) => Some("a tuple"),
-                Target::Array(..) => Some("an array"),
-                Target::Struct(..) => Some("a struct"),
-                Target::NumLit(..)
-                | Target::StrLit(..)
-                | Target::CharLit(..)
-                | Target::BoolLit(..) => Some("a literal"),
-                Target::Path(..) => Some("a path or enum variant"),
-                Target::OrChain(..) | Target::Placeholder(..) | Target::Rest(..) => {
-                    Some("a pattern")
+        let (pws, keyword, (is_mutable, var, val, filters, (), nws)) = p.parse_next(i)?;
+        let ws = Ws(pws, nws);
+        if is_mutable && !matches!(var, Target::Name(_)) {
+            return Err(winnow::error::ErrMode::Cut(ErrorContext::new(
+                "only a variable name can be declared `mut`",
+                start,
+            )));
+        }
+
+        // Any filters after a value would be part of the value expression.
+        if let Some(val) = val {
+            return Ok(WithSpan::new(
+                Let {
+                    ws,
+                    var,
+                    val: Some(LetValueOrBlock::Value(val)),
+                    is_mutable,
+                },
+                start,
+            ));
+        }
+
+        // Without a value, this is either a block assignment, or a forward declaration.
+        // Only a block assignment is terminated by `{% endset %}` / `{% endlet %}`.
+        let is_block = !filters.is_empty();
+        let before_block = *i;
+        // Trying to parse the rest of the template as the body of a block assignment again and
+        // again would take exponential time for consecutive forward declarations.
+        let block = if !is_block && s.is_forward_declaration(before_block) {
+            Ok(None)
+        } else {
+            Self::block(i, s, keyword, start, is_block)
+        };
+        match block {
+            Ok(Some((nodes, end_ws))) => {
+                if !matches!(var, Target::Name(_)) {
+                    return Err(winnow::error::ErrMode::Cut(ErrorContext::new(
+                        "a block assignment can only assign to a variable name",
+                        start,
+                    )));
                 }
-            };
-            if let Some(kind) = kind {
-                return Err(winnow::error::ErrMode::Cut(ErrorContext::new(
-                    format!(
-                        "when you forward-define a variable, you cannot use {kind} in place of \
-                         a variable name"
-                    ),
+                let filters =
+                    filters
+                        .into_iter()
+                        .fold(None, |source, (name, generics, arguments, span)| {
+                            let source = match source {
+                                Some(source) => WithSpan::new(Expr::Filter(source), span),
+                                None => WithSpan::new(Expr::FilterSource, span),
+                            };
+                            let mut arguments = arguments.unwrap_or_default();
+                            arguments.insert(0, source);
+                            Some(Filter {
+                                name,
+                                arguments,
+                                generics,
+                            })
+                        });
+                return Ok(WithSpan::new(
+                    Let {
+                        ws,
+                        var,
+                        val: Some(LetValueOrBlock::Block {
+                            nodes,
+                            filters,
+                            ws: end_ws,
+                        }),
+                        is_mutable,
+                    },
                     start,
-                )));
+                ));
+            }
+            Err(err) if is_block => return Err(err),
+            Ok(None) | Err(_) => {
+                *i = before_block;
+                s.forward_declarations
+                    .borrow_mut()
+                    .insert(before_block.as_ptr());
             }
         }
 
+        let kind = match &var {
+            Target::Name(_) => None,
+            Target::Tuple(..) => Some("a tuple"),
+            Target::Array(..) => Some("an array"),
+            Target::Struct(..) => Some("a struct"),
+            Target::NumLit(..) | Target::StrLit(..) | Target::CharLit(..) | Target::BoolLit(..) => {
+                Some("a literal")
+            }
+            Target::Path(..) => Some("a path or enum variant"),
+            Target::OrChain(..) | Target::Placeholder(..) | Target::Rest(..) => Some("a pattern"),
+        };
+        if let Some(kind) = kind {
+            return Err(winnow::error::ErrMode::Cut(ErrorContext::new(
+                format!(
+                    "when you forward-define a variable, you cannot use {kind} in place of \
+                     a variable name"
+                ),
+                start,
+            )));
+        }
+
         Ok(WithSpan::new(
             Let {
-                ws: Ws(pws, nws),
+                ws,
                 var,
-                val,
+                val: None,
+                is_mutable,
             },
             start,
         ))
     }
+
+    /// Parses the body of a block assignment, and its end tag, up to the closing `%}`.
+    ///
+    /// Returns `None` if the body is not terminated, i.e. if this is not a block assignment.
+    /// If `required`, then a missing end tag is an error.
+    fn block(
+        i: &mut &'a str,
+        s: &State<'_, '_>,
+        keyword: &'a str,
+        start: &'a str,
+        required: bool,
+    ) -> ParseResult<'a, Option<(Vec<Node<'a>>, Ws)>> {
+        let end_keyword = match keyword {
+            "set" => "endset",
+            _ => "endlet",
+        };
+        if opt(|i: &mut _| s.tag_block_end(i)).parse_next(i)?.is_none() {
+            return Ok(None);
+        }
+        if !required && memchr::memmem::find(i.as_bytes(), end_keyword.as_bytes()).is_none() {
+            return Ok(None);
+        }
+        let nodes = Node::many(i, s)?;
+        if required {
+            check_block_start(i, start, s, keyword, end_keyword)?;
+        } else if opt(|i: &mut _| s.tag_block_start(i))
+            .parse_next(i)?
+            .is_none()
+        {
+            return Ok(None);
+        }
+        let end = (
+            opt(Whitespace::parse),
+            end_node(keyword, end_keyword),
+            opt(Whitespace::parse),
+        )
+            .parse_next(i);
+        match end {
+            Ok((pws, _, nws)) => Ok(Some((nodes, Ws(pws, nws)))),
+            Err(_) if !required => Ok(None),
+            Err(err @ winnow::error::ErrMode::Cut(_)) => Err(err),
+            Err(_) => Err(winnow::error::ErrMode::Cut(ErrorContext::new(
+                format!("expected `{end_keyword}` to terminate `{keyword}` node"),
+                *i,
+            ))),
+        }
+    }
 }
 
 #[derive(Debug, PartialEq)]
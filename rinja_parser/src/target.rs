@@ -3,7 +3,7 @@ use winnow::token::one_of;
 use winnow::{ModalParser, Parser};
 
 use crate::{
-    CharLit, ErrorContext, Num, ParseErr, ParseResult, PathOrIdentifier, State, StrLit, WithSpan,
+    CharLit, ErrorContext, Level, Num, ParseErr, ParseResult, PathOrIdentifier, StrLit, WithSpan,
     bool_lit, char_lit, identifier, is_rust_keyword, keyword, num_lit, path_or_identifier, str_lit,
     ws,
 };
@@ -27,13 +27,13 @@ pub enum Target<'a> {
 
 impl<'a> Target<'a> {
     /// Parses multiple targets with `or` separating them
-    pub(super) fn parse(i: &mut &'a str, s: &State<'_, '_>) -> ParseResult<'a, Self> {
-        let _level_guard = s.level.nest(i)?;
+    pub(super) fn parse(i: &mut &'a str, level: Level<'_>) -> ParseResult<'a, Self> {
+        let _level_guard = level.nest(i)?;
         let mut p = opt(preceded(ws(keyword("or")), |i: &mut _| {
-            Self::parse_one(i, s)
+            Self::parse_one(i, level)
         }));
 
-        let target = Self::parse_one(i, s)?;
+        let target = Self::parse_one(i, level)?;
         let Some(snd_target) = p.parse_next(i)? else {
             return Ok(target);
         };
@@ -46,7 +46,7 @@ impl<'a> Target<'a> {
     }
 
     /// Parses a single target without an `or`, unless it is wrapped in parentheses.
-    fn parse_one(i: &mut &'a str, s: &State<'_, '_>) -> ParseResult<'a, Self> {
+    fn parse_one(i: &mut &'a str, level: Level<'_>) -> ParseResult<'a, Self> {
         let mut opt_opening_paren = opt(ws('(')).map(|o| o.is_some());
         let mut opt_opening_brace = opt(ws('{')).map(|o| o.is_some());
         let mut opt_opening_bracket = opt(ws('[')).map(|o| o.is_some());
@@ -60,7 +60,7 @@ impl<'a> Target<'a> {
         let target_is_tuple = opt_opening_paren.parse_next(i)?;
         if target_is_tuple {
             let (singleton, mut targets) =
-                collect_targets(i, ')', |i: &mut _| Self::unnamed(i, s))?;
+                collect_targets(i, ')', |i: &mut _| Self::unnamed(i, level))?;
             if singleton {
                 return Ok(targets.pop().unwrap());
             }
@@ -72,7 +72,7 @@ impl<'a> Target<'a> {
         let target_is_array = opt_opening_bracket.parse_next(i)?;
         if target_is_array {
             let (singleton, mut targets) =
-                collect_targets(i, ']', |i: &mut _| Self::unnamed(i, s))?;
+                collect_targets(i, ']', |i: &mut _| Self::unnamed(i, level))?;
             if singleton {
                 return Ok(targets.pop().unwrap());
             }
@@ -95,7 +95,7 @@ impl<'a> Target<'a> {
 
             let is_unnamed_struct = opt_opening_paren.parse_next(i)?;
             if is_unnamed_struct {
-                let (_, targets) = collect_targets(i, ')', |i: &mut _| Self::unnamed(i, s))?;
+                let (_, targets) = collect_targets(i, ')', |i: &mut _| Self::unnamed(i, level))?;
                 return Ok(Self::Tuple(
                     path,
                     only_one_rest_pattern(targets, false, "struct")?,
@@ -104,7 +104,7 @@ impl<'a> Target<'a> {
 
             let is_named_struct = opt_opening_brace.parse_next(i)?;
             if is_named_struct {
-                let (_, targets) = collect_targets(i, '}', |i: &mut _| Self::named(i, s))?;
+                let (_, targets) = collect_targets(i, '}', |i: &mut _| Self::named(i, level))?;
                 return Ok(Self::Struct(path, targets));
             }
 
@@ -134,11 +134,11 @@ impl<'a> Target<'a> {
         .parse_next(i)
     }
 
-    fn unnamed(i: &mut &'a str, s: &State<'_, '_>) -> ParseResult<'a, Self> {
-        alt((Self::rest, |i: &mut _| Self::parse(i, s))).parse_next(i)
+    fn unnamed(i: &mut &'a str, level: Level<'_>) -> ParseResult<'a, Self> {
+        alt((Self::rest, |i: &mut _| Self::parse(i, level))).parse_next(i)
     }
 
-    fn named(i: &mut &'a str, s: &State<'_, '_>) -> ParseResult<'a, (&'a str, Self)> {
+    fn named(i: &mut &'a str, level: Level<'_>) -> ParseResult<'a, (&'a str, Self)> {
         let start = *i;
         let rest = opt(Self::rest.with_taken()).parse_next(i)?;
         if let Some(rest) = rest {
@@ -166,7 +166,7 @@ impl<'a> Target<'a> {
         *i = start;
         let (src, target) = (
             identifier,
-            opt(preceded(ws(':'), |i: &mut _| Self::parse(i, s))),
+            opt(preceded(ws(':'), |i: &mut _| Self::parse(i, level))),
         )
             .parse_next(i)?;
 
@@ -1,6 +1,7 @@
-use crate::node::{Lit, Whitespace, Ws};
+use crate::node::{CondTest, Let, LetValueOrBlock, Lit, Whitespace, Ws};
 use crate::{
-    Ast, Expr, Filter, InnerSyntax, Node, Num, Span, StrLit, Syntax, SyntaxBuilder, WithSpan,
+    Ast, Attr, Dialect, Expr, Filter, InnerSyntax, Node, Num, Span, StrLit, Syntax, SyntaxBuilder,
+    Target, WithSpan,
 };
 
 impl<T> WithSpan<'static, T> {
@@ -1116,6 +1117,492 @@ fn let_set() {
     );
 }
 
+#[test]
+fn test_inline_if_else() {
+    fn if_else<'a>(
+        then: Expr<'a>,
+        cond: Expr<'a>,
+        otherwise: Option<Expr<'a>>,
+    ) -> WithSpan<'a, Expr<'a>> {
+        WithSpan::no_span(Expr::IfElse {
+            then: Box::new(WithSpan::no_span(then)),
+            cond: Box::new(WithSpan::no_span(CondTest {
+                target: None,
+                expr: WithSpan::no_span(cond),
+                contains_bool_lit_or_is_defined: false,
+            })),
+            otherwise: otherwise.map(|e| Box::new(WithSpan::no_span(e))),
+        })
+    }
+
+    let syntax = Syntax::default();
+    assert_eq!(
+        Ast::from_str("{{ a if b else c }}", None, &syntax)
+            .unwrap()
+            .nodes,
+        vec![Node::Expr(
+            Ws(None, None),
+            if_else(Expr::Var("a"), Expr::Var("b"), Some(Expr::Var("c"))),
+        )],
+    );
+    assert_eq!(
+        Ast::from_str("{{ a if b }}", None, &syntax).unwrap().nodes,
+        vec![Node::Expr(
+            Ws(None, None),
+            if_else(Expr::Var("a"), Expr::Var("b"), None),
+        )],
+    );
+    // the `else` branch is right associative
+    assert_eq!(
+        Ast::from_str("{{ a if b else c if d else e }}", None, &syntax)
+            .unwrap()
+            .nodes,
+        vec![Node::Expr(
+            Ws(None, None),
+            if_else(
+                Expr::Var("a"),
+                Expr::Var("b"),
+                Some(if_else(Expr::Var("c"), Expr::Var("d"), Some(Expr::Var("e"))).inner),
+            ),
+        )],
+    );
+    // it binds looser than any operator
+    assert_eq!(
+        Ast::from_str("{{ a ~ b if c || d else e }}", None, &syntax)
+            .unwrap()
+            .nodes,
+        vec![Node::Expr(
+            Ws(None, None),
+            if_else(
+                Expr::Concat(vec![
+                    WithSpan::no_span(Expr::Var("a")),
+                    WithSpan::no_span(Expr::Var("b")),
+                ]),
+                Expr::BinOp(
+                    "||",
+                    Box::new(WithSpan::no_span(Expr::Var("c"))),
+                    Box::new(WithSpan::no_span(Expr::Var("d"))),
+                ),
+                Some(Expr::Var("e")),
+            ),
+        )],
+    );
+
+    // the condition can bind variables like in `{% if let %}`
+    let ast = Ast::from_str("{{ x if let Some(x) = y else z }}", None, &syntax).unwrap();
+    assert!(matches!(
+        ast.nodes(),
+        [Node::Expr(_, expr)] if matches!(
+            &**expr,
+            Expr::IfElse { cond, otherwise: Some(_), .. } if cond.target.is_some(),
+        ),
+    ));
+
+    // in a `for` loop, the `if` is the loop condition
+    let ast = Ast::from_str("{% for x in y if z %}{% endfor %}", None, &syntax).unwrap();
+    assert!(matches!(
+        ast.nodes(),
+        [Node::Loop(l)] if *l.iter == Expr::Var("y") && l.cond.as_deref() == Some(&Expr::Var("z")),
+    ));
+
+    assert!(Ast::from_str("{{ a if }}", None, &syntax).is_err());
+    assert!(Ast::from_str("{{ a if b else }}", None, &syntax).is_err());
+}
+
+#[test]
+fn test_in_operator() {
+    let syntax = Syntax::default();
+    for (source, op) in [("{{ a in b }}", "in"), ("{{ a not  in b }}", "not in")] {
+        assert_eq!(
+            Ast::from_str(source, None, &syntax).unwrap().nodes,
+            vec![Node::Expr(
+                Ws(None, None),
+                WithSpan::no_span(Expr::BinOp(
+                    op,
+                    Box::new(WithSpan::no_span(Expr::Var("a"))),
+                    Box::new(WithSpan::no_span(Expr::Var("b"))),
+                )),
+            )],
+        );
+    }
+
+    // `in` has the same precedence as the comparison operators
+    assert_eq!(
+        Ast::from_str("{{ a + b in c && d }}", None, &syntax)
+            .unwrap()
+            .nodes,
+        vec![Node::Expr(
+            Ws(None, None),
+            WithSpan::no_span(Expr::BinOp(
+                "&&",
+                Box::new(WithSpan::no_span(Expr::BinOp(
+                    "in",
+                    Box::new(WithSpan::no_span(Expr::BinOp(
+                        "+",
+                        Box::new(WithSpan::no_span(Expr::Var("a"))),
+                        Box::new(WithSpan::no_span(Expr::Var("b"))),
+                    ))),
+                    Box::new(WithSpan::no_span(Expr::Var("c"))),
+                ))),
+                Box::new(WithSpan::no_span(Expr::Var("d"))),
+            )),
+        )],
+    );
+
+    // identifiers that merely start with `in` or `not` are not operators
+    assert!(Ast::from_str("{{ a inner }}", None, &syntax).is_err());
+    assert!(Ast::from_str("{{ a nothing }}", None, &syntax).is_err());
+    assert!(Ast::from_str("{{ a not }}", None, &syntax).is_err());
+}
+
+#[test]
+fn test_map_literal() {
+    let syntax = Syntax::default();
+    assert_eq!(
+        Ast::from_str("{{ {} }}", None, &syntax).unwrap().nodes,
+        vec![Node::Expr(
+            Ws(None, None),
+            WithSpan::no_span(Expr::Map(vec![]))
+        )],
+    );
+    assert_eq!(
+        Ast::from_str(r#"{{ { "a": 1, "b" : {"c": x},} }}"#, None, &syntax)
+            .unwrap()
+            .nodes,
+        vec![Node::Expr(
+            Ws(None, None),
+            WithSpan::no_span(Expr::Map(vec![
+                (
+                    "a",
+                    WithSpan::no_span(Expr::NumLit("1", Num::Int("1", None)))
+                ),
+                (
+                    "b",
+                    WithSpan::no_span(Expr::Map(vec![("c", WithSpan::no_span(Expr::Var("x")))]))
+                ),
+            ])),
+        )],
+    );
+
+    assert_eq!(
+        Ast::from_str("{{ {a: 1} }}", None, &syntax)
+            .unwrap_err()
+            .to_string(),
+        "map keys must be string literals\nfailed to parse template source near offset 4",
+    );
+    assert_eq!(
+        Ast::from_str(r#"{{ {"a": 1, "a": 2} }}"#, None, &syntax)
+            .unwrap_err()
+            .to_string(),
+        "key `a` was used more than once in the map\n\
+         failed to parse template source near offset 12",
+    );
+    assert!(Ast::from_str(r#"{{ {b"a": 1} }}"#, None, &syntax).is_err());
+    assert!(Ast::from_str(r#"{{ {"a": 1 "b": 2} }}"#, None, &syntax).is_err());
+}
+
+#[test]
+fn test_closure() {
+    let syntax = Syntax::default();
+    assert_eq!(
+        Ast::from_str("{{ a|f(|x| x.name, || 1) }}", None, &syntax)
+            .unwrap()
+            .nodes,
+        vec![Node::Expr(
+            Ws(None, None),
+            WithSpan::no_span(Expr::Filter(Filter {
+                name: "f",
+                arguments: vec![
+                    WithSpan::no_span(Expr::Var("a")),
+                    WithSpan::no_span(Expr::Closure {
+                        params: vec!["x"],
+                        body: Box::new(WithSpan::no_span(Expr::Attr(
+                            Box::new(WithSpan::no_span(Expr::Var("x"))),
+                            Attr {
+                                name: "name",
+                                generics: vec![],
+                            },
+                        ))),
+                    }),
+                    WithSpan::no_span(Expr::Closure {
+                        params: vec![],
+                        body: Box::new(WithSpan::no_span(Expr::NumLit("1", Num::Int("1", None),))),
+                    }),
+                ],
+                generics: vec![],
+            })),
+        )],
+    );
+
+    // the body extends as far as possible
+    assert_eq!(
+        Ast::from_str("{% let f = | a , b, | a + b|abs %}", None, &syntax)
+            .unwrap()
+            .nodes,
+        vec![Node::Let(WithSpan::no_span(Let {
+            ws: Ws(None, None),
+            var: Target::Name("f"),
+            val: Some(LetValueOrBlock::Value(WithSpan::no_span(Expr::Closure {
+                params: vec!["a", "b"],
+                body: Box::new(WithSpan::no_span(Expr::BinOp(
+                    "+",
+                    Box::new(WithSpan::no_span(Expr::Var("a"))),
+                    Box::new(WithSpan::no_span(Expr::Filter(Filter {
+                        name: "abs",
+                        arguments: vec![WithSpan::no_span(Expr::Var("b"))],
+                        generics: vec![],
+                    }))),
+                ))),
+            }))),
+            is_mutable: false,
+        }))],
+    );
+
+    assert!(Ast::from_str("{{ |x| }}", None, &syntax).is_err());
+    assert!(Ast::from_str("{{ |1| x }}", None, &syntax).is_err());
+    assert!(Ast::from_str("{{ |x x }}", None, &syntax).is_err());
+}
+
+#[test]
+fn test_while() {
+    let syntax = Syntax::default();
+    let ast = Ast::from_str(
+        "{% while let Some(x) = it.next() -%}{% break %}{%- endwhile %}",
+        None,
+        &syntax,
+    )
+    .unwrap();
+    let [Node::While(w)] = ast.nodes() else {
+        panic!("expected a single `while` node: {:?}", ast.nodes());
+    };
+    assert_eq!(w.ws1, Ws(None, Some(Whitespace::Suppress)));
+    assert_eq!(w.ws2, Ws(Some(Whitespace::Suppress), None));
+    assert_eq!(
+        w.cond.target,
+        Some(Target::Tuple(vec!["Some"], vec![Target::Name("x")]))
+    );
+    assert!(matches!(*w.cond.expr, Expr::Call { .. }));
+    assert!(matches!(w.nodes[..], [Node::Break(_)]));
+
+    assert_eq!(
+        Ast::from_str("{% while x %}", None, &syntax)
+            .unwrap_err()
+            .to_string(),
+        "expected `endwhile` to terminate `while` node, found nothing\n\
+         failed to parse template source near offset 2",
+    );
+    assert!(Ast::from_str("{% while x %}{% endfor %}", None, &syntax).is_err());
+    assert!(Ast::from_str("{% while %}{% endwhile %}", None, &syntax).is_err());
+}
+
+#[test]
+fn test_with() {
+    let syntax = Syntax::default();
+    let ast = Ast::from_str(
+        "{%- with a = b, (c, d) = e, %}{{ a }}{% endwith -%}",
+        None,
+        &syntax,
+    )
+    .unwrap();
+    let [Node::With(w)] = ast.nodes() else {
+        panic!("expected a single `with` node: {:?}", ast.nodes());
+    };
+    assert_eq!(w.ws1, Ws(Some(Whitespace::Suppress), None));
+    assert_eq!(w.ws2, Ws(None, Some(Whitespace::Suppress)));
+    assert_eq!(
+        w.vars,
+        [
+            (Target::Name("a"), WithSpan::no_span(Expr::Var("b"))),
+            (
+                Target::Tuple(vec![], vec![Target::Name("c"), Target::Name("d")]),
+                WithSpan::no_span(Expr::Var("e")),
+            ),
+        ],
+    );
+    assert!(matches!(w.nodes[..], [Node::Expr(..)]));
+
+    assert!(Ast::from_str("{% with %}{% endwith %}", None, &syntax).is_err());
+    assert!(Ast::from_str("{% with a %}{% endwith %}", None, &syntax).is_err());
+    assert!(Ast::from_str("{% with a = b %}{% endif %}", None, &syntax).is_err());
+}
+
+#[test]
+fn test_let_mut() {
+    let syntax = Syntax::default();
+    let ast = Ast::from_str(
+        "{% let mut a = 1 %}{% set mut b %}{% let c = 2 %}",
+        None,
+        &syntax,
+    )
+    .unwrap();
+    let [Node::Let(a), Node::Let(b), Node::Let(c)] = ast.nodes() else {
+        panic!("expected three `let` nodes: {:?}", ast.nodes());
+    };
+    assert_eq!(a.var, Target::Name("a"));
+    assert!(a.is_mutable);
+    assert_eq!(b.var, Target::Name("b"));
+    assert!(b.is_mutable && b.val.is_none());
+    assert!(!c.is_mutable);
+
+    assert_eq!(
+        Ast::from_str("{% let mut (a, b) = c %}", None, &syntax)
+            .unwrap_err()
+            .to_string(),
+        "only a variable name can be declared `mut`\nfailed to parse template source near \
+         offset 2",
+    );
+    assert!(Ast::from_str("{% let mut %}", None, &syntax).is_err());
+}
+
+#[test]
+fn test_jinja2_dialect() {
+    let syntax = Syntax(InnerSyntax {
+        dialect: Dialect::Jinja2,
+        ..InnerSyntax::default()
+    });
+    let var = |name| Box::new(WithSpan::no_span(Expr::Var(name)));
+    let group = |expr| {
+        Box::new(WithSpan::no_span(Expr::Group(Box::new(WithSpan::no_span(
+            expr,
+        )))))
+    };
+
+    // `not` has a lower precedence than comparisons
+    assert_eq!(
+        Ast::from_str("{{ not a == b and c or d }}", None, &syntax)
+            .unwrap()
+            .nodes,
+        vec![Node::Expr(
+            Ws(None, None),
+            WithSpan::no_span(Expr::BinOp(
+                "||",
+                Box::new(WithSpan::no_span(Expr::BinOp(
+                    "&&",
+                    Box::new(WithSpan::no_span(Expr::Unary(
+                        "!",
+                        group(Expr::BinOp("==", var("a"), var("b"))),
+                    ))),
+                    var("c"),
+                ))),
+                var("d"),
+            )),
+        )],
+    );
+    assert_eq!(
+        Ast::from_str("{{ ['a', True, none] }}", None, &syntax)
+            .unwrap()
+            .nodes,
+        vec![Node::Expr(
+            Ws(None, None),
+            WithSpan::no_span(Expr::Array(vec![
+                WithSpan::no_span(Expr::StrLit(StrLit {
+                    prefix: None,
+                    content: "a",
+                })),
+                WithSpan::no_span(Expr::BoolLit(true)),
+                WithSpan::no_span(Expr::Path(vec!["None"])),
+            ])),
+        )],
+    );
+    assert_eq!(
+        Ast::from_str("{{ x is not odd }}", None, &syntax)
+            .unwrap()
+            .nodes,
+        vec![Node::Expr(
+            Ws(None, None),
+            WithSpan::no_span(Expr::Unary(
+                "!",
+                group(Expr::BinOp(
+                    "!=",
+                    group(Expr::BinOp(
+                        "%",
+                        var("x"),
+                        Box::new(WithSpan::no_span(Expr::NumLit("2", Num::Int("2", None)))),
+                    )),
+                    Box::new(WithSpan::no_span(Expr::NumLit("0", Num::Int("0", None)))),
+                )),
+            )),
+        )],
+    );
+    assert!(matches!(
+        Ast::from_str("{{ super() }}", None, &syntax).unwrap().nodes[..],
+        [Node::Call(ref call)] if call.name == "super",
+    ));
+
+    assert_eq!(
+        Ast::from_str("{{ x is foo }}", None, &syntax)
+            .unwrap_err()
+            .to_string()
+            .lines()
+            .next(),
+        Some(
+            "unknown test `foo` or wrong number of arguments, supported are `defined`, `none`, \
+             `true`, `false`, `even`, `odd`, `divisibleby(n)`, `in(seq)` and the comparisons \
+             `eq(x)`, `ne(x)`, `lt(x)`, `le(x)`, `gt(x)`, `ge(x)`",
+        ),
+    );
+    assert!(Ast::from_str("{{ 'a\"b' }}", None, &syntax).is_err());
+    assert!(Ast::from_str("{{ 'a\\\"b' }}", None, &syntax).is_ok());
+
+    // none of this is accepted without the dialect
+    let syntax = Syntax::default();
+    assert!(Ast::from_str("{{ a and b }}", None, &syntax).is_err());
+    assert!(Ast::from_str("{{ 'ab' }}", None, &syntax).is_err());
+    assert!(Ast::from_str("{{ x is even }}", None, &syntax).is_err());
+    assert!(matches!(
+        Ast::from_str("{{ super() }}", None, &syntax).unwrap().nodes[..],
+        [Node::Expr(..)],
+    ));
+}
+
+#[test]
+fn let_block() {
+    let syntax = Syntax::default();
+    let ast = Ast::from_str("{% set x | upper %}a{% endset %}", None, &syntax).unwrap();
+    let [Node::Let(l)] = ast.nodes() else {
+        panic!("expected a single `let` node: {:?}", ast.nodes());
+    };
+    assert_eq!(l.var, Target::Name("x"));
+    let Some(LetValueOrBlock::Block {
+        nodes,
+        filters: Some(filters),
+        ..
+    }) = &l.val
+    else {
+        panic!("expected a block assignment: {:?}", l.val);
+    };
+    assert_eq!(filters.name, "upper");
+    assert!(matches!(nodes.as_slice(), [Node::Lit(_)]));
+
+    let ast = Ast::from_str("{% let x %}a{% endlet %}", None, &syntax).unwrap();
+    assert!(matches!(
+        ast.nodes(),
+        [Node::Let(l)] if matches!(l.val, Some(LetValueOrBlock::Block { filters: None, .. })),
+    ));
+
+    // without an end tag, this is a forward declaration
+    let ast = Ast::from_str("{% let x %}a", None, &syntax).unwrap();
+    assert!(matches!(ast.nodes(), [Node::Let(l), Node::Lit(_)] if l.val.is_none()));
+
+    // filters require an end tag
+    assert!(Ast::from_str("{% set x | upper %}a", None, &syntax).is_err());
+    // the end tag must match the start tag
+    assert!(Ast::from_str("{% set x %}a{% endlet %}", None, &syntax).is_err());
+    // only a variable can capture a block
+    assert!(Ast::from_str("{% set (a, b) %}a{% endset %}", None, &syntax).is_err());
+
+    // Many forward declarations must not take exponential time, even if the template contains
+    // an end tag further down.
+    let mut src = "{% set x %}a".repeat(64);
+    src.push_str("{% set y %}b{% endset %}");
+    let ast = Ast::from_str(&src, None, &syntax).unwrap();
+    assert_eq!(ast.nodes().len(), 129);
+    assert!(matches!(
+        ast.nodes().last(),
+        Some(Node::Let(l)) if matches!(l.val, Some(LetValueOrBlock::Block { .. })),
+    ));
+}
+
 #[test]
 fn fuzzed_filter_recursion() {
     const TEMPLATE: &str = include_str!("../tests/filter-recursion.txt");
@@ -1342,3 +1829,55 @@ fn fuzzed_deeply_tested_if_let() {
         Some("your template code is too deeply nested, or the last expression is too complex"),
     );
 }
+
+#[test]
+fn test_error_recovery() {
+    let syntax = Syntax::default();
+    let errors = |src: &str| -> Vec<(usize, String)> {
+        Ast::from_str_with_recovery(src, None, &syntax)
+            .unwrap_err()
+            .into_iter()
+            .map(|err| (err.offset, err.message.unwrap_or_default().into_owned()))
+            .collect()
+    };
+
+    assert!(Ast::from_str_with_recovery("{% if a %}{{ b }}{% endif %}", None, &syntax).is_ok());
+
+    // Every broken tag is reported, stray closing tags are skipped.
+    let src = "{% if a + %}x{% else %}y{% endif %}\n\
+        {% for %}{% endfor %}\n\
+        {% foo %}";
+    assert_eq!(
+        errors(src),
+        [
+            (9, String::new()),
+            (43, String::new()),
+            (60, "unknown node `foo`".into()),
+        ],
+    );
+    assert_eq!(
+        Ast::from_str(src, None, &syntax).unwrap_err().offset,
+        errors(src)[0].0,
+    );
+
+    // After an error inside a loop, `{% break %}` is still accepted.
+    let src = "{% for x in y %}{{ x + }}{% if x %}{% break %}{% endif %}{% endfor %}{% bar %}";
+    assert_eq!(
+        errors(src)
+            .into_iter()
+            .map(|(_, msg)| msg)
+            .collect::<Vec<_>>(),
+        ["", "unknown node `bar`"],
+    );
+
+    // The resynchronization skips block delimiters in comments and string literals.
+    let src = "{% if a + %}{# {% foo %} {# {% nested %} #} #}\n\
+        {% if b = \"{% quoted %}\" %}{% endif %}{% bar %}";
+    assert_eq!(
+        errors(src)
+            .into_iter()
+            .map(|(_, msg)| msg)
+            .collect::<Vec<_>>(),
+        ["", "", "unknown node `bar`"],
+    );
+}
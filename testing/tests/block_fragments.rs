@@ -163,3 +163,21 @@ fn test_variable_inheritance_in_block() {
 
     assert_eq!(Y.render().unwrap(), "--> 42\n42");
 }
+
+/// Blocks that do not contain the rendered block are skipped, blocks that contain it are not.
+#[test]
+fn test_fragment_skips_other_blocks() {
+    #[derive(Template)]
+    #[template(
+        source = r#"{% block a %}{% for i in 0..3 %}a{% endfor %}{% endblock %}
+{%- block b %}<p>{% block c %}{{ name }}{% endblock %}</p>{% endblock -%}"#,
+        ext = "html",
+        block = "c"
+    )]
+    struct FragmentSkip<'a> {
+        name: &'a str,
+    }
+
+    let t = FragmentSkip { name: "world" };
+    assert_eq!(t.render().unwrap(), "world");
+}
@@ -0,0 +1,77 @@
+use rinja::Template;
+
+mod filters {
+    pub fn sort_by_key<T, K: Ord>(items: &[T], key: impl Fn(&T) -> K) -> rinja::Result<Vec<&T>> {
+        let mut items = items.iter().collect::<Vec<_>>();
+        items.sort_by_key(|item| key(item));
+        Ok(items)
+    }
+
+    pub fn map_join<T, U: std::fmt::Display>(
+        items: &[T],
+        f: impl Fn(&T) -> U,
+        sep: &str,
+    ) -> rinja::Result<String> {
+        Ok(items
+            .iter()
+            .map(|item| f(item).to_string())
+            .collect::<Vec<_>>()
+            .join(sep))
+    }
+}
+
+struct User {
+    name: &'static str,
+    age: u32,
+}
+
+#[test]
+fn test_closure_filter_argument() {
+    #[derive(Template)]
+    #[template(
+        source = r#"
+{%- for user in users|sort_by_key(|u| u.age) -%}
+    {{ user.name }};
+{%- endfor -%}
+{{ users|map_join(|u| u.name, ", ") }}
+{{- users|map_join(|u| u.age + offset, ",") -}}
+"#,
+        ext = "txt"
+    )]
+    struct ClosureFilter {
+        users: Vec<User>,
+        offset: u32,
+    }
+
+    let t = ClosureFilter {
+        users: vec![User { name: "b", age: 30 }, User { name: "a", age: 20 }],
+        offset: 1,
+    };
+    assert_eq!(t.render().unwrap(), "a;b;b, a31,21");
+}
+
+#[test]
+fn test_closure_captures() {
+    #[derive(Template)]
+    #[template(
+        source = r#"
+{%- let bonus = 5 -%}
+{%- for sep in [",", ";"] -%}
+    {{ users|map_join(|user| user.age + bonus, sep) }}|
+{%- endfor -%}
+{{ users|map_join(|_| "?", "") }}
+{{- users|map_join(|user| user.name if user.age > min_age else "-", "") -}}
+"#,
+        ext = "txt"
+    )]
+    struct ClosureCaptures {
+        users: Vec<User>,
+        min_age: u32,
+    }
+
+    let t = ClosureCaptures {
+        users: vec![User { name: "a", age: 30 }, User { name: "b", age: 20 }],
+        min_age: 25,
+    };
+    assert_eq!(t.render().unwrap(), "35,25|35;25|??a-");
+}
@@ -0,0 +1,33 @@
+use rinja::Template;
+
+// Wrapping expressions in location markers must not change how they resolve variables.
+#[test]
+fn test_error_locations() {
+    #[derive(Template)]
+    #[template(
+        source = r#"
+{%- macro item(value) -%}
+    [{{ value }}]
+{%- endmacro -%}
+{%- let total = items.len() -%}
+{%- for item in items -%}
+    {%- let doubled = item * 2 -%}
+    {%- if let Some(label) = label -%}{{ label }}{%- endif -%}
+    {%- call item(doubled) -%}
+    {%- match doubled %}{% when 4 %}!{% else %}{% endmatch -%}
+{%- endfor -%}
+/{{ total }}"#,
+        ext = "txt",
+        config = "error-locations.toml"
+    )]
+    struct Items<'a> {
+        items: &'a [u32],
+        label: Option<&'a str>,
+    }
+
+    let tmpl = Items {
+        items: &[1, 2, 3],
+        label: Some("-"),
+    };
+    assert_eq!(tmpl.render().unwrap(), "-[2]-[4]!-[6]/3");
+}
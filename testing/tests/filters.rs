@@ -98,12 +98,76 @@ fn filter_fmt() {
     assert_eq!(t.render().unwrap(), "\"formatted\"");
 }
 
+#[test]
+fn filter_pyformat() {
+    #[derive(Template)]
+    #[template(
+        source = r#"{{ "{} items"|pyformat(n) }}
+{{ "{:.2f}|{:>6.1f}|{:f}"|pyformat(price, price, 1.5) }}
+{{ "{1}-{0}-{1}"|pyformat(a, b) }}
+{{ "{:*^7}|{:<4}|{:04}|{:+}|{:#x}|{:X}|{:b}|{!r}"|pyformat(a, a, n, n, n, n, n, a) }}
+{{ "{{}} {}"|pyformat("<b>") }}"#,
+        ext = "html"
+    )]
+    struct PyFormat<'a> {
+        n: u32,
+        price: f64,
+        a: &'a str,
+        b: &'a str,
+    }
+
+    let t = PyFormat {
+        n: 42,
+        price: 4.56789,
+        a: "x",
+        b: "y",
+    };
+    assert_eq!(
+        t.render().unwrap(),
+        "42 items
+4.57|   4.6|1.500000
+y-x-y
+***x***|x   |0042|+42|0x2a|2A|101010|&#34;x&#34;
+{} &#60;b&#62;"
+    );
+}
+
+#[test]
+fn printf_operator() {
+    #[derive(Template)]
+    #[template(
+        source = r#"{{ "%s items" % n }}
+{{ "%s of %d: %.2f%%" % (a, n, price) }}
+{{ "[%5s][%-5s][%05d][%+d][%#x][%X][%o][%r][%.1s]" % (a, a, n, n, n, n, n, a, a) }}
+{{ "%f {}" % price }}"#,
+        ext = "txt"
+    )]
+    struct Printf<'a> {
+        n: u32,
+        price: f64,
+        a: &'a str,
+    }
+
+    let t = Printf {
+        n: 42,
+        price: 4.56789,
+        a: "xyz",
+    };
+    assert_eq!(
+        t.render().unwrap(),
+        r#"42 items
+xyz of 42: 4.57%
+[  xyz][xyz  ][00042][+42][0x2a][2A][52]["xyz"][x]
+4.567890 {}"#
+    );
+}
+
 mod filters {
     pub fn myfilter(s: &str) -> ::rinja::Result<String> {
         Ok(s.replace("oo", "aa"))
     }
     // for test_nested_filter_ref
-    pub fn mytrim(s: &dyn (::std::fmt::Display)) -> ::rinja::Result<String> {
+    pub fn mytrim(s: &dyn ::std::fmt::Display) -> ::rinja::Result<String> {
         Ok(s.to_string().trim().to_owned())
     }
 }
@@ -24,3 +24,91 @@ else
     assert_eq!(If { s: "c" }.render().unwrap(), "c");
     assert_eq!(If { s: "d" }.render().unwrap(), "else");
 }
+
+#[test]
+fn test_inline_if() {
+    #[derive(Template)]
+    #[template(
+        source = r#"<li class="{{ "active" if selected }}">{{ name if selected else "&" }}</li>"#,
+        ext = "html"
+    )]
+    struct Item<'a> {
+        name: &'a str,
+        selected: bool,
+    }
+
+    assert_eq!(
+        Item {
+            name: "<b>",
+            selected: true
+        }
+        .render()
+        .unwrap(),
+        r#"<li class="active">&#60;b&#62;</li>"#
+    );
+    assert_eq!(
+        Item {
+            name: "<b>",
+            selected: false
+        }
+        .render()
+        .unwrap(),
+        r#"<li class="">&#38;</li>"#
+    );
+}
+
+#[test]
+fn test_inline_if_chain() {
+    #[derive(Template)]
+    #[template(
+        source = r#"{{ "many" if count > 1 else "one" if count == 1 else "none" }}"#,
+        ext = "txt"
+    )]
+    struct Count {
+        count: usize,
+    }
+
+    assert_eq!(Count { count: 0 }.render().unwrap(), "none");
+    assert_eq!(Count { count: 1 }.render().unwrap(), "one");
+    assert_eq!(Count { count: 2 }.render().unwrap(), "many");
+}
+
+#[test]
+fn test_inline_if_let() {
+    #[derive(Template)]
+    #[template(
+        source = r#"{{ *n if let Some(n) = count else 0 }} {{ n if n is defined else "-" }}"#,
+        ext = "txt"
+    )]
+    struct Count {
+        count: Option<u32>,
+    }
+
+    assert_eq!(Count { count: Some(7) }.render().unwrap(), "7 -");
+    assert_eq!(Count { count: None }.render().unwrap(), "0 -");
+}
+
+#[test]
+fn test_inline_if_different_types() {
+    #[derive(Template)]
+    #[template(
+        source = r#"{{ n if c else "-" }} {{ x|safe if c else y }} {{ y if c else x|safe }}"#,
+        ext = "html"
+    )]
+    struct Mixed<'a> {
+        n: u32,
+        c: bool,
+        x: &'a str,
+        y: &'a str,
+    }
+
+    let mut t = Mixed {
+        n: 3,
+        c: true,
+        x: "<b>",
+        y: "<i>",
+    };
+    assert_eq!(t.render().unwrap(), "3 <b> &#60;i&#62;");
+    t.c = false;
+    assert_eq!(t.render().unwrap(), "- &#60;i&#62; <b>");
+}
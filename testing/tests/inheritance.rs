@@ -354,3 +354,23 @@ fn test_named_end() {
     let n = NamedBlocks { title: "title" };
     assert_eq!(n.render().unwrap(), "title\n\ntadam\nCopyright 2017");
 }
+
+// With `warn_unused = true`, this template would not compile with `-D warnings` if any macro
+// or block was reported as unused.
+#[test]
+fn test_warn_unused_all_used() {
+    #[derive(Template)]
+    #[template(
+        source = r#"{% extends "base.html" %}
+{%- macro greet(name) %}Hello, {{ name }}!{% endmacro -%}
+{%- block content %}{% call greet(title) %}{% block inner %}{% endblock %}{% endblock -%}"#,
+        ext = "html",
+        config = "warn-unused.toml"
+    )]
+    struct AllUsed<'a> {
+        title: &'a str,
+    }
+
+    let t = AllUsed { title: "Foo" };
+    assert_eq!(t.render().unwrap(), "Foo\nHello, Foo!\nFoo\nCopyright 2017");
+}
@@ -0,0 +1,94 @@
+use rinja::Template;
+
+#[test]
+fn test_jinja2_literals_and_operators() {
+    #[derive(Template)]
+    #[template(
+        source = r#"
+{%- if flag and not b or c is none -%}
+    {{ 'yes' ~ '|' ~ "it's" }}
+{%- endif -%}
+{%- if not a == 1 %}|{{ True }} {{ False }} {{ none == c }}{% endif -%}
+"#,
+        ext = "txt",
+        config = "jinja2.toml"
+    )]
+    struct Literals {
+        flag: bool,
+        a: u32,
+        b: bool,
+        c: Option<u32>,
+    }
+
+    let t = Literals {
+        flag: true,
+        a: 2,
+        b: false,
+        c: None,
+    };
+    assert_eq!(t.render().unwrap(), "yes|it's|true false true");
+    let t = Literals {
+        flag: false,
+        a: 0,
+        b: true,
+        c: Some(1),
+    };
+    assert_eq!(t.render().unwrap(), "|true false false");
+}
+
+#[test]
+fn test_jinja2_tests() {
+    #[derive(Template)]
+    #[template(
+        source = r#"
+{%- for n in 1..=max -%}
+    {{ n }}:
+    {%- if n is even %}even{% else %}odd{% endif -%}
+    {%- if n is divisibleby(3) %},fizz{% endif -%}
+    {%- if n is not odd and n is gt(2) %},big{% endif -%}
+    {%- if n is in([1, 5]) %},listed{% endif -%}
+    {%- if n is not in([1, 5]) and n is eq(loop.index) %},index{% endif -%}
+    {%- if loop.last %}.{% else %} {% endif -%}
+{%- endfor -%}
+{%- if value is not none and missing is not defined %} {{ value|unwrap_or(0) }}{% endif -%}
+"#,
+        ext = "txt",
+        config = "jinja2.toml"
+    )]
+    struct Tests {
+        max: usize,
+        value: Option<u32>,
+    }
+
+    mod filters {
+        pub fn unwrap_or(value: &Option<u32>, default: u32) -> rinja::Result<u32> {
+            Ok(value.unwrap_or(default))
+        }
+    }
+
+    let t = Tests {
+        max: 6,
+        value: Some(7),
+    };
+    assert_eq!(
+        t.render().unwrap(),
+        "1:odd,listed 2:even,index 3:odd,fizz,index 4:even,big,index 5:odd,listed \
+         6:even,fizz,big,index. 7",
+    );
+}
+
+#[test]
+fn test_jinja2_super() {
+    #[derive(Template)]
+    #[template(
+        source = r#"{% extends "base.html" %}{% block foo %}[{{ super() }}]{% endblock %}"#,
+        ext = "html",
+        config = "jinja2.toml"
+    )]
+    struct Super {
+        title: &'static str,
+    }
+
+    let t = Super { title: "title" };
+    assert!(t.render().unwrap().contains("[Foo]"));
+}
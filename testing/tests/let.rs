@@ -51,3 +51,145 @@ fn underscore_ident2() {
 
     assert_eq!(X.render().unwrap(), "hey\nhoy\nmatched");
 }
+
+// The body of a block assignment is rendered once, and can be used multiple times.
+#[test]
+fn let_block() {
+    #[derive(Template)]
+    #[template(
+        source = r#"{%- set crumbs -%}
+    {%- for item in items %}{% if !loop.first %} &gt; {% endif %}<a>{{ item }}</a>{% endfor -%}
+{%- endset -%}
+<header>{{ crumbs }}</header>
+<footer>{{ crumbs }}</footer>"#,
+        ext = "html"
+    )]
+    struct Breadcrumbs<'a> {
+        items: &'a [&'a str],
+    }
+
+    let t = Breadcrumbs {
+        items: &["Home", "<Docs>"],
+    };
+    assert_eq!(
+        t.render().unwrap(),
+        "<header><a>Home</a> &gt; <a>&#60;Docs&#62;</a></header>\n\
+         <footer><a>Home</a> &gt; <a>&#60;Docs&#62;</a></footer>"
+    );
+}
+
+// The filters of a block assignment are applied like in a `{% filter %}` block.
+#[test]
+fn let_block_filters() {
+    #[derive(Template)]
+    #[template(
+        source = r#"{% let x | lower | capitalize %}HELLO {{ name }}!{% endlet %}[{{ x }}]
+{%- set x | trim -%} {{ x }} and more {% endset %}[{{ x }}]"#,
+        ext = "txt"
+    )]
+    struct Filtered<'a> {
+        name: &'a str,
+    }
+
+    let t = Filtered { name: "WORLD" };
+    assert_eq!(t.render().unwrap(), "[Hello world!][Hello world! and more]");
+}
+
+// A block assignment can initialize a forward-declared variable, and a variable without a
+// matching `{% endset %}` is still a forward declaration.
+#[test]
+fn let_block_forward_declared() {
+    #[derive(Template)]
+    #[template(
+        source = r#"{%- set x -%}
+{%- if cond -%}
+    {%- set x %}yes{% endset -%}
+{%- else -%}
+    {%- set x %}no{% endset -%}
+{%- endif -%}
+{{ x }}"#,
+        ext = "html"
+    )]
+    struct Forward {
+        cond: bool,
+    }
+
+    assert_eq!(Forward { cond: true }.render().unwrap(), "yes");
+    assert_eq!(Forward { cond: false }.render().unwrap(), "no");
+}
+
+// A `mut` variable is updated in place, so its value survives the scope of a loop body.
+#[test]
+fn let_mut_accumulator() {
+    struct Item {
+        group: &'static str,
+        name: &'static str,
+        price: u32,
+    }
+
+    #[derive(Template)]
+    #[template(
+        source = r#"
+{%- let mut total = 0 -%}
+{%- set mut last_group = "" -%}
+{%- for item in items -%}
+    {%- if item.group != last_group -%}
+        [{{ item.group }}]
+        {%- set last_group = item.group -%}
+    {%- endif -%}
+    {{ item.name }};
+    {%- let total = total + item.price -%}
+{%- endfor -%}
+{{ total }}"#,
+        ext = "txt"
+    )]
+    struct Accumulator {
+        items: Vec<Item>,
+    }
+
+    let t = Accumulator {
+        items: vec![
+            Item {
+                group: "a",
+                name: "x",
+                price: 1,
+            },
+            Item {
+                group: "a",
+                name: "y",
+                price: 2,
+            },
+            Item {
+                group: "b",
+                name: "z",
+                price: 4,
+            },
+        ],
+    };
+    assert_eq!(t.render().unwrap(), "[a]x;y;[b]z;7");
+}
+
+// A block assignment can reassign a `mut` variable, and a forward-declared `mut` variable can
+// be reassigned more than once.
+#[test]
+fn let_mut_block() {
+    #[derive(Template)]
+    #[template(
+        source = r#"
+{%- set mut out -%}
+{%- set out %}-{% endset -%}
+{%- for word in words -%}
+    {%- set out %}{{ out }}{{ word }}-{% endset -%}
+{%- endfor -%}
+{{ out }}"#,
+        ext = "html"
+    )]
+    struct Joined<'a> {
+        words: &'a [&'a str],
+    }
+
+    let t = Joined {
+        words: &["a", "<b>"],
+    };
+    assert_eq!(t.render().unwrap(), "-a-&#60;b&#62;-");
+}
@@ -464,3 +464,75 @@ fn test_loop_locals() {
     let t = LoopLocalsContext { bla: 10 };
     assert_eq!(t.render().unwrap(), "10");
 }
+
+#[test]
+fn test_for_step_by() {
+    #[derive(Template)]
+    #[template(
+        source = r#"
+{%- for i in (1..=total).step_by(*per_page) -%}
+    [{{ i }}]
+{%- endfor -%}
+{%- for i in (0..10).rev().step_by(3) %} {{ i }}{% endfor -%}
+"#,
+        ext = "txt"
+    )]
+    struct ForStepBy {
+        total: usize,
+        per_page: usize,
+    }
+
+    let t = ForStepBy {
+        total: 25,
+        per_page: 10,
+    };
+    assert_eq!(t.render().unwrap(), "[1][11][21] 9 6 3 0");
+}
+
+#[test]
+fn test_while() {
+    use std::cell::{Cell, RefCell};
+
+    struct Pager {
+        pages: RefCell<std::ops::Range<u32>>,
+        skipped: Cell<u32>,
+    }
+
+    impl Pager {
+        fn next_page(&self) -> Option<u32> {
+            self.pages.borrow_mut().next()
+        }
+
+        fn skip(&self) -> bool {
+            self.skipped.set(self.skipped.get() + 1);
+            self.skipped.get() <= 2
+        }
+    }
+
+    #[derive(Template)]
+    #[template(
+        source = r#"
+{%- while pager.skip() -%}
+    -
+{%- endwhile -%}
+{%- while let Some(page) = pager.next_page() -%}
+    {%- if *page == 2 %}{% continue %}{% endif -%}
+    {%- if *page == 5 %}{% break %}{% endif -%}
+    [{{ page }}]
+{%- endwhile %}
+{% while false %}never{% endwhile -%}
+"#,
+        ext = "txt"
+    )]
+    struct While {
+        pager: Pager,
+    }
+
+    let t = While {
+        pager: Pager {
+            pages: RefCell::new(1..10),
+            skipped: Cell::new(0),
+        },
+    };
+    assert_eq!(t.render().unwrap(), "--[1][3][4]\n");
+}
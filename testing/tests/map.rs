@@ -0,0 +1,98 @@
+use rinja::Template;
+
+#[test]
+fn test_map_attr() {
+    #[derive(Template)]
+    #[template(
+        source = r#"
+{%- let user = {"name": name, "age": age + 1, "address": {"city": "Paris"}} -%}
+{{ user.name }} ({{ user.age }}) lives in {{ user.address.city }}.
+{{- ({"a": 1, "b": 2}).b -}}
+"#,
+        ext = "txt"
+    )]
+    struct MapAttr<'a> {
+        name: &'a str,
+        age: u32,
+    }
+
+    let t = MapAttr {
+        name: "Jane",
+        age: 41,
+    };
+    assert_eq!(t.render().unwrap(), "Jane (42) lives in Paris.2");
+}
+
+#[test]
+fn test_map_iter() {
+    #[derive(Template)]
+    #[template(
+        source = r#"
+{%- for (key, value) in {"a": 1, "b": name, "c": 'c'} -%}
+    {{ key }}={{ value }};
+{%- endfor -%}
+{%- let map = {"x": true} -%}
+{%- for (key, value) in map -%}
+    {{ key }}={{ value }};
+{%- endfor -%}
+{{- map.len() -}}
+"#,
+        ext = "txt"
+    )]
+    struct MapIter {
+        name: String,
+    }
+
+    let t = MapIter {
+        name: "<b>".to_owned(),
+    };
+    assert_eq!(t.render().unwrap(), "a=1;b=<b>;c=c;x=true;1");
+}
+
+#[test]
+fn test_map_macro_arg() {
+    #[derive(Template)]
+    #[template(
+        source = r#"
+{%- macro link(opts) -%}
+    <a href="{{ opts.href }}" class="{{ opts.class }}">{{ opts.label.text }}</a>
+{%- endmacro -%}
+{%- call link({"href": url, "class": "button", "label": {"text": "Go"}}) -%}
+{%- let options = {"href": "/", "class": "home", "label": {"text": "Home"}} -%}
+{%- call link(options) -%}
+"#,
+        ext = "html"
+    )]
+    struct MapMacroArg<'a> {
+        url: &'a str,
+    }
+
+    let t = MapMacroArg { url: "/a?b&c" };
+    assert_eq!(
+        t.render().unwrap(),
+        r#"<a href="/a?b&#38;c" class="button">Go</a><a href="/" class="home">Home</a>"#,
+    );
+}
+
+#[cfg(feature = "serde_json")]
+#[test]
+fn test_map_json() {
+    #[derive(Template)]
+    #[template(
+        source = r#"{{ {"id": id, "tags": tags, "extra": {"draft": false}, "empty": {}}|json }}"#,
+        ext = "txt"
+    )]
+    struct MapJson {
+        id: u32,
+        tags: Vec<&'static str>,
+    }
+
+    let t = MapJson {
+        id: 7,
+        tags: vec!["a", "b"],
+    };
+    assert_eq!(
+        t.render().unwrap(),
+        r#"{"id":7,"tags":["a","b"],"extra":{"draft":false},"empty":{}}"#,
+    );
+}
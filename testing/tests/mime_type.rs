@@ -0,0 +1,69 @@
+use rinja::Template;
+
+#[derive(Template)]
+#[template(path = "hello.html")]
+struct HtmlTemplate<'a> {
+    name: &'a str,
+}
+
+#[derive(Template)]
+#[template(path = "foo.html.jinja")]
+struct JinjaTemplate;
+
+#[derive(Template)]
+#[template(source = "{}", ext = "json", escape = "none")]
+struct JsonTemplate;
+
+#[derive(Template)]
+#[template(source = "", ext = "txt", escape = "html")]
+struct EscapedTemplate;
+
+#[derive(Template)]
+#[template(path = "hello.html", config = "mime-types.toml")]
+struct ConfiguredTemplate<'a> {
+    name: &'a str,
+}
+
+#[derive(Template)]
+#[template(ext = "html")]
+enum EnumTemplate {
+    #[template(source = "a")]
+    A,
+    #[template(source = "b")]
+    B,
+}
+
+// The variants disagree, so the defaults of `Template` are used.
+#[derive(Template)]
+enum MixedEnumTemplate {
+    #[template(source = "a", ext = "html")]
+    A,
+    #[template(source = "b", ext = "txt")]
+    B,
+}
+
+#[test]
+fn test_extension() {
+    assert_eq!(HtmlTemplate::EXTENSION, Some("html"));
+    assert_eq!(JinjaTemplate::EXTENSION, Some("html"));
+    assert_eq!(JsonTemplate::EXTENSION, Some("json"));
+    assert_eq!(EscapedTemplate::EXTENSION, Some("txt"));
+    assert_eq!(EnumTemplate::EXTENSION, Some("html"));
+    assert_eq!(MixedEnumTemplate::EXTENSION, None);
+}
+
+#[test]
+fn test_mime_type() {
+    assert_eq!(HtmlTemplate::MIME_TYPE, "text/html; charset=utf-8");
+    assert_eq!(JinjaTemplate::MIME_TYPE, "text/html; charset=utf-8");
+    assert_eq!(JsonTemplate::MIME_TYPE, "application/json");
+    assert_eq!(EscapedTemplate::MIME_TYPE, "text/html; charset=utf-8");
+    assert_eq!(ConfiguredTemplate::MIME_TYPE, "application/xhtml+xml");
+    assert_eq!(EnumTemplate::MIME_TYPE, "text/html; charset=utf-8");
+    assert_eq!(EnumTemplate::A.render().unwrap(), "a");
+    assert_eq!(EnumTemplate::B.render().unwrap(), "b");
+    assert_eq!(MixedEnumTemplate::MIME_TYPE, "text/plain; charset=utf-8");
+    assert_eq!(MixedEnumTemplate::A.render().unwrap(), "a");
+    assert_eq!(MixedEnumTemplate::B.render().unwrap(), "b");
+    assert_eq!(<&HtmlTemplate<'_>>::MIME_TYPE, "text/html; charset=utf-8");
+}
@@ -0,0 +1,180 @@
+use rinja::Template;
+
+#[test]
+fn test_minify_html() {
+    #[derive(Template)]
+    #[template(
+        source = r#"<!DOCTYPE html>
+<html>
+    <head>
+        <!-- the title of the page -->
+        <title>{{ title }}</title>
+    </head>
+    <body class="main">
+        <p   id="greeting"  >
+            Hello,   {{ name }}!
+        </p>
+        <a href="{{ url }}" title="  go  there  ">link</a>
+    </body>
+</html>"#,
+        ext = "html",
+        minify = "html"
+    )]
+    struct Page<'a> {
+        title: &'a str,
+        name: &'a str,
+        url: &'a str,
+    }
+
+    let page = Page {
+        title: "Welcome",
+        name: "world",
+        url: "/a  b",
+    };
+    assert_eq!(
+        page.render().unwrap(),
+        "<!DOCTYPE html><html><head><title>Welcome</title></head><body class=main>\
+        <p id=greeting>\nHello, world!\n</p>\n<a href=\"/a  b\" title=\"  go  there  \">link</a>\
+        \n</body></html>",
+    );
+}
+
+#[test]
+fn test_minify_html_keeps_raw_elements() {
+    #[derive(Template)]
+    #[template(
+        source = "<div>\n  <pre>\n  {{ a }}   {% if true %}  b  {% endif %}\n  </pre>\n  \
+            <textarea>  {{ a }}  </textarea>\n  <script>if (1  <  2) {}</script>\n</div>",
+        ext = "html",
+        minify = "html"
+    )]
+    struct Raw<'a> {
+        a: &'a str,
+    }
+
+    assert_eq!(
+        Raw { a: "a" }.render().unwrap(),
+        "<div><pre>\n  a     b  \n  </pre>\n<textarea>  a  </textarea>\
+        \n<script>if (1  <  2) {}</script></div>",
+    );
+}
+
+#[test]
+fn test_minify_from_config() {
+    #[derive(Template)]
+    #[template(
+        source = "<ul>\n  <li>{{ a }}</li>\n</ul>",
+        ext = "html",
+        config = "minify.toml"
+    )]
+    struct Configured<'a> {
+        a: &'a str,
+    }
+
+    #[derive(Template)]
+    #[template(
+        source = "<ul>\n  <li>{{ a }}</li>\n</ul>",
+        ext = "html",
+        config = "minify.toml",
+        minify = "none"
+    )]
+    struct OptedOut<'a> {
+        a: &'a str,
+    }
+
+    #[derive(Template)]
+    #[template(source = "a\n\n  {{ a }}", ext = "txt", config = "minify.toml")]
+    struct NotHtml<'a> {
+        a: &'a str,
+    }
+
+    assert_eq!(
+        Configured { a: "x" }.render().unwrap(),
+        "<ul><li>x</li></ul>"
+    );
+    assert_eq!(
+        OptedOut { a: "x" }.render().unwrap(),
+        "<ul>\n  <li>x</li>\n</ul>"
+    );
+    assert_eq!(NotHtml { a: "x" }.render().unwrap(), "a\n\n  x");
+}
+
+#[test]
+fn test_minify_html_inline_elements() {
+    #[derive(Template)]
+    #[template(
+        source = "<div>\n  <a>{{ a }}</a>\n  <a>{{ b }}</a>\n</div>",
+        ext = "html",
+        minify = "html"
+    )]
+    struct Links<'a> {
+        a: &'a str,
+        b: &'a str,
+    }
+
+    assert_eq!(
+        Links { a: "x", b: "y" }.render().unwrap(),
+        "<div>\n<a>x</a>\n<a>y</a>\n</div>",
+    );
+}
+
+#[test]
+fn test_minify_html_branches() {
+    // Each branch is minified on its own. The branches end in different states, so the rest of
+    // the template cannot be minified.
+    #[derive(Template)]
+    #[template(
+        source = "{% if pre %}<pre>{% else %}<p>   x   </p>{% endif %}  a   b  </pre>",
+        ext = "html",
+        minify = "html"
+    )]
+    struct Branches {
+        pre: bool,
+    }
+
+    assert_eq!(
+        Branches { pre: true }.render().unwrap(),
+        "<pre>  a   b  </pre>"
+    );
+    assert_eq!(
+        Branches { pre: false }.render().unwrap(),
+        "<p> x </p>  a   b  </pre>"
+    );
+}
+
+#[test]
+fn test_minify_html_branches_with_different_tags() {
+    // Only the last tag of the branches differs, so the text after them is minified.
+    #[derive(Template)]
+    #[template(
+        source = "{% if div %}<div>{% else %}<span>{% endif %}\n  a   b",
+        ext = "html",
+        minify = "html"
+    )]
+    struct Branches {
+        div: bool,
+    }
+
+    assert_eq!(Branches { div: true }.render().unwrap(), "<div>\na b");
+    assert_eq!(Branches { div: false }.render().unwrap(), "<span>\na b");
+}
+
+#[test]
+fn test_minify_html_loop_body() {
+    // From the second iteration on, the body starts inside the `<textarea>`, so it cannot be
+    // minified.
+    #[derive(Template)]
+    #[template(
+        source = "{% for x in xs %}{{ x }}  a  <textarea>{% endfor %}",
+        ext = "html",
+        minify = "html"
+    )]
+    struct Loop<'a> {
+        xs: &'a [u32],
+    }
+
+    assert_eq!(
+        Loop { xs: &[1, 2] }.render().unwrap(),
+        "1  a  <textarea>2  a  <textarea>"
+    );
+}
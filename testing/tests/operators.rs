@@ -61,3 +61,107 @@ fn test_short_circuit() {
     let t = ShortCircuitTemplate {};
     assert_eq!(t.render().unwrap(), "truetrue");
 }
+
+#[test]
+fn test_constant_folding() {
+    // the same expressions, once evaluated at compile time and once at runtime
+    #[derive(Template)]
+    #[template(
+        source = r#"{{ "<b>"|upper }} {{ 1 + 2 * 3 }} {{ -(7 % 4) }} {{ 1.5f32 / 4.0 }} {{ "a" ~ 'b' ~ 3 }}
+{{ " <i> "|trim|safe }} {{ "ÉCOLE"|lower|capitalize }} {{ 250u8 + 5 }}"#,
+        ext = "html"
+    )]
+    struct Folded;
+
+    #[derive(Template)]
+    #[template(
+        source = r#"{{ b|upper }} {{ one + 2 * 3 }} {{ -(7 % (one + 3)) }} {{ x / 4.0 }} {{ "a" ~ c ~ 3 }}
+{{ i|trim|safe }} {{ e|lower|capitalize }} {{ u + 5 }}"#,
+        ext = "html"
+    )]
+    struct Runtime<'a> {
+        b: &'a str,
+        one: i32,
+        x: f32,
+        c: char,
+        i: &'a str,
+        e: &'a str,
+        u: u8,
+    }
+
+    let expected = "&#60;B&#62; 7 -3 0.375 ab3\n<i> École 255";
+    assert_eq!(Folded.render().unwrap(), expected);
+    assert_eq!(Folded::RENDERED, Some(expected));
+    let runtime = Runtime {
+        b: "<b>",
+        one: 1,
+        x: 1.5,
+        c: 'b',
+        i: " <i> ",
+        e: "ÉCOLE",
+        u: 250,
+    };
+    assert_eq!(runtime.render().unwrap(), expected);
+}
+
+#[test]
+fn test_constant_conditions() {
+    #[derive(Template)]
+    #[template(
+        source = r#"{% if 1 + 1 == 2 && "a" < "b" %}yes{% else %}no{% endif %}
+{%- if 2 * 3 > 6 || 'x' != 'x' %}!{% endif %}"#,
+        ext = "txt"
+    )]
+    struct Conditions;
+
+    assert_eq!(Conditions.render().unwrap(), "yes");
+}
+
+#[test]
+fn test_in() {
+    use std::collections::{BTreeMap, HashSet};
+
+    #[derive(Template)]
+    #[template(
+        source = r#"
+{%- if role in ["admin", "owner"] %}staff{% else %}user{% endif -%}
+{{ " " ~ (role not in ["admin", "owner"]) -}}
+{{ " " ~ ("mi" in role) ~ " " ~ ('x' not in role) -}}
+{{ " " ~ (role in allowed) ~ " " ~ (role in names) -}}
+{{ " " ~ (age in (18..65)) ~ " " ~ (age not in (..=20)) -}}
+{% for tag in tags %} {{ tag in tags && "b" in tag }}{% endfor -%}
+"#,
+        ext = "txt"
+    )]
+    struct In<'a> {
+        role: &'a str,
+        allowed: HashSet<&'a str>,
+        names: BTreeMap<String, u32>,
+        age: u32,
+        tags: Vec<String>,
+    }
+
+    let t = In {
+        role: "admin",
+        allowed: ["admin"].into_iter().collect(),
+        names: [("guest".to_owned(), 1)].into_iter().collect(),
+        age: 42,
+        tags: vec!["a".to_owned(), "b".to_owned()],
+    };
+    assert_eq!(
+        t.render().unwrap(),
+        "staff false true true true false true true false true"
+    );
+
+    let t = In {
+        role: "guest",
+        allowed: HashSet::new(),
+        names: [("guest".to_owned(), 1)].into_iter().collect(),
+        age: 12,
+        tags: vec![],
+    };
+    assert_eq!(
+        t.render().unwrap(),
+        "user true false true false true false false"
+    );
+}
@@ -0,0 +1,54 @@
+use rinja::Template;
+
+/// Reads all files that were dumped for the template struct `name`.
+fn read_dumps(name: &str) -> Vec<String> {
+    let prefix = format!("{name}-");
+    let mut dumps = Vec::new();
+    for entry in std::fs::read_dir(concat!(env!("OUT_DIR"), "/rinja")).unwrap() {
+        let path = entry.unwrap().path();
+        let file_name = path.file_name().unwrap().to_str().unwrap();
+        if file_name.starts_with(&prefix) && file_name.ends_with(".rs") {
+            dumps.push(std::fs::read_to_string(&path).unwrap());
+        }
+    }
+    dumps
+}
+
+// The crate has a build script, so the output of `print` is written into `$OUT_DIR/rinja`.
+#[test]
+fn test_print_into_out_dir() {
+    #[derive(Template)]
+    #[template(source = "Hello, {{ name }}!", ext = "txt", print = "all")]
+    struct PrintedHello<'a> {
+        name: &'a str,
+    }
+
+    assert_eq!(PrintedHello { name: "world" }.to_string(), "Hello, world!");
+
+    let dumps = read_dumps("PrintedHello");
+    assert_eq!(dumps.len(), 1);
+    assert!(dumps[0].starts_with("// [\n"));
+    assert!(dumps[0].contains(r#"//         Var("name"),"#));
+    // the generated code is pretty-printed
+    assert!(dumps[0].contains("\n    fn render_into_with_values"));
+}
+
+// Types with the same name in different modules must not overwrite each other's dump.
+#[test]
+fn test_print_same_name() {
+    mod a {
+        #[derive(rinja::Template)]
+        #[template(source = "a", ext = "txt", print = "code")]
+        pub struct PrintedTwice;
+    }
+
+    mod b {
+        #[derive(rinja::Template)]
+        #[template(source = "b", ext = "txt", print = "code")]
+        pub struct PrintedTwice;
+    }
+
+    assert_eq!(a::PrintedTwice.to_string(), "a");
+    assert_eq!(b::PrintedTwice.to_string(), "b");
+    assert_eq!(read_dumps("PrintedTwice").len(), 2);
+}
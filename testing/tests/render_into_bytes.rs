@@ -0,0 +1,81 @@
+use rinja::Template;
+
+#[derive(Template)]
+#[template(
+    source = "<p>{{ text }}</p>{{ number }} {{ inner|safe }} {{ text|safe }}",
+    ext = "html"
+)]
+struct Outer<'a> {
+    text: &'a str,
+    number: i32,
+    inner: Inner<'a>,
+}
+
+#[derive(Template)]
+#[template(source = "<b>{{ self.0 }}</b>", ext = "html")]
+struct Inner<'a>(&'a str);
+
+#[derive(Template)]
+#[template(
+    source = "{% filter lower %}A{{ text }}{% endfilter %}|{{ text|upper }}",
+    ext = "html"
+)]
+struct Filtered<'a> {
+    text: &'a str,
+}
+
+#[derive(Template)]
+#[template(source = "{% block a %}<{{ text }}>{% endblock %}", ext = "html", blocks = ["a"])]
+struct WithBlock<'a> {
+    text: &'a str,
+}
+
+#[derive(Template)]
+#[template(ext = "html")]
+enum Enum<'a> {
+    #[template(source = "<i>{{ self.0 }}</i>")]
+    A(&'a str),
+}
+
+#[track_caller]
+fn check<T: Template>(tmpl: &T, expected: &str) {
+    assert_eq!(tmpl.render().unwrap(), expected);
+
+    let mut buf = Vec::new();
+    tmpl.render_into_bytes(&mut buf).unwrap();
+    assert_eq!(std::str::from_utf8(&buf).unwrap(), expected);
+}
+
+#[test]
+fn test_render_into_bytes() {
+    let tmpl = Outer {
+        text: "'grüße' & <ciao>",
+        number: -42,
+        inner: Inner("a\"b"),
+    };
+    check(
+        &tmpl,
+        "<p>&#39;grüße&#39; &#38; &#60;ciao&#62;</p>-42 <b>a&#34;b</b> 'grüße' & <ciao>",
+    );
+
+    check(&Filtered { text: "B" }, "ab|B");
+    check(&WithBlock { text: "&" }.as_a(), "<&#38;>");
+    check(&Enum::A("<>"), "<i>&#60;&#62;</i>");
+}
+
+#[test]
+fn test_fast_writable_into_bytes() {
+    use rinja::filters::{FastWritable, Html, escape};
+
+    let mut buf = Vec::new();
+    escape("<a & b>", Html)
+        .unwrap()
+        .write_bytes_into(&mut buf)
+        .unwrap();
+    123_u32.write_bytes_into(&mut buf).unwrap();
+    'ß'.write_bytes_into(&mut buf).unwrap();
+    assert_eq!(
+        std::str::from_utf8(&buf).unwrap(),
+        "&#60;a &#38; b&#62;123ß"
+    );
+}
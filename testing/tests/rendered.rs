@@ -0,0 +1,86 @@
+use rinja::Template;
+
+#[derive(Template)]
+#[template(source = "<p>Hello, world!</p>\n{#- a comment -#}", ext = "html")]
+struct ConstantTemplate;
+
+#[derive(Template)]
+#[template(
+    source = "{% raw %}{{ a }}{% endraw %} {{ \"b\" }} {{ 1 }}",
+    ext = "txt"
+)]
+struct FoldedTemplate;
+
+#[derive(Template)]
+#[template(source = "Hello, {{ name }}!", ext = "html")]
+struct NameTemplate<'a> {
+    name: &'a str,
+}
+
+#[derive(Template)]
+#[template(source = "{% if true %}a{% endif %}", ext = "txt")]
+struct IfTemplate;
+
+#[derive(Template)]
+#[template(path = "base.html")]
+struct BaseTemplate<'a> {
+    title: &'a str,
+}
+
+#[test]
+fn test_rendered() {
+    assert_eq!(ConstantTemplate::RENDERED, Some("<p>Hello, world!</p>"));
+    assert_eq!(
+        ConstantTemplate::RENDERED,
+        Some(ConstantTemplate.render().unwrap().as_str()),
+    );
+    assert_eq!(FoldedTemplate::RENDERED, Some("{{ a }} b 1"));
+    assert_eq!(FoldedTemplate.render().unwrap(), "{{ a }} b 1");
+}
+
+#[test]
+fn test_not_rendered() {
+    assert_eq!(NameTemplate::RENDERED, None);
+    assert_eq!(NameTemplate::RENDERED_GZIP, None);
+    assert_eq!(NameTemplate::RENDERED_BROTLI, None);
+    assert_eq!(IfTemplate::RENDERED, None);
+    assert_eq!(BaseTemplate::RENDERED, None);
+}
+
+#[cfg(feature = "gzip")]
+#[test]
+fn test_rendered_gzip() {
+    use std::io::Read;
+
+    let compressed = ConstantTemplate::RENDERED_GZIP.unwrap();
+    let mut decompressed = String::new();
+    flate2::read::GzDecoder::new(compressed)
+        .read_to_string(&mut decompressed)
+        .unwrap();
+    assert_eq!(Some(decompressed.as_str()), ConstantTemplate::RENDERED);
+}
+
+#[cfg(not(feature = "gzip"))]
+#[test]
+fn test_rendered_gzip() {
+    assert_eq!(ConstantTemplate::RENDERED_GZIP, None);
+}
+
+#[cfg(feature = "brotli")]
+#[test]
+fn test_rendered_brotli() {
+    use std::io::Read;
+
+    let compressed = ConstantTemplate::RENDERED_BROTLI.unwrap();
+    let mut decompressed = String::new();
+    brotli::Decompressor::new(compressed, 4096)
+        .read_to_string(&mut decompressed)
+        .unwrap();
+    assert_eq!(Some(decompressed.as_str()), ConstantTemplate::RENDERED);
+}
+
+#[cfg(not(feature = "brotli"))]
+#[test]
+fn test_rendered_brotli() {
+    assert_eq!(ConstantTemplate::RENDERED_BROTLI, None);
+}
@@ -46,3 +46,59 @@ fn test_super_size_hint() {
 
     assert_eq!(T::SIZE_HINT, 5);
 }
+
+#[test]
+fn test_adaptive_size_hint() {
+    #[derive(Template)]
+    #[template(source = "{{ text }}", ext = "txt")]
+    struct T<'a> {
+        text: &'a str,
+    }
+
+    let initial = T::adaptive_size_hint();
+    assert_eq!(initial, T::SIZE_HINT);
+
+    let long = "x".repeat(initial + 100);
+    T { text: &long }.render().unwrap();
+    assert_eq!(T::adaptive_size_hint(), long.len());
+
+    // shorter outputs only shrink the estimate slowly
+    T { text: "" }.render().unwrap();
+    let shrunk = T::adaptive_size_hint();
+    assert!(shrunk < long.len());
+    assert!(shrunk > long.len() / 2);
+}
+
+#[test]
+fn test_render_into_vec() {
+    #[derive(Template)]
+    #[template(source = "Hello, {{ name }}!", ext = "txt")]
+    struct Hello<'a> {
+        name: &'a str,
+    }
+
+    #[derive(Template)]
+    #[template(source = "a{{ fail()? }}", ext = "txt")]
+    struct Failing;
+
+    impl Failing {
+        fn fail(&self) -> Result<&'static str, rinja::Error> {
+            Err(rinja::Error::Fmt)
+        }
+    }
+
+    let mut buf = Vec::new();
+    Hello { name: "world" }.render_into_vec(&mut buf).unwrap();
+    assert_eq!(buf, b"Hello, world!");
+
+    // the output is appended, and the buffer can be reused
+    Hello { name: "ü" }.render_into_vec(&mut buf).unwrap();
+    assert_eq!(buf, "Hello, world!Hello, ü!".as_bytes());
+    buf.clear();
+    Hello { name: "again" }.render_into_vec(&mut buf).unwrap();
+    assert_eq!(buf, b"Hello, again!");
+
+    // a failed rendering leaves the buffer unchanged
+    assert!(Failing.render_into_vec(&mut buf).is_err());
+    assert_eq!(buf, b"Hello, again!");
+}
@@ -231,3 +231,101 @@ fn error_conversion_from_wrapped_in_io() {
     };
     assert!(err.is::<CustomError>());
 }
+
+#[test]
+fn error_location_of_try() {
+    #[derive(Template)]
+    #[template(
+        source = "{% let v = self.parse()? %}\n{{ s }}={{ v }}",
+        ext = "txt",
+        config = "runtime-error-locations.toml"
+    )]
+    struct IntParserTemplate<'a> {
+        s: &'a str,
+    }
+
+    impl IntParserTemplate<'_> {
+        fn parse(&self) -> Result<i32, std::num::ParseIntError> {
+            self.s.parse()
+        }
+    }
+
+    let err = IntParserTemplate { s: "💯" }.render().unwrap_err();
+    let location = err.location().unwrap();
+    assert_eq!(location.path(), "IntParserTemplate.txt");
+    assert_eq!((location.row(), location.column()), (1, 24));
+    assert_matches!(location.error(), rinja::Error::Custom(_));
+    assert_eq!(
+        err.to_string(),
+        "IntParserTemplate.txt:1:24: invalid digit found in string"
+    );
+    assert_matches!(err.without_location(), rinja::Error::Custom(_));
+
+    assert_eq!(
+        IntParserTemplate { s: "100" }.render().unwrap(),
+        "\n100=100"
+    );
+}
+
+#[test]
+fn error_location_of_display() {
+    struct Failing;
+
+    impl fmt::Display for Failing {
+        fn fmt(&self, _: &mut fmt::Formatter<'_>) -> fmt::Result {
+            Err(fmt::Error)
+        }
+    }
+
+    #[derive(Template)]
+    #[template(
+        source = "{{ a }}\n  {{ b }}",
+        ext = "txt",
+        config = "runtime-error-locations.toml"
+    )]
+    struct Outer<A: fmt::Display, B: fmt::Display> {
+        a: A,
+        b: B,
+    }
+
+    let err = Outer { a: "a", b: Failing }.render().unwrap_err();
+    let location = err.location().unwrap();
+    assert_eq!(location.path(), "Outer.txt");
+    assert_eq!((location.row(), location.column()), (2, 6));
+    assert_matches!(location.error(), rinja::Error::Fmt);
+
+    // The location of the innermost template wins.
+    #[derive(Template)]
+    #[template(
+        source = "{{ inner }}",
+        ext = "txt",
+        config = "runtime-error-locations.toml"
+    )]
+    struct Wrapper {
+        inner: Outer<&'static str, Failing>,
+    }
+
+    let inner = Outer { a: "a", b: Failing };
+    let err = Wrapper { inner }.render().unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Outer.txt:2:6: an error occurred when formatting an argument"
+    );
+}
+
+#[test]
+fn no_error_location_by_default() {
+    #[derive(Template)]
+    #[template(source = "{{ value()? }}", ext = "txt")]
+    struct ResultTemplate;
+
+    impl ResultTemplate {
+        fn value(&self) -> Result<&'static str, fmt::Error> {
+            Err(fmt::Error)
+        }
+    }
+
+    let err = ResultTemplate.render().unwrap_err();
+    assert!(err.location().is_none());
+    assert_matches!(err, rinja::Error::Fmt);
+}
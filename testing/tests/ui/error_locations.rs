@@ -0,0 +1,31 @@
+use rinja::Template;
+
+#[derive(Template)]
+#[template(
+    source = "Hello, {{ name.to_uppercase() }}!\n{{ name.nonexistent() }}",
+    ext = "txt",
+    config = "error-locations.toml"
+)]
+struct Hello<'a> {
+    name: &'a str,
+}
+
+#[derive(Template)]
+#[template(
+    source = "{% let x = count.len() %}{% match count.is_empty() %}{% when true %}{{ x }}{% else %}{% endmatch %}",
+    ext = "txt",
+    config = "error-locations.toml"
+)]
+struct LetAndMatch {
+    count: u32,
+}
+
+// The name of the macro contains the path of the template.
+#[derive(Template)]
+#[template(path = "error-locations/hello.txt", config = "error-locations.toml")]
+struct InDirectory<'a> {
+    name: &'a str,
+}
+
+fn main() {
+}
@@ -0,0 +1,22 @@
+use rinja::Template;
+
+#[derive(Template)]
+#[template(
+    source = r#"{% for v in values %}{{ loop.revindex }}{{ v }},{% endfor %}"#,
+    ext = "txt"
+)]
+struct ForRevindex<'a> {
+    values: &'a [u32],
+}
+
+#[derive(Template)]
+#[template(
+    source = r#"{% for v in values %}{{ loop.previtem }}{{ v }},{% endfor %}"#,
+    ext = "txt"
+)]
+struct ForPrevitem<'a> {
+    values: &'a [u32],
+}
+
+fn main() {
+}
@@ -0,0 +1,24 @@
+use rinja::Template;
+
+#[derive(Template)]
+#[template(
+    ext = "txt",
+    source = "
+{% if cond + %}
+    {{ a }}
+{% else %}
+    {{ b }}
+{% endif %}
+{% for x in %}
+    {% if x %}{% break %}{% endif %}
+{% endfor %}
+{% foo %}
+"
+)]
+struct ThreeErrors {
+    cond: bool,
+    a: u32,
+    b: u32,
+}
+
+fn main() {}
@@ -0,0 +1,52 @@
+use rinja::Template;
+
+#[derive(Template)]
+#[template(ext = "txt", source = r#"{{ "%s and %s" % n }}"#)]
+struct PrintfTooFewArgs {
+    n: u32,
+}
+
+#[derive(Template)]
+#[template(ext = "txt", source = r#"{{ "%s" % (n, n) }}"#)]
+struct PrintfTooManyArgs {
+    n: u32,
+}
+
+#[derive(Template)]
+#[template(ext = "txt", source = r#"{{ "%e" % n }}"#)]
+struct PrintfUnsupported {
+    n: u32,
+}
+
+#[derive(Template)]
+#[template(ext = "txt", source = r#"{{ "{} {}"|pyformat(n) }}"#)]
+struct PyFormatTooFewArgs {
+    n: u32,
+}
+
+#[derive(Template)]
+#[template(ext = "txt", source = r#"{{ "{0}"|pyformat(n, n) }}"#)]
+struct PyFormatUnusedArg {
+    n: u32,
+}
+
+#[derive(Template)]
+#[template(ext = "txt", source = r#"{{ "{} {0}"|pyformat(n) }}"#)]
+struct PyFormatMixedNumbering {
+    n: u32,
+}
+
+#[derive(Template)]
+#[template(ext = "txt", source = r#"{{ "{name}"|pyformat(n) }}"#)]
+struct PyFormatNamedField {
+    n: u32,
+}
+
+#[derive(Template)]
+#[template(ext = "txt", source = r#"{{ fmt|pyformat(n) }}"#)]
+struct PyFormatNotALiteral<'a> {
+    fmt: &'a str,
+    n: u32,
+}
+
+fn main() {}
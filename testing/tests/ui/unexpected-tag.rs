@@ -41,7 +41,7 @@ struct UnexpectedWhen;
 #[derive(Template)]
 #[template(in_doc = true, ext = "html")]
 /// ```rinja
-/// {% let var %}value{% endlet %}
+/// {% let var = 1 %}value{% endlet %}
 /// ```
 struct UnexpectedEndLet;
 
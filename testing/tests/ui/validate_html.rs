@@ -0,0 +1,52 @@
+use rinja::Template;
+
+#[derive(Template)]
+#[template(
+    source = "<div>\n  <span>{{ a }}</div>\n</span>",
+    ext = "html",
+    config = "validate-html.toml"
+)]
+struct Misnested<'a> {
+    a: &'a str,
+}
+
+#[derive(Template)]
+#[template(
+    source = "<section>\n  <div class=\"{{ a }}\">\n</section>",
+    ext = "html",
+    config = "validate-html.toml"
+)]
+struct Unclosed<'a> {
+    a: &'a str,
+}
+
+#[derive(Template)]
+#[template(
+    source = "{% if a %}<p>a</p></div>{% endif %}",
+    ext = "html",
+    config = "validate-html.toml"
+)]
+struct NoOpeningTag {
+    a: bool,
+}
+
+#[derive(Template)]
+#[template(
+    source = "<main>{{ a }}</main><article>",
+    ext = "html",
+    config = "validate-html.toml"
+)]
+struct NeverClosed<'a> {
+    a: &'a str,
+}
+
+#[derive(Template)]
+#[template(
+    source = "<div><a href=/index/>home</div>",
+    ext = "html",
+    config = "validate-html.toml"
+)]
+struct UnquotedSlash;
+
+fn main() {
+}
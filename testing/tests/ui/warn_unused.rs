@@ -0,0 +1,28 @@
+#![deny(deprecated)]
+
+use rinja::Template;
+
+#[derive(Template)]
+#[template(
+    source = r#"{%- macro used() %}used{% endmacro -%}
+{%- macro unused() %}unused{% endmacro -%}
+{% call used() %}"#,
+    ext = "txt",
+    config = "warn-unused.toml"
+)]
+struct UnusedMacro;
+
+#[derive(Template)]
+#[template(
+    source = r#"{% extends "base.html" %}
+{%- block content %}content{% endblock -%}
+{%- block sidebar %}sidebar{% endblock -%}"#,
+    ext = "html",
+    config = "warn-unused.toml"
+)]
+struct UnusedBlock<'a> {
+    title: &'a str,
+}
+
+fn main() {
+}
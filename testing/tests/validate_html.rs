@@ -0,0 +1,132 @@
+use rinja::Template;
+
+// The templates in this file are only checked for being accepted by the HTML validation.
+
+#[test]
+fn test_balanced_html() {
+    #[derive(Template)]
+    #[template(
+        source = r#"<!DOCTYPE html>
+<html>
+<head><meta charset="utf-8"><title>{{ title }}</title></head>
+<body>
+    <!-- </div> in a comment is ignored -->
+    <div class="{{ class }}" data-x='<b>'>
+        <img src="a.png" alt="">
+        <br/>
+        <svg><path d="M0 0"/></svg>
+        <ul>
+            <li>one
+            <li>two
+        </ul>
+        <p>paragraph
+        <p>paragraph
+    </div>
+    <script>if (a < b && "</div>") {}</script>
+</body>
+</html>"#,
+        ext = "html",
+        config = "validate-html.toml"
+    )]
+    struct Page<'a> {
+        title: &'a str,
+        class: &'a str,
+    }
+
+    let page = Page {
+        title: "x",
+        class: "y",
+    };
+    assert!(page.render().unwrap().contains("<li>two"));
+}
+
+#[test]
+fn test_balanced_control_flow() {
+    #[derive(Template)]
+    #[template(
+        source = r#"
+{%- if wide -%}<div class="wide">{%- else -%}<div>{%- endif -%}
+    {%- for item in items -%}<span>{{ item }}</span>{%- endfor -%}
+    {%- match wide -%}
+        {%- when true -%}<b>wide</b>
+        {%- when false -%}<i>narrow</i>
+    {%- endmatch -%}
+</div>"#,
+        ext = "html",
+        config = "validate-html.toml"
+    )]
+    struct Flow<'a> {
+        wide: bool,
+        items: &'a [&'a str],
+    }
+
+    let flow = Flow {
+        wide: true,
+        items: &["a"],
+    };
+    assert_eq!(
+        flow.render().unwrap(),
+        r#"<div class="wide"><span>a</span><b>wide</b></div>"#,
+    );
+}
+
+#[test]
+fn test_unbalanced_branches_are_skipped() {
+    // The validation cannot follow the structure, so it accepts the template.
+    #[derive(Template)]
+    #[template(
+        source = "{% if link %}<a href=\"/\">{% endif %}text{% if link %}</a>{% endif %}",
+        ext = "html",
+        config = "validate-html.toml"
+    )]
+    struct Skipped {
+        link: bool,
+    }
+
+    assert_eq!(
+        Skipped { link: true }.render().unwrap(),
+        "<a href=\"/\">text</a>"
+    );
+}
+
+#[test]
+fn test_self_closing_tags() {
+    // The `/` of an unquoted attribute value does not close the element.
+    #[derive(Template)]
+    #[template(
+        source = r##"<p><a href=/x/>x</a><br /><svg><use href="#a" /><path d=M0/></path></svg></p>"##,
+        ext = "html",
+        config = "validate-html.toml"
+    )]
+    struct SelfClosing;
+
+    assert!(SelfClosing.render().unwrap().starts_with("<p><a href=/x/>"));
+}
+
+#[test]
+fn test_include_is_skipped() {
+    // The included template opens the `<main>` element, which is closed in here.
+    #[derive(Template)]
+    #[template(
+        source = "{% include \"validate-html-main.html\" %}text</main>",
+        ext = "html",
+        config = "validate-html.toml"
+    )]
+    struct Included;
+
+    assert_eq!(Included.render().unwrap(), "<main>text</main>");
+}
+
+#[test]
+fn test_not_validated_by_default() {
+    #[derive(Template)]
+    #[template(source = "<div></span>", ext = "html")]
+    struct Broken;
+
+    #[derive(Template)]
+    #[template(source = "<div>", ext = "txt", config = "validate-html.toml")]
+    struct NotHtml;
+
+    assert_eq!(Broken.render().unwrap(), "<div></span>");
+    assert_eq!(NotHtml.render().unwrap(), "<div>");
+}
@@ -0,0 +1,262 @@
+// The tests in this file are only run if the matching "with-*" feature is enabled, e.g.
+// `cargo test --test web_frameworks --features with-axum`.
+#![allow(dead_code)]
+
+use std::fmt;
+
+use rinja::Template;
+
+#[derive(Template)]
+#[template(source = "<p>Hello, {{ name }}!</p>", ext = "html")]
+struct Hello<'a> {
+    name: &'a str,
+}
+
+#[derive(Template)]
+#[template(source = "{{ fail }}", ext = "txt")]
+struct Failing {
+    fail: Fail,
+}
+
+struct Fail;
+
+impl fmt::Display for Fail {
+    fn fmt(&self, _: &mut fmt::Formatter<'_>) -> fmt::Result {
+        Err(fmt::Error)
+    }
+}
+
+/// Each variant is served with its own `Content-Type`
+#[derive(Template)]
+enum Mixed {
+    #[template(source = "<p>html</p>", ext = "html")]
+    Html,
+    #[template(source = "text", ext = "txt")]
+    Text,
+}
+
+const HTML: &str = "text/html; charset=utf-8";
+const TEXT: &str = "text/plain; charset=utf-8";
+
+#[cfg(any(
+    feature = "with-axum",
+    feature = "with-poem",
+    feature = "with-rocket",
+    feature = "with-warp"
+))]
+fn block_on<F: std::future::Future>(future: F) -> F::Output {
+    tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .unwrap()
+        .block_on(future)
+}
+
+#[cfg(feature = "with-actix-web")]
+mod actix_web {
+    use ::actix_web::Responder;
+    use ::actix_web::body::MessageBody;
+    use ::actix_web::http::StatusCode;
+    use ::actix_web::http::header::CONTENT_TYPE;
+    use ::actix_web::test::TestRequest;
+
+    use super::*;
+
+    fn respond(tmpl: impl Responder) -> (StatusCode, Option<String>, String) {
+        let req = TestRequest::default().to_http_request();
+        let res = tmpl.respond_to(&req).map_into_boxed_body();
+        let content_type = res.headers().get(CONTENT_TYPE);
+        let content_type = content_type.map(|v| v.to_str().unwrap().to_owned());
+        let status = res.status();
+        let body = res.into_body().try_into_bytes().unwrap();
+        (
+            status,
+            content_type,
+            String::from_utf8(body.to_vec()).unwrap(),
+        )
+    }
+
+    #[test]
+    fn test_responder() {
+        let (status, content_type, body) = respond(Hello { name: "world" });
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(content_type.as_deref(), Some(HTML));
+        assert_eq!(body, "<p>Hello, world!</p>");
+
+        assert_eq!(respond(Mixed::Html).1.as_deref(), Some(HTML));
+        assert_eq!(respond(Mixed::Text).1.as_deref(), Some(TEXT));
+
+        let (status, ..) = respond(Failing { fail: Fail });
+        assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+    }
+}
+
+#[cfg(feature = "with-axum")]
+mod axum {
+    use ::axum_core::response::IntoResponse;
+    use ::http_body_util::BodyExt;
+
+    use super::*;
+
+    fn respond(tmpl: impl IntoResponse) -> (u16, Option<String>, String) {
+        let res = tmpl.into_response();
+        let content_type = res.headers().get("content-type");
+        let content_type = content_type.map(|v| v.to_str().unwrap().to_owned());
+        let status = res.status().as_u16();
+        let body = block_on(res.into_body().collect()).unwrap().to_bytes();
+        (
+            status,
+            content_type,
+            String::from_utf8(body.to_vec()).unwrap(),
+        )
+    }
+
+    #[test]
+    fn test_into_response() {
+        let (status, content_type, body) = respond(Hello { name: "world" });
+        assert_eq!(status, 200);
+        assert_eq!(content_type.as_deref(), Some(HTML));
+        assert_eq!(body, "<p>Hello, world!</p>");
+
+        assert_eq!(respond(Mixed::Html).1.as_deref(), Some(HTML));
+        assert_eq!(respond(Mixed::Text).1.as_deref(), Some(TEXT));
+
+        let (status, ..) = respond(Failing { fail: Fail });
+        assert_eq!(status, 500);
+    }
+}
+
+#[cfg(feature = "with-poem")]
+mod poem {
+    use ::poem::IntoResponse;
+
+    use super::*;
+
+    fn respond(tmpl: impl IntoResponse) -> (u16, Option<String>, String) {
+        let res = tmpl.into_response();
+        let content_type = res.content_type().map(ToOwned::to_owned);
+        let status = res.status().as_u16();
+        let body = block_on(res.into_body().into_string()).unwrap();
+        (status, content_type, body)
+    }
+
+    #[test]
+    fn test_into_response() {
+        let (status, content_type, body) = respond(Hello { name: "world" });
+        assert_eq!(status, 200);
+        assert_eq!(content_type.as_deref(), Some(HTML));
+        assert_eq!(body, "<p>Hello, world!</p>");
+
+        assert_eq!(respond(Mixed::Html).1.as_deref(), Some(HTML));
+        assert_eq!(respond(Mixed::Text).1.as_deref(), Some(TEXT));
+
+        let (status, ..) = respond(Failing { fail: Fail });
+        assert_eq!(status, 500);
+    }
+}
+
+#[cfg(feature = "with-rocket")]
+mod rocket {
+    use ::rocket::http::Status;
+    use ::rocket::local::asynchronous::Client;
+    use ::rocket::response::Responder;
+
+    use super::*;
+
+    fn respond<T>(tmpl: T) -> Result<(Option<String>, String), Status>
+    where
+        T: for<'r> Responder<'r, 'static>,
+    {
+        block_on(async {
+            let client = Client::untracked(::rocket::build()).await.unwrap();
+            let req = client.get("/");
+            let mut res = tmpl.respond_to(&req)?;
+            let content_type = res.content_type().map(|v| v.to_string());
+            let body = res.body_mut().to_string().await.unwrap();
+            Ok((content_type, body))
+        })
+    }
+
+    #[test]
+    fn test_responder() {
+        let (content_type, body) = respond(Hello { name: "world" }).unwrap();
+        assert_eq!(content_type.as_deref(), Some(HTML));
+        assert_eq!(body, "<p>Hello, world!</p>");
+
+        assert_eq!(respond(Mixed::Html).unwrap().0.as_deref(), Some(HTML));
+        assert_eq!(respond(Mixed::Text).unwrap().0.as_deref(), Some(TEXT));
+
+        let status = respond(Failing { fail: Fail }).unwrap_err();
+        assert_eq!(status, Status::InternalServerError);
+    }
+}
+
+#[cfg(feature = "with-salvo")]
+mod salvo {
+    use ::salvo_core::http::ResBody;
+    use ::salvo_core::http::header::CONTENT_TYPE;
+    use ::salvo_core::{Response, Scribe};
+
+    use super::*;
+
+    fn respond(tmpl: impl Scribe) -> (Option<u16>, Option<String>, String) {
+        let mut res = Response::new();
+        tmpl.render(&mut res);
+        let content_type = res.headers().get(CONTENT_TYPE);
+        let content_type = content_type.map(|v| v.to_str().unwrap().to_owned());
+        let status = res.status_code.map(|s| s.as_u16());
+        let body = match res.take_body() {
+            ResBody::Once(bytes) => String::from_utf8(bytes.to_vec()).unwrap(),
+            _ => String::new(),
+        };
+        (status, content_type, body)
+    }
+
+    #[test]
+    fn test_scribe() {
+        let (status, content_type, body) = respond(Hello { name: "world" });
+        assert_eq!(status, None);
+        assert_eq!(content_type.as_deref(), Some(HTML));
+        assert_eq!(body, "<p>Hello, world!</p>");
+
+        assert_eq!(respond(Mixed::Html).1.as_deref(), Some(HTML));
+        assert_eq!(respond(Mixed::Text).1.as_deref(), Some(TEXT));
+
+        let (status, ..) = respond(Failing { fail: Fail });
+        assert_eq!(status, Some(500));
+    }
+}
+
+#[cfg(feature = "with-warp")]
+mod warp {
+    use ::warp::Reply;
+
+    use super::*;
+
+    fn respond(tmpl: impl Reply) -> (u16, Option<String>, String) {
+        let res = tmpl.into_response();
+        let content_type = res.headers().get("content-type");
+        let content_type = content_type.map(|v| v.to_str().unwrap().to_owned());
+        let status = res.status().as_u16();
+        let body = block_on(::warp::hyper::body::to_bytes(res.into_body())).unwrap();
+        (
+            status,
+            content_type,
+            String::from_utf8(body.to_vec()).unwrap(),
+        )
+    }
+
+    #[test]
+    fn test_reply() {
+        let (status, content_type, body) = respond(Hello { name: "world" });
+        assert_eq!(status, 200);
+        assert_eq!(content_type.as_deref(), Some(HTML));
+        assert_eq!(body, "<p>Hello, world!</p>");
+
+        assert_eq!(respond(Mixed::Html).1.as_deref(), Some(HTML));
+        assert_eq!(respond(Mixed::Text).1.as_deref(), Some(TEXT));
+
+        let (status, ..) = respond(Failing { fail: Fail });
+        assert_eq!(status, 500);
+    }
+}
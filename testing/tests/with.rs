@@ -0,0 +1,56 @@
+use rinja::Template;
+
+struct Page {
+    title: String,
+}
+
+#[test]
+fn test_with_include() {
+    #[derive(Template)]
+    #[template(
+        source = r#"
+{%- with title = page.title, user = current -%}
+    {% include "with-card.html" %}
+{%- endwith %}
+{{ title }}"#,
+        ext = "html"
+    )]
+    struct WithInclude<'a> {
+        page: Page,
+        current: &'a str,
+        title: &'a str,
+    }
+
+    let t = WithInclude {
+        page: Page {
+            title: "Hello & welcome".to_owned(),
+        },
+        current: "Ferris",
+        title: "outer",
+    };
+    assert_eq!(
+        t.render().unwrap(),
+        "<div class=\"card\"><h2>Hello &#38; welcome</h2>by Ferris</div>\nouter",
+    );
+}
+
+#[test]
+fn test_with_scoping() {
+    #[derive(Template)]
+    #[template(
+        source = r#"
+{%- let x = 1 -%}
+{%- with x = x + 1, y = x * 10, (a, b) = pair, -%}
+    {{ x }} {{ y }} {{ a }}{{ b }}
+    {%- with x = "inner" %} {{ x }}{% endwith -%}
+    {%- let z = 5 %} {{ z }}
+{%- endwith %} {% if z is defined %}{{ z }}{% endif %}{{ x }}"#,
+        ext = "txt"
+    )]
+    struct WithScoping {
+        pair: (char, char),
+    }
+
+    let t = WithScoping { pair: ('a', 'b') };
+    assert_eq!(t.render().unwrap(), "2 20 ab inner 5 1");
+}